@@ -0,0 +1,88 @@
+//! A single-file JSON artifact bundling CTL metadata with the certificates it refers to.
+
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use windows_ctl::{store::StoreWriter, CtlError, TrustedSubject};
+use x509_cert::{
+    der::{Decode, Encode},
+    Certificate,
+};
+
+use crate::{
+    fetcher::Http,
+    intermediates::{self, Recorder},
+    schema::{self, Document},
+};
+
+/// Writes each entry's metadata, along with its base64-encoded DER certificate,
+/// as a single JSON document.
+pub struct JsonWriter<W: Write> {
+    sink: W,
+    entries: Vec<Value>,
+    intermediates: Option<(Http, Recorder)>,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink,
+            entries: vec![],
+            intermediates: None,
+        }
+    }
+
+    /// Additionally chases the certificates' AIA issuers when finishing, and lists
+    /// them (as base64-encoded DER) under `intermediates`.
+    pub fn with_intermediates(mut self, http: Http) -> Self {
+        self.intermediates = Some((http, Recorder::default()));
+        self
+    }
+}
+
+impl<W: Write> StoreWriter for JsonWriter<W> {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        let mut value = serde_json::to_value(entry).map_err(std::io::Error::from)?;
+        value["certificate"] = json!(STANDARD.encode(cert.to_der()?));
+
+        if let Some((_, recorder)) = &mut self.intermediates {
+            recorder.write(entry, cert)?;
+        }
+        self.entries.push(value);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), CtlError> {
+        let mut document = json!({ "entries": self.entries });
+        if let Some((http, recorder)) = &self.intermediates {
+            document["intermediates"] = intermediates::chase(http, &recorder.certs)
+                .iter()
+                .map(|cert| Ok(json!(STANDARD.encode(cert.to_der()?))))
+                .collect::<Result<_, CtlError>>()?;
+        }
+
+        serde_json::to_writer_pretty(&mut self.sink, &schema::versioned(document))
+            .map_err(std::io::Error::from)?;
+        writeln!(self.sink)?;
+        Ok(self.sink.flush()?)
+    }
+}
+
+/// Reads the certificates back out of a bundle written by [`JsonWriter`].
+pub fn read_bundle(contents: &[u8]) -> Result<Vec<Certificate>> {
+    let bundle = schema::upgrade(Document::Bundle, serde_json::from_slice(contents)?)?;
+
+    bundle["entries"]
+        .as_array()
+        .ok_or_else(|| anyhow!("malformed bundle: missing entries"))?
+        .iter()
+        .map(|entry| {
+            let encoded = entry["certificate"]
+                .as_str()
+                .ok_or_else(|| anyhow!("malformed bundle: entry without a certificate"))?;
+            Ok(Certificate::from_der(&STANDARD.decode(encoded)?)?)
+        })
+        .collect()
+}
@@ -0,0 +1,122 @@
+//! Cross-checking CTL roots against certificate transparency (CT) observations.
+//!
+//! CT logs' own APIs (RFC 6962) can't be searched by issuer, so rather than querying
+//! logs directly, roots are looked up in an offline dataset (e.g. exported from a CT
+//! aggregator). The dataset is a JSON object mapping root fingerprints to what has
+//! been observed in CT for certificates chaining to them:
+//!
+//! ```json
+//! {
+//!   "cabd2a79a1076a31f21d253635cb039d4329a5e8": { "certificates": 1234, "last_seen": "2024-05-01T00:00:00Z" },
+//!   "96bcec06264976f37460779acf28c5a7cfe8a3c0aae11a8ffcee05c0bddf08c6": { "certificates": 0 }
+//! }
+//! ```
+//!
+//! Fingerprints may be SHA-1 or SHA-256, in any format that identifiers are accepted
+//! in. Entries are matched by their identifier, or by their SHA-256 fingerprint
+//! attribute (if they have one).
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use windows_ctl::{SubjectId, TrustedSubject};
+
+/// What's been observed in CT for the certificates chaining to a root.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Observation {
+    /// The number of certificates observed.
+    pub certificates: u64,
+    /// When a certificate was last observed, if known.
+    #[serde(default)]
+    pub last_seen: Option<String>,
+}
+
+/// An offline dataset of CT observations, keyed by root fingerprint.
+pub struct CtDataset(HashMap<SubjectId, Observation>);
+
+impl CtDataset {
+    /// Parses a dataset (see the module documentation for its format).
+    pub fn from_slice(contents: &[u8]) -> Result<Self> {
+        Ok(Self(
+            serde_json::from_slice(contents).context("invalid CT dataset")?,
+        ))
+    }
+
+    /// Returns what's been observed for `entry`'s root, or `None` if the dataset
+    /// doesn't cover it.
+    pub fn lookup(&self, entry: &TrustedSubject) -> Result<Option<&Observation>> {
+        if let Some(observation) = self.0.get(&entry.cert_id()) {
+            return Ok(Some(observation));
+        }
+        Ok(entry
+            .sha256_fingerprint()?
+            .and_then(|sha256| self.0.get(&SubjectId::from(sha256.to_vec()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows_ctl::MS_CERT_PROP_ID_SHA256_HASH_OID;
+    use x509_cert::{
+        attr::Attribute,
+        der::{
+            asn1::{Any, OctetString, SetOfVec},
+            Decode, Encode, Tag,
+        },
+    };
+
+    use super::*;
+
+    /// A `TrustedSubject` with the given identifier and (optionally) SHA-256 fingerprint.
+    fn entry(id: &[u8], sha256: Option<[u8; 32]>) -> TrustedSubject {
+        let mut contents = OctetString::new(id).unwrap().to_der().unwrap();
+        if let Some(sha256) = sha256 {
+            let attr = Attribute {
+                oid: MS_CERT_PROP_ID_SHA256_HASH_OID,
+                values: SetOfVec::try_from(vec![Any::new(Tag::OctetString, sha256).unwrap()])
+                    .unwrap(),
+            };
+            contents.extend(SetOfVec::try_from(vec![attr]).unwrap().to_der().unwrap());
+        }
+
+        let mut der = vec![0x30, contents.len() as u8];
+        der.extend(contents);
+        TrustedSubject::from_der(&der).unwrap()
+    }
+
+    #[test]
+    fn test_ct_dataset() {
+        let dataset = CtDataset::from_slice(
+            format!(
+                r#"{{
+                    "{}": {{ "certificates": 5, "last_seen": "2024-05-01T00:00:00Z" }},
+                    "{}": {{ "certificates": 0 }}
+                }}"#,
+                "11".repeat(20),
+                "AA:".repeat(31) + "AA"
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            dataset.lookup(&entry(&[0x11; 20], None)).unwrap(),
+            Some(&Observation {
+                certificates: 5,
+                last_seen: Some("2024-05-01T00:00:00Z".into()),
+            })
+        );
+        assert_eq!(
+            dataset
+                .lookup(&entry(&[0x22; 20], Some([0xaa; 32])))
+                .unwrap()
+                .map(|o| o.certificates),
+            Some(0)
+        );
+        assert_eq!(dataset.lookup(&entry(&[0x33; 20], None)).unwrap(), None);
+
+        assert!(CtDataset::from_slice(br#"{ "xyz": { "certificates": 1 } }"#).is_err());
+        assert!(CtDataset::from_slice(br#"{ "abcd": 1 }"#).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Comparing two generations of a list.
+//!
+//! Entries are matched by identifier, which on its own only says which certificates
+//! were added or removed. Given a cache of the certificates themselves, removed and
+//! added entries are also joined by subject name, so that a root that was renewed (or
+//! whose key was rolled over) shows up as such, rather than as an unrelated removal
+//! and addition.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    io::Write,
+};
+
+use anyhow::Result;
+use windows_ctl::{
+    oids,
+    store::{CertificateCache, CertificateFetcher, TrustStore},
+    CertificateTrustList, CtlError, SubjectId, TrustedSubject,
+};
+use x509_cert::Certificate;
+
+/// The differences between two generations of a list.
+#[derive(Debug, Default)]
+pub struct ListDiff<'a> {
+    /// Entries only in the newer list (that don't replace a removed one).
+    pub added: Vec<&'a TrustedSubject>,
+    /// Entries only in the older list (that weren't replaced by an added one).
+    pub removed: Vec<&'a TrustedSubject>,
+    /// Entries in both lists, with a description of each property that differs.
+    pub changed: Vec<(&'a TrustedSubject, BTreeSet<String>)>,
+    /// Removed entries whose certificates were replaced by an added entry's.
+    pub replaced: Vec<Replacement<'a>>,
+    /// The number of added or removed entries whose certificates weren't available.
+    pub unresolved: usize,
+}
+
+/// A certificate that was replaced by another with the same subject.
+#[derive(Debug)]
+pub struct Replacement<'a> {
+    /// The older list's entry.
+    pub old: &'a TrustedSubject,
+    /// The newer list's entry.
+    pub new: &'a TrustedSubject,
+    /// The certificates' (shared) subject.
+    pub subject: String,
+    /// Whether the certificates have the same public key, i.e. whether the root was
+    /// renewed rather than rekeyed.
+    pub same_key: bool,
+}
+
+/// A fetcher for when only cached certificates should be used.
+struct CacheOnly;
+
+impl CertificateFetcher for CacheOnly {
+    fn fetch(&mut self, _entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+        Ok(None)
+    }
+}
+
+/// Compares the entries of `old` and `new`, also comparing the certificates of added
+/// and removed entries if a `cache` of them is given.
+pub fn diff<'a>(
+    old: &'a CertificateTrustList,
+    new: &'a CertificateTrustList,
+    cache: Option<&mut dyn CertificateCache>,
+) -> Result<ListDiff<'a>> {
+    let old_entries = entries_by_id(old);
+    let new_entries = entries_by_id(new);
+    let mut diff = ListDiff::default();
+
+    for entry in new.trusted_subjects.iter().flatten() {
+        match old_entries.get(&entry.cert_id()) {
+            None => diff.added.push(entry),
+            Some(old_entry) if *old_entry != entry => diff
+                .changed
+                .push((entry, changed_properties(old_entry, entry))),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .trusted_subjects
+        .iter()
+        .flatten()
+        .filter(|entry| !new_entries.contains_key(&entry.cert_id()))
+        .collect();
+
+    if let Some(cache) = cache {
+        diff.join_certificates(old, new, cache)?;
+    }
+    Ok(diff)
+}
+
+impl<'a> ListDiff<'a> {
+    /// Pairs up removed and added entries whose certificates have the same subject.
+    fn join_certificates(
+        &mut self,
+        old: &'a CertificateTrustList,
+        new: &'a CertificateTrustList,
+        cache: &mut dyn CertificateCache,
+    ) -> Result<()> {
+        let mut resolve = |ctl, entries: &[&'a TrustedSubject]| -> Result<Vec<_>> {
+            let store = TrustStore::new(ctl);
+            let mut resolved = vec![];
+            for entry in entries {
+                match store.resolve(entry, &mut CacheOnly, &mut *cache)? {
+                    Some(cert) => resolved.push((*entry, cert)),
+                    None => self.unresolved += 1,
+                }
+            }
+            Ok(resolved)
+        };
+        let removed = resolve(old, &self.removed)?;
+        let mut added = resolve(new, &self.added)?;
+
+        for (old_entry, old_cert) in removed {
+            let subject = &old_cert.tbs_certificate.subject;
+            let Some(i) = added
+                .iter()
+                .position(|(_, cert)| cert.tbs_certificate.subject == *subject)
+            else {
+                continue;
+            };
+            let (new_entry, new_cert) = added.remove(i);
+
+            self.removed.retain(|entry| *entry != old_entry);
+            self.added.retain(|entry| *entry != new_entry);
+            self.replaced.push(Replacement {
+                old: old_entry,
+                new: new_entry,
+                subject: subject.to_string(),
+                same_key: same_key(&old_cert, &new_cert),
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes a line per difference.
+    pub fn report(&self, mut sink: impl Write) -> Result<()> {
+        for entry in &self.added {
+            writeln!(sink, "added:   {}", Label(entry))?;
+        }
+        for entry in &self.removed {
+            writeln!(sink, "removed: {}", Label(entry))?;
+        }
+        for (entry, properties) in &self.changed {
+            let properties = properties.iter().cloned().collect::<Vec<_>>();
+            writeln!(sink, "changed: {}: {}", Label(entry), properties.join(", "))?;
+        }
+        for replacement in &self.replaced {
+            writeln!(
+                sink,
+                "{}: {} -> {}: {}",
+                if replacement.same_key {
+                    "renewed"
+                } else {
+                    "rekeyed"
+                },
+                replacement.old.cert_id(),
+                Label(replacement.new),
+                replacement.subject
+            )?;
+        }
+        Ok(sink.flush()?)
+    }
+}
+
+/// Displays an entry's identifier, followed by its friendly name (if any).
+struct Label<'a>(&'a TrustedSubject);
+
+impl fmt::Display for Label<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.cert_id())?;
+        match self.0.friendly_name() {
+            Ok(Some(name)) => write!(f, " ({name})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn entries_by_id(ctl: &CertificateTrustList) -> HashMap<SubjectId, &TrustedSubject> {
+    ctl.trusted_subjects
+        .iter()
+        .flatten()
+        .map(|entry| (entry.cert_id(), entry))
+        .collect()
+}
+
+/// Returns a description of each property whose values differ between the entries.
+fn changed_properties(old: &TrustedSubject, new: &TrustedSubject) -> BTreeSet<String> {
+    let old = old.attributes_by_oid();
+    let new = new.attributes_by_oid();
+
+    old.keys()
+        .chain(new.keys())
+        .filter(|oid| old.get(oid) != new.get(oid))
+        .map(|oid| oids::describe(oid).map_or_else(|| oid.to_string(), String::from))
+        .collect()
+}
+
+fn same_key(old: &Certificate, new: &Certificate) -> bool {
+    old.tbs_certificate.subject_public_key_info == new.tbs_certificate.subject_public_key_info
+}
+
+#[cfg(test)]
+mod tests {
+    use windows_ctl::{
+        store::{MemoryStorage, StorageCache},
+        CtlBuilder, ListUsage, SubjectAlgorithm, SubjectAttribute,
+    };
+    use x509_cert::{
+        der::{asn1::BitString, DecodePem, Encode},
+        serial_number::SerialNumber,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let cert = Certificate::from_pem(include_str!("../testdata/aia.pem")).unwrap();
+        let mut renewed = cert.clone();
+        renewed.tbs_certificate.serial_number = SerialNumber::new(&[0x42]).unwrap();
+        let mut rekeyed = renewed.clone();
+        rekeyed
+            .tbs_certificate
+            .subject_public_key_info
+            .subject_public_key = BitString::from_bytes(&[0x42; 65]).unwrap();
+        let mut other = cert.clone();
+        other.tbs_certificate.subject = Default::default();
+
+        let list = |certs: &[&Certificate]| {
+            certs
+                .iter()
+                .fold(
+                    CtlBuilder::new().usage(ListUsage::RootListSigner),
+                    |b, c| b.add_subject(c, []),
+                )
+                .build()
+                .unwrap()
+        };
+        let old = list(&[&cert, &other]);
+        let renewal = list(&[&renewed, &other]);
+        let rekeying = list(&[&rekeyed]);
+
+        // Without certificates, only identifiers can be compared.
+        let d = diff(&old, &renewal, None).unwrap();
+        assert_eq!((d.added.len(), d.removed.len()), (1, 1));
+        assert!(d.replaced.is_empty() && d.changed.is_empty());
+
+        let mut cache = StorageCache::new(MemoryStorage::new());
+        for c in [&cert, &renewed, &rekeyed] {
+            let der = c.to_der().unwrap();
+            let id = SubjectAlgorithm::Sha1.identify(&der);
+            cache.put(&id, &der).unwrap();
+        }
+
+        let d = diff(&old, &renewal, Some(&mut cache)).unwrap();
+        assert!(d.added.is_empty() && d.removed.is_empty());
+        assert_eq!(d.replaced.len(), 1);
+        assert!(d.replaced[0].same_key);
+        assert_eq!(d.unresolved, 0);
+
+        // `other` isn't cached, so it's left as a removal.
+        let d = diff(&old, &rekeying, Some(&mut cache)).unwrap();
+        assert_eq!(d.removed.len(), 1);
+        assert_eq!(d.replaced.len(), 1);
+        assert!(!d.replaced[0].same_key);
+        assert_eq!(d.unresolved, 1);
+
+        let renamed = CtlBuilder::new()
+            .usage(ListUsage::RootListSigner)
+            .add_subject(&cert, [SubjectAttribute::FriendlyName("Renamed".into())])
+            .add_subject(&other, [])
+            .build()
+            .unwrap();
+        let d = diff(&old, &renamed, None).unwrap();
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(d.changed[0].1.iter().collect::<Vec<_>>(), ["friendly name"]);
+    }
+}
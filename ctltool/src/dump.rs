@@ -0,0 +1,189 @@
+//! Streaming serialization of `dump` documents.
+//!
+//! Building the whole document as a [`serde_json::Value`] before writing it out
+//! holds a second, much larger copy of every entry in memory, which adds up for big
+//! lists like the disallowed list. Instead, each entry is serialized and written out
+//! as it's reached.
+
+use serde::{
+    ser::{self, SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+use windows_ctl::{CertificateTrustList, SerializeOptions, TrustedSubject};
+
+use crate::{explain, schema::SCHEMA_VERSION};
+
+/// A `dump` document: a list's own fields, and the given entries from it.
+pub struct DumpDocument<I> {
+    list: Map<String, Value>,
+    entries: I,
+    full: bool,
+    options: SerializeOptions,
+}
+
+impl<'a, I> DumpDocument<I>
+where
+    I: Iterator<Item = &'a TrustedSubject> + Clone,
+{
+    /// Creates a document over `entries` (of `ctl`), additionally including each
+    /// entry's unknown attributes if `full` is set.
+    pub fn new(
+        ctl: &CertificateTrustList,
+        entries: I,
+        full: bool,
+        options: SerializeOptions,
+    ) -> serde_json::Result<Self> {
+        Ok(Self {
+            list: list_fields(ctl, options)?,
+            entries,
+            full,
+            options,
+        })
+    }
+}
+
+/// Returns `ctl`'s serialized fields, other than its entries.
+fn list_fields(
+    ctl: &CertificateTrustList,
+    options: SerializeOptions,
+) -> serde_json::Result<Map<String, Value>> {
+    // Copy everything but the (potentially huge) entries.
+    let header = CertificateTrustList {
+        version: ctl.version,
+        subject_usage: ctl.subject_usage.clone(),
+        list_identifier: ctl.list_identifier.clone(),
+        sequence_number: ctl.sequence_number.clone(),
+        this_update: ctl.this_update,
+        next_update: ctl.next_update,
+        subject_algorithm: ctl.subject_algorithm.clone(),
+        trusted_subjects: None,
+        ctl_extensions: None,
+    };
+
+    let mut fields = match serde_json::to_value(header.serialize_with(options))? {
+        Value::Object(fields) => fields,
+        _ => unreachable!("lists serialize as objects"),
+    };
+    fields.remove("entries");
+    Ok(fields)
+}
+
+impl<'a, I> Serialize for DumpDocument<I>
+where
+    I: Iterator<Item = &'a TrustedSubject> + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.list.len() + 2))?;
+        for (key, value) in &self.list {
+            map.serialize_entry(key, value)?;
+        }
+        map.serialize_entry(
+            "entries",
+            &Entries(self.entries.clone(), self.full, self.options),
+        )?;
+        map.serialize_entry("schema_version", &SCHEMA_VERSION)?;
+        map.end()
+    }
+}
+
+/// The `entries` array, serialized one entry at a time.
+struct Entries<I>(I, bool, SerializeOptions);
+
+impl<'a, I> Serialize for Entries<I>
+where
+    I: Iterator<Item = &'a TrustedSubject> + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for entry in self.0.clone() {
+            if self.1 {
+                // Only this entry is ever materialized.
+                let mut value = serde_json::to_value(entry.serialize_with(self.2))
+                    .map_err(ser::Error::custom)?;
+                value["unknown_attributes"] = explain::unknown_attributes_json(entry);
+                seq.serialize_element(&value)?;
+            } else {
+                seq.serialize_element(&entry.serialize_with(self.2))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use x509_cert::{
+        der::{asn1::UtcTime, Decode},
+        ext::pkix::ExtendedKeyUsage,
+        spki::{AlgorithmIdentifier, ObjectIdentifier},
+        time::Time,
+    };
+
+    use super::*;
+    use crate::schema;
+
+    /// A `TrustedSubject` with the given identifier byte, and a single unknown attribute.
+    fn entry(id: u8) -> TrustedSubject {
+        let mut der = vec![0x30, 0x2d, 0x04, 0x14];
+        der.extend([id; 20]);
+        // SET { SEQUENCE { OID 1.2.3.4, SET { OCTET STRING "0123456789" } } }
+        der.extend([
+            0x31, 0x15, 0x30, 0x13, 0x06, 0x03, 0x2a, 0x03, 0x04, 0x31, 0x0c, 0x04, 0x0a,
+        ]);
+        der.extend(b"0123456789");
+        TrustedSubject::from_der(&der).unwrap()
+    }
+
+    #[test]
+    fn test_dump_document() {
+        let entries = vec![entry(0x11), entry(0x22)];
+        let ctl = CertificateTrustList {
+            version: Default::default(),
+            subject_usage: ExtendedKeyUsage(vec![ObjectIdentifier::new_unwrap(
+                "1.3.6.1.4.1.311.10.3.9",
+            )]),
+            list_identifier: None,
+            sequence_number: None,
+            this_update: Time::UtcTime(UtcTime::from_unix_duration(Duration::ZERO).unwrap()),
+            next_update: None,
+            subject_algorithm: AlgorithmIdentifier {
+                oid: ObjectIdentifier::new_unwrap("1.3.14.3.2.26"),
+                parameters: None,
+            },
+            trusted_subjects: Some(entries.clone()),
+            ctl_extensions: None,
+        };
+
+        for full in [false, true] {
+            let streamed = serde_json::to_value(
+                DumpDocument::new(&ctl, entries.iter(), full, Default::default()).unwrap(),
+            )
+            .unwrap();
+
+            let expected = entries
+                .iter()
+                .map(|entry| {
+                    let mut value = serde_json::to_value(entry).unwrap();
+                    if full {
+                        value["unknown_attributes"] = explain::unknown_attributes_json(entry);
+                    }
+                    value
+                })
+                .collect::<Vec<_>>();
+            let mut document = serde_json::to_value(&ctl).unwrap();
+            document["entries"] = json!(expected);
+            assert_eq!(streamed, schema::versioned(document));
+        }
+
+        let empty = serde_json::to_value(
+            DumpDocument::new(&ctl, [].iter(), false, Default::default()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(empty["entries"], json!([]));
+        assert_eq!(empty["usage"], json!(["1.3.6.1.4.1.311.10.3.9"]));
+    }
+}
@@ -0,0 +1,173 @@
+//! Human-readable explanations of CTLs and their entries.
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use windows_ctl::{
+    eku, oids, CertificateTrustList, ListUsage, MetaEku, SubjectAttribute, TrustedSubject,
+};
+use x509_cert::der::asn1::{ObjectIdentifier, OctetStringRef};
+use x509_cert::der::DateTime;
+
+use crate::hexdump::{der_outline, hexdump};
+
+/// Prints a summary of the CTL itself.
+pub fn explain_list(ctl: &CertificateTrustList) -> Result<()> {
+    println!("kind:              {:?}", ctl.kind());
+    let usages = ctl
+        .subject_usage
+        .0
+        .iter()
+        .map(|oid| match ListUsage::from_oid(oid) {
+            Some(usage) => format!("{usage} ({oid})"),
+            None => oid.to_string(),
+        })
+        .collect::<Vec<_>>();
+    println!("usage:             {}", usages.join(", "));
+    if let Some(name) = ctl.name()? {
+        println!("name:              {name}");
+    }
+    if let Some(sequence_number) = &ctl.sequence_number {
+        println!("sequence number:   {sequence_number}");
+    }
+    println!("this update:       {}", ctl.this_update);
+    if let Some(next_update) = &ctl.next_update {
+        println!("next update:       {next_update}");
+    }
+    match ctl.identifier_algorithm() {
+        Some(algorithm) => println!("subject algorithm: {algorithm}"),
+        None => println!("subject algorithm: {}", ctl.subject_algorithm.oid),
+    }
+    println!(
+        "entries:           {}",
+        ctl.trusted_subjects.as_ref().map_or(0, Vec::len)
+    );
+
+    Ok(())
+}
+
+/// Prints every attribute of `entry`, rendering those that can't be decoded as hexdumps.
+pub fn explain_entry(entry: &TrustedSubject) {
+    println!("{}", entry.cert_id());
+    if let Ok(bits) = entry.trust_bits() {
+        let names = bits.iter_names().map(|(name, _)| name).collect::<Vec<_>>();
+        match names.is_empty() {
+            true => println!("  trust: none"),
+            false => println!("  trust: {}", names.join(" | ")),
+        }
+    }
+
+    for attr in entry.decoded_attributes() {
+        match attr {
+            Ok(SubjectAttribute::Unknown { .. }) => {}
+            Ok(attr) => println!("  {}", describe(&attr)),
+            Err(e) => println!("  malformed attribute: {e}"),
+        }
+    }
+
+    for (oid, bytes) in undecoded_attributes(entry) {
+        // Documented properties that just aren't decoded are named; anything else may
+        // be a property type that's new to the list.
+        let kind = oids::describe(&oid).unwrap_or("unrecognized");
+        println!("  {oid} ({kind}, {} bytes):", bytes.len());
+        for line in render_unknown(&bytes) {
+            println!("    {line}");
+        }
+    }
+}
+
+/// Renders an unknown attribute value as a hexdump, followed by its DER structure
+/// (if it has any).
+fn render_unknown(bytes: &[u8]) -> Vec<String> {
+    let mut lines = hexdump(bytes);
+    if let Some(outline) = der_outline(bytes) {
+        lines.push("decoded as DER:".into());
+        lines.extend(outline.into_iter().map(|line| format!("  {line}")));
+    }
+    lines
+}
+
+/// Returns every attribute value of `entry` that isn't decoded, as `(oid, bytes)`
+/// pairs: documented properties that the library doesn't model, as well as
+/// unrecognized ones. As with [`TrustedSubject::unknown_attributes`], `OCTET STRING`
+/// values are unwrapped.
+fn undecoded_attributes(entry: &TrustedSubject) -> Vec<(ObjectIdentifier, Vec<u8>)> {
+    entry
+        .decoded_attributes()
+        .filter_map(|attr| match attr {
+            Ok(SubjectAttribute::Unknown { oid, raw }) => {
+                let bytes = raw
+                    .decode_as::<OctetStringRef>()
+                    .map_or_else(|_| raw.value().to_vec(), |o| o.as_bytes().to_vec());
+                Some((oid, bytes))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns a JSON rendering of `entry`'s undecoded attributes, for `dump --full`.
+pub fn unknown_attributes_json(entry: &TrustedSubject) -> Value {
+    undecoded_attributes(entry)
+        .into_iter()
+        .map(|(oid, bytes)| {
+            json!({
+                "oid": oid.to_string(),
+                "hexdump": hexdump(&bytes),
+                "der": der_outline(&bytes),
+            })
+        })
+        .collect()
+}
+
+/// Describes a single (known) attribute in a line.
+fn describe(attr: &SubjectAttribute) -> String {
+    match attr {
+        SubjectAttribute::FriendlyName(name) => format!("friendly name: {name}"),
+        SubjectAttribute::MetaEkus(ekus) => format!("extended key usages: {}", ekus_list(ekus)),
+        SubjectAttribute::DisallowedFiletime(time) => format!("disallowed at: {}", timestamp(time)),
+        SubjectAttribute::DisallowedEkus(ekus) => {
+            format!("disallowed extended key usages: {}", ekus_list(ekus))
+        }
+        SubjectAttribute::NotBeforeFiletime(time) => {
+            format!("not-before time: {}", timestamp(time))
+        }
+        SubjectAttribute::NotBeforeEkus(ekus) => {
+            format!("not-before extended key usages: {}", ekus_list(ekus))
+        }
+        SubjectAttribute::Sha256Hash(hash) => format!("SHA-256 hash: {}", hex::encode(hash)),
+        SubjectAttribute::KeyId(id) => format!("key identifier: {}", hex::encode(id)),
+        SubjectAttribute::RootProgramCertPolicies(policies) => format!(
+            "root program certificate policies: {}",
+            oid_list(policies.iter().map(|p| &p.policy_identifier))
+        ),
+        SubjectAttribute::RootProgramChainPolicies(policies) => {
+            format!("root program chain policies: {}", oid_list(policies.iter()))
+        }
+        SubjectAttribute::SubjectNameMd5Hash(hash) => {
+            format!("MD5 subject name hash: {}", hex::encode(hash))
+        }
+        other => format!("{other:?}"),
+    }
+}
+
+pub fn ekus_list(ekus: &MetaEku) -> String {
+    ekus.iter()
+        .map(|oid| match eku::name(oid) {
+            Some(name) => format!("{name} ({oid})"),
+            None => oid.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn oid_list<'a>(oids: impl Iterator<Item = &'a ObjectIdentifier>) -> String {
+    oids.map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn timestamp(time: &SystemTime) -> String {
+    DateTime::from_system_time(*time)
+        .map(|dt| dt.to_string())
+        .unwrap_or_else(|_| format!("{time:?}"))
+}
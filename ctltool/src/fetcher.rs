@@ -0,0 +1,451 @@
+//! Retrieval of the certificates that CTL entries refer to.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use reqwest::{
+    blocking::Response,
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH,
+    },
+    StatusCode,
+};
+use windows_ctl::{
+    store::{CertificateFetcher, DirCache, StoreBuilder, StoreReport},
+    CertificateTrustList, CtlError, SubjectId, TrustedSubject,
+};
+
+use crate::schema;
+
+/// How long a single download may take, unless overridden by `--timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The base URL that Microsoft serves CTL-listed certificates from.
+const CDN_BASE_URL: &str =
+    "http://www.download.windowsupdate.com/msdownload/update/v3/static/trustedr/en";
+
+/// The User-Agent sent with every request, unless overridden by `--user-agent`.
+const DEFAULT_USER_AGENT: &str = concat!("ctltool/", env!("CARGO_PKG_VERSION"));
+
+/// Returns the Microsoft CDN URL of the certificate with the given identifier.
+pub fn cdn_url(id: &SubjectId) -> String {
+    format!("{CDN_BASE_URL}/{id}.crt")
+}
+
+/// Options controlling how certificates are retrieved.
+#[derive(Args, Debug)]
+pub struct FetchOpts {
+    /// A JSON file mapping identifiers to URLs or DER/PEM files, consulted before the Microsoft CDN
+    ///
+    /// Relative file paths are resolved against the mapping file's directory.
+    #[arg(long, value_name = "FILE")]
+    sources: Option<PathBuf>,
+
+    /// Fail if any entry has no downloadable certificate, rather than recording it as metadata-only
+    #[arg(long)]
+    strict: bool,
+
+    /// If some certificates can't be retrieved, still write out the rest (and then fail), rather than writing nothing
+    ///
+    /// The failures are listed on stderr, and in the manifest (if any).
+    #[arg(long)]
+    keep_partial: bool,
+
+    /// Write a JSON manifest of the fetched, metadata-only, and failed entries to this file (must not exist)
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Cache verified certificates in this directory, and reuse them on later runs
+    #[arg(long, value_name = "DIR")]
+    cache: Option<PathBuf>,
+
+    /// Give up on any single download after this many seconds [default: 30]
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Give up on all downloads once this many seconds have passed, in total
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    deadline: Option<Duration>,
+}
+
+/// Options applying to every outbound HTTP request, e.g. for getting through proxies
+/// or mirrors that allow-list clients.
+#[derive(Args, Clone, Debug, Default)]
+pub struct HttpOpts {
+    /// The User-Agent to send with every request [default: ctltool/<version>]
+    #[arg(long, global = true, value_name = "AGENT")]
+    user_agent: Option<String>,
+
+    /// An extra header to send with every request, as `Name: value` (may be repeated, with later values for the same header taking precedence)
+    #[arg(long = "header", global = true, value_name = "HEADER", value_parser = parse_header)]
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+/// Parses a `Name: value` header.
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| anyhow!("header must be given as `Name: value`: {header:?}"))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .with_context(|| format!("invalid header name: {name:?}"))?;
+    let value = HeaderValue::from_str(value.trim())
+        .with_context(|| format!("invalid value for header {name}"))?;
+    Ok((name, value))
+}
+
+pub fn parse_seconds(secs: &str) -> Result<Duration> {
+    secs.parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow!("invalid number of seconds: {secs:?}"))
+}
+
+/// Where to retrieve a particular certificate from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    Url(String),
+    File(PathBuf),
+}
+
+/// An HTTP client that honors `--timeout` and `--deadline`, along with [`HttpOpts`].
+#[derive(Clone, Debug)]
+pub struct Http {
+    client: reqwest::blocking::Client,
+    timeout: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Http {
+    /// Creates a client whose requests each time out after `timeout` (or the default),
+    /// and which stops sending requests once `deadline` has passed, if given.
+    pub fn new(
+        opts: &HttpOpts,
+        timeout: Option<Duration>,
+        deadline: Option<Duration>,
+    ) -> Result<Self> {
+        let timeout = timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let mut headers = HeaderMap::new();
+        for (name, value) in &opts.headers {
+            headers.insert(name, value.clone());
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(opts.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self {
+            client,
+            timeout,
+            deadline: deadline.map(|deadline| Instant::now() + deadline),
+        })
+    }
+
+    /// Sends a GET request for `url`, accepting compressed responses.
+    ///
+    /// The response's body should be read with [`Http::body`], which undoes any
+    /// compression.
+    pub fn get(&self, url: &str) -> Result<Response> {
+        let mut request = self
+            .client
+            .get(url)
+            .header(ACCEPT_ENCODING, "gzip, deflate");
+        if let Some(deadline) = self.deadline {
+            // Requests never outlive the deadline, however long their own timeout.
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .ok_or_else(|| anyhow!("cert retrieval failed: --deadline exceeded"))?;
+            request = request.timeout(remaining.min(self.timeout));
+        }
+        Ok(request.send()?)
+    }
+
+    /// Reads the body of `resp`, failing if it's shorter or longer than its
+    /// `Content-Length`, and then decompresses it according to its `Content-Encoding`.
+    ///
+    /// Truncated downloads are rejected here, rather than left to show up as
+    /// fingerprint mismatches (or, for unlisted certificates, not at all).
+    pub fn body(resp: Response) -> Result<Vec<u8>> {
+        let url = resp.url().clone();
+        let headers = resp.headers().clone();
+        let header = |name: HeaderName| {
+            headers
+                .get(&name)
+                .map(HeaderValue::to_str)
+                .transpose()
+                .with_context(|| format!("{url} returned a malformed {name} header"))
+        };
+        let length = header(CONTENT_LENGTH)?
+            .map(str::parse::<u64>)
+            .transpose()
+            .with_context(|| format!("{url} returned a malformed content-length header"))?;
+        let encoding = header(CONTENT_ENCODING)?;
+
+        let raw = resp.bytes()?;
+        decode_body(&raw, length, encoding).with_context(|| format!("bad response from {url}"))
+    }
+}
+
+/// Checks `raw` against the response's `Content-Length` (if any), and then undoes its
+/// `Content-Encoding` (if any).
+fn decode_body(raw: &[u8], length: Option<u64>, encoding: Option<&str>) -> Result<Vec<u8>> {
+    if let Some(length) = length {
+        if raw.len() as u64 != length {
+            return Err(anyhow!(
+                "received {} bytes, but content-length is {length}",
+                raw.len()
+            ));
+        }
+    }
+
+    let mut body = vec![];
+    match encoding
+        .map(|encoding| encoding.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        None | Some("identity") => body.extend_from_slice(raw),
+        Some("gzip" | "x-gzip") => {
+            GzDecoder::new(raw)
+                .read_to_end(&mut body)
+                .context("corrupt gzip response")?;
+        }
+        Some("deflate") => {
+            ZlibDecoder::new(raw)
+                .read_to_end(&mut body)
+                .context("corrupt deflate response")?;
+        }
+        Some(encoding) => return Err(anyhow!("unsupported content-encoding: {encoding}")),
+    }
+    Ok(body)
+}
+
+/// Downloads certificates, either from the Microsoft CDN or from user-supplied overrides.
+struct Downloader {
+    http: Http,
+    overrides: HashMap<SubjectId, Source>,
+    strict: bool,
+}
+
+impl Downloader {
+    fn source(&self, entry: &TrustedSubject) -> Source {
+        // CDN URLs are built from the entry's identifier as-is, so they follow
+        // the list's subject algorithm (SHA-1 or otherwise).
+        let id = entry.cert_id();
+        self.overrides
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| Source::Url(cdn_url(&id)))
+    }
+
+    fn download(&self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>> {
+        match self.source(entry) {
+            Source::Url(url) => {
+                let resp = self.http.get(&url)?;
+                if resp.status() == StatusCode::NOT_FOUND && !self.strict {
+                    return Ok(None);
+                }
+                if !resp.status().is_success() {
+                    return Err(anyhow!(
+                        "cert retrieval failed: {} returned {}",
+                        &url,
+                        resp.status().as_u16()
+                    ));
+                }
+                Ok(Some(Http::body(resp)?))
+            }
+            Source::File(path) => {
+                Ok(Some(fs::read(&path).with_context(|| {
+                    format!("cert retrieval failed: couldn't read {path:?}")
+                })?))
+            }
+        }
+    }
+}
+
+impl CertificateFetcher for Downloader {
+    fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+        self.download(entry)
+            .map_err(|e| CtlError::Fetch(entry.cert_id(), e.into()))
+    }
+}
+
+/// Retrieves certificates for a [`StoreBuilder`], and reports on what was retrieved.
+///
+/// Some entries (particularly on the disallowed list) have no certificate on the CDN.
+/// Unless `--strict` is given, these are recorded as "metadata-only" rather than
+/// treated as errors.
+pub struct Fetcher {
+    downloader: Downloader,
+    cache: Option<DirCache>,
+    manifest: Option<PathBuf>,
+    keep_partial: bool,
+}
+
+impl Fetcher {
+    pub fn new(opts: &FetchOpts, http: &HttpOpts) -> Result<Self> {
+        let mut overrides = HashMap::new();
+
+        if let Some(sources) = &opts.sources {
+            let contents = fs::read(sources)
+                .with_context(|| format!("failed to read source mapping: {sources:?}"))?;
+            let mapping: HashMap<String, String> =
+                serde_json::from_slice(&contents).context("invalid source mapping")?;
+            let base = sources.parent().map(PathBuf::from).unwrap_or_default();
+
+            for (id, location) in mapping {
+                let id = id.parse::<SubjectId>()?;
+                let source = if location.starts_with("http://") || location.starts_with("https://")
+                {
+                    Source::Url(location)
+                } else {
+                    Source::File(base.join(location))
+                };
+                overrides.insert(id, source);
+            }
+        }
+
+        let cache = opts
+            .cache
+            .as_ref()
+            .map(|dir| DirCache::new(dir).with_context(|| format!("invalid cache: {dir:?}")))
+            .transpose()?;
+
+        Ok(Self {
+            downloader: Downloader {
+                http: Http::new(http, opts.timeout, opts.deadline)?,
+                overrides,
+                strict: opts.strict,
+            },
+            cache,
+            manifest: opts.manifest.clone(),
+            keep_partial: opts.keep_partial,
+        })
+    }
+
+    /// Returns the HTTP client that certificates are downloaded with.
+    pub fn http(&self) -> Http {
+        self.downloader.http.clone()
+    }
+
+    /// Returns a [`StoreBuilder`] for the entries of `ctl` that retrieves certificates
+    /// according to this fetcher's options.
+    pub fn store_builder<'a>(&'a mut self, ctl: &'a CertificateTrustList) -> StoreBuilder<'a> {
+        let builder = StoreBuilder::new(ctl, &mut self.downloader).keep_partial(self.keep_partial);
+        match &mut self.cache {
+            Some(cache) => builder.cache(cache),
+            None => builder,
+        }
+    }
+
+    /// Reports on the metadata-only and failed entries, and writes the manifest (if
+    /// requested).
+    ///
+    /// Fails if any certificates couldn't be retrieved, after the manifest is written.
+    pub fn finish(self, report: &StoreReport) -> Result<()> {
+        if !report.metadata_only.is_empty() {
+            eprintln!(
+                "note: {} entries have no downloadable certificate and were recorded as metadata-only",
+                report.metadata_only.len()
+            );
+        }
+        for (id, error) in &report.failed {
+            eprintln!("error: couldn't retrieve {id}: {error}");
+        }
+
+        if let Some(path) = &self.manifest {
+            let ids = |ids: &[SubjectId]| ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let manifest = schema::versioned(serde_json::json!({
+                "fetched": ids(&report.written),
+                "metadata_only": ids(&report.metadata_only),
+                "failed": report
+                    .failed
+                    .iter()
+                    .map(|(id, error)| serde_json::json!({ "identifier": id, "error": error }))
+                    .collect::<Vec<_>>(),
+            }));
+
+            let file = File::options()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .with_context(|| format!("refusing to write to an extant file: {path:?}"))?;
+            serde_json::to_writer_pretty(file, &manifest)?;
+        }
+
+        if !report.failed.is_empty() {
+            return Err(anyhow!(
+                "failed to retrieve {} certificate(s); the output contains only the other {}",
+                report.failed.len(),
+                report.written.len()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{
+        write::{GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_decode_body() {
+        let body = b"not really a certificate".to_vec();
+        let len = |raw: &[u8]| Some(raw.len() as u64);
+
+        assert_eq!(decode_body(&body, len(&body), None).unwrap(), body);
+        assert_eq!(decode_body(&body, None, Some("identity")).unwrap(), body);
+
+        let mut gzip = GzEncoder::new(vec![], Compression::default());
+        gzip.write_all(&body).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(decode_body(&gzip, len(&gzip), Some("gzip")).unwrap(), body);
+
+        let mut deflate = ZlibEncoder::new(vec![], Compression::default());
+        deflate.write_all(&body).unwrap();
+        let deflate = deflate.finish().unwrap();
+        assert_eq!(
+            decode_body(&deflate, len(&deflate), Some("deflate")).unwrap(),
+            body
+        );
+
+        // Truncated, whether or not compressed.
+        assert!(decode_body(&body[1..], len(&body), None).is_err());
+        assert!(decode_body(&gzip[..gzip.len() - 8], None, Some("gzip")).is_err());
+        assert!(decode_body(&gzip[..10], len(&gzip), Some("gzip")).is_err());
+
+        assert!(decode_body(&body, len(&body), Some("br")).is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let (name, value) = parse_header("X-Api-Key:  secret ").unwrap();
+        assert_eq!(name, "x-api-key");
+        assert_eq!(value, "secret");
+
+        let (name, value) = parse_header("Proxy-Authorization: Basic a2V5OnZhbHVl").unwrap();
+        assert_eq!(name, "proxy-authorization");
+        assert_eq!(value, "Basic a2V5OnZhbHVl");
+
+        for invalid in ["X-Api-Key", "Bad Name: value", "X-Api-Key: line\nbreak"] {
+            assert!(parse_header(invalid).is_err(), "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Human-readable renderings of raw (e.g. unknown attribute) values.
+
+use windows_ctl::{eku, oids};
+use x509_cert::der::{
+    asn1::{AnyRef, ObjectIdentifier, OctetStringRef},
+    Reader, SliceReader, Tag, Tagged,
+};
+
+/// How many bytes each hexdump line covers.
+const WIDTH: usize = 16;
+
+/// Renders `bytes` as a hexdump, one line per 16 bytes: the offset, the bytes in
+/// hex, and then any printable ASCII.
+pub fn hexdump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(WIDTH)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|&b| match b {
+                    b' ' | b'!'..=b'~' => b as char,
+                    _ => '.',
+                })
+                .collect::<String>();
+            format!(
+                "{:08x}  {hex:<width$}  |{ascii}|",
+                i * WIDTH,
+                width = WIDTH * 3 - 1
+            )
+        })
+        .collect()
+}
+
+/// Attempts to decode `bytes` as a sequence of DER elements, returning an indented
+/// outline of their structure (one line per element).
+///
+/// Returns `None` if `bytes` isn't entirely well-formed DER.
+pub fn der_outline(bytes: &[u8]) -> Option<Vec<String>> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec![];
+    outline(bytes, 0, &mut lines).ok()?;
+    Some(lines)
+}
+
+fn outline(bytes: &[u8], depth: usize, lines: &mut Vec<String>) -> x509_cert::der::Result<()> {
+    let mut reader = SliceReader::new(bytes)?;
+    while !reader.is_finished() {
+        let element: AnyRef = reader.decode()?;
+        let indent = "  ".repeat(depth);
+        let tag = element.tag();
+        let value = element.value();
+
+        if tag.is_constructed() {
+            lines.push(format!("{indent}{tag} ({} bytes)", value.len()));
+            outline(value, depth + 1, lines)?;
+            continue;
+        }
+
+        lines.push(format!("{indent}{tag} {}", summarize(element)));
+
+        // Microsoft likes to wrap DER in OCTET STRINGs, so look inside those too.
+        if tag == Tag::OctetString {
+            if let Some(inner) = der_outline(value) {
+                lines.extend(inner.into_iter().map(|line| format!("{indent}  {line}")));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Summarizes a primitive DER element's value.
+fn summarize(element: AnyRef) -> String {
+    let value = element.value();
+    match element.tag() {
+        Tag::ObjectIdentifier => match element.decode_as::<ObjectIdentifier>() {
+            Ok(oid) => match eku::name(&oid).or_else(|| oids::describe(&oid)) {
+                Some(name) => format!("{oid} ({name})"),
+                None => oid.to_string(),
+            },
+            Err(_) => hex::encode(value),
+        },
+        Tag::Utf8String | Tag::PrintableString | Tag::Ia5String => {
+            format!("{:?}", String::from_utf8_lossy(value))
+        }
+        Tag::Integer | Tag::Boolean => hex::encode(value),
+        Tag::OctetString => match element.decode_as::<OctetStringRef>() {
+            Ok(octets) => format!("({} bytes)", octets.as_bytes().len()),
+            Err(_) => hex::encode(value),
+        },
+        Tag::Null => String::new(),
+        _ => format!("({} bytes)", value.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexdump() {
+        let lines = hexdump(b"0123456789abcdef\x00\x01");
+        assert_eq!(
+            lines,
+            [
+                "00000000  30 31 32 33 34 35 36 37 38 39 61 62 63 64 65 66  |0123456789abcdef|",
+                "00000010  00 01                                            |..|",
+            ]
+        );
+        assert!(hexdump(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_der_outline() {
+        // SEQUENCE { OID serverAuth, OCTET STRING { INTEGER 5 } }
+        let der = [
+            0x30, 0x0f, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01, 0x04, 0x03,
+            0x02, 0x01, 0x05,
+        ];
+        assert_eq!(
+            der_outline(&der).unwrap(),
+            [
+                "SEQUENCE (15 bytes)",
+                "  OBJECT IDENTIFIER 1.3.6.1.5.5.7.3.1 (Server Authentication)",
+                "  OCTET STRING (3 bytes)",
+                "    INTEGER 05",
+            ]
+        );
+
+        assert_eq!(der_outline(&[]), None);
+        assert_eq!(der_outline(&[0x30, 0x05, 0x02]), None);
+        assert_eq!(der_outline(b"not DER"), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Retrieval of past generations of a list, by sequence number, from archives.
+//!
+//! Microsoft only serves the latest release of each list, so reconstructing history
+//! relies on archives: either a mirror that serves releases at predictable URLs, or a
+//! local directory of previously retrieved lists, named however they happen to be.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use windows_ctl::{CertificateTrustList, CtlKind, SequenceNumber};
+
+use crate::{fetcher::Http, parse_ctl};
+
+/// A list whose past generations can be retrieved.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ListName {
+    /// The trusted root list
+    Authroot,
+    /// The disallowed list
+    Disallowed,
+}
+
+impl ListName {
+    /// Returns the name substituted for `{list}` in URL templates.
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListName::Authroot => "authroot",
+            ListName::Disallowed => "disallowed",
+        }
+    }
+
+    /// Returns whether `ctl` is a generation of this list.
+    fn matches(&self, ctl: &CertificateTrustList) -> bool {
+        match self {
+            ListName::Authroot => ctl.kind() == CtlKind::AuthRoot,
+            ListName::Disallowed => ctl.kind() == CtlKind::Disallowed,
+        }
+    }
+}
+
+/// Where past generations are retrieved from.
+#[derive(Clone, Debug)]
+pub enum Archive {
+    /// A URL template, in which `{sequence}` is replaced by the hex sequence number
+    /// and `{list}` by the list's name.
+    Url(String),
+    /// A directory of lists (as cabinets, DER, or PEM), searched for the one with the
+    /// requested sequence number.
+    Dir(PathBuf),
+}
+
+impl Archive {
+    /// Parses an `--archive` argument: a URL template if it's HTTP(S), and a
+    /// directory otherwise.
+    pub fn parse(arg: &str) -> Result<Self> {
+        if arg.starts_with("http://") || arg.starts_with("https://") {
+            if !arg.contains("{sequence}") {
+                return Err(anyhow!("archive URL template has no {{sequence}}: {arg}"));
+            }
+            Ok(Self::Url(arg.into()))
+        } else {
+            Ok(Self::Dir(arg.into()))
+        }
+    }
+
+    /// Retrieves the generation of `list` with the given sequence number, returning
+    /// both the list and its PKCS#7 DER (unwrapped, if it was archived as a cabinet
+    /// or PEM).
+    pub fn retrieve(
+        &self,
+        http: &Http,
+        list: ListName,
+        sequence_number: &SequenceNumber,
+    ) -> Result<(CertificateTrustList, Vec<u8>)> {
+        let found = match self {
+            Self::Url(template) => {
+                let url = template
+                    .replace("{sequence}", &sequence_number.to_hex())
+                    .replace("{list}", list.as_str());
+                let resp = http.get(&url)?;
+                if !resp.status().is_success() {
+                    return Err(anyhow!("{url} returned {}", resp.status().as_u16()));
+                }
+                parse_ctl(&Http::body(resp)?).with_context(|| format!("bad list from {url}"))?
+            }
+            Self::Dir(dir) => search_dir(dir, list, sequence_number)?.ok_or_else(|| {
+                anyhow!(
+                    "no {} list with sequence number {sequence_number} in {dir:?}",
+                    list.as_str()
+                )
+            })?,
+        };
+
+        let (ctl, _) = &found;
+        if !list.matches(ctl) {
+            return Err(anyhow!("archived list isn't the {} list", list.as_str()));
+        }
+        if ctl.sequence_number.as_ref() != Some(sequence_number) {
+            return Err(anyhow!(
+                "archived list has sequence number {}, not {sequence_number}",
+                ctl.sequence_number
+                    .as_ref()
+                    .map_or_else(|| "none".into(), SequenceNumber::to_hex)
+            ));
+        }
+        Ok(found)
+    }
+}
+
+/// Returns the generation of `list` in `dir` with the given sequence number, if any.
+///
+/// Files that aren't lists are skipped.
+fn search_dir(
+    dir: &Path,
+    list: ListName,
+    sequence_number: &SequenceNumber,
+) -> Result<Option<(CertificateTrustList, Vec<u8>)>> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("failed to read archive directory: {dir:?}"))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    paths.sort();
+
+    for path in paths.into_iter().filter(|path| path.is_file()) {
+        let Ok((ctl, der)) = parse_ctl(&fs::read(&path)?) else {
+            continue;
+        };
+        if list.matches(&ctl) && ctl.sequence_number.as_ref() == Some(sequence_number) {
+            return Ok(Some((ctl, der)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_parse() {
+        assert!(matches!(
+            Archive::parse("https://example.com/{list}/{sequence}.cab").unwrap(),
+            Archive::Url(_)
+        ));
+        assert!(Archive::parse("https://example.com/authrootstl.cab").is_err());
+        assert!(matches!(
+            Archive::parse("archive/").unwrap(),
+            Archive::Dir(_)
+        ));
+    }
+}
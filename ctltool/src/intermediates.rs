@@ -0,0 +1,154 @@
+//! Discovery of intermediate certificates via Authority Information Access (AIA), for
+//! consumers building full chain pools.
+//!
+//! Some of the certificates that CTL entries refer to turn out to be cross-signed,
+//! or otherwise point at their issuers with AIA `caIssuers` URLs. Those issuers
+//! aren't trust anchors, but chain builders may need them.
+
+use std::{collections::HashSet, fs, io::Write, path::Path};
+
+use anyhow::{anyhow, Result};
+use sha1::{Digest, Sha1};
+use windows_ctl::{store::read_certificates, store::StoreWriter, CtlError, TrustedSubject};
+use x509_cert::{
+    der::{asn1::ObjectIdentifier, pem::LineEnding, Encode, EncodePem},
+    ext::pkix::{name::GeneralName, AuthorityInfoAccessSyntax},
+    Certificate,
+};
+
+use crate::fetcher::Http;
+
+/// The `id-ad-caIssuers` access method (RFC 5280, section 4.2.2.1).
+const CA_ISSUERS: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.48.2");
+
+/// How many issuers deep to follow AIA from each certificate.
+const MAX_DEPTH: usize = 4;
+
+/// Records the certificates written to a store, so that their issuers can be
+/// chased afterwards.
+#[derive(Default)]
+pub struct Recorder {
+    pub certs: Vec<Certificate>,
+}
+
+impl StoreWriter for Recorder {
+    fn write(&mut self, _entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        self.certs.push(cert.clone());
+        Ok(())
+    }
+}
+
+/// Follows AIA `caIssuers` URLs from each of `certs`, returning every certificate
+/// found that isn't itself one of `certs`.
+///
+/// Only HTTP(S) URLs are followed. Failed retrievals are reported on stderr and
+/// otherwise skipped, since the intermediates are only a convenience.
+pub fn chase(http: &Http, certs: &[Certificate]) -> Vec<Certificate> {
+    let fingerprint = |cert: &Certificate| cert.to_der().map(|der| Sha1::digest(der).to_vec());
+
+    let mut seen = certs.iter().flat_map(fingerprint).collect::<HashSet<_>>();
+    let mut visited = HashSet::new();
+    let mut found = vec![];
+
+    let mut frontier = certs.to_vec();
+    for _ in 0..MAX_DEPTH {
+        let mut next = vec![];
+        for url in frontier.iter().flat_map(ca_issuers) {
+            if !visited.insert(url.clone()) {
+                continue;
+            }
+
+            match download(http, &url) {
+                Ok(certs) => {
+                    for cert in certs {
+                        if fingerprint(&cert).is_ok_and(|fp| seen.insert(fp)) {
+                            next.push(cert.clone());
+                            found.push(cert);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("warning: couldn't retrieve intermediates from {url}: {e}"),
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    found
+}
+
+/// Writes `certs` as an annotated PEM section, to be appended to a PEM bundle.
+pub fn write_pem(mut sink: impl Write, certs: &[Certificate]) -> Result<()> {
+    writeln!(
+        sink,
+        "# Intermediates, discovered via Authority Information Access."
+    )?;
+    writeln!(
+        sink,
+        "# These aren't listed in the CTL, and aren't trust anchors."
+    )?;
+    for cert in certs {
+        let tbs_cert = &cert.tbs_certificate;
+        writeln!(sink, "Issuer: {}", tbs_cert.issuer)?;
+        writeln!(sink, "Subject: {}", tbs_cert.subject)?;
+        writeln!(sink, "{}", cert.to_pem(LineEnding::LF)?)?;
+    }
+    Ok(sink.flush()?)
+}
+
+/// Writes each of `certs` as a DER file in an `intermediates` subdirectory of `dir`,
+/// named after its SHA-1 fingerprint.
+pub fn write_der_dir(dir: &Path, certs: &[Certificate]) -> Result<()> {
+    let dir = dir.join("intermediates");
+    fs::create_dir(&dir)?;
+    for cert in certs {
+        let der = cert.to_der()?;
+        let name = format!("{}.crt", hex::encode(Sha1::digest(&der)));
+        fs::write(dir.join(name), der)?;
+    }
+    Ok(())
+}
+
+/// Returns the HTTP(S) `caIssuers` URLs in `cert`'s AIA extension, if it has one.
+fn ca_issuers(cert: &Certificate) -> Vec<String> {
+    let Ok(Some((_, aia))) = cert.tbs_certificate.get::<AuthorityInfoAccessSyntax>() else {
+        return vec![];
+    };
+
+    aia.0
+        .into_iter()
+        .filter(|access| access.access_method == CA_ISSUERS)
+        .filter_map(|access| match access.access_location {
+            GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+            _ => None,
+        })
+        .filter(|uri| uri.starts_with("http://") || uri.starts_with("https://"))
+        .collect()
+}
+
+/// Downloads the certificates at `url`, which may be DER, PEM, or a PKCS#7
+/// certs-only bundle (`.p7c`).
+fn download(http: &Http, url: &str) -> Result<Vec<Certificate>> {
+    let resp = http.get(url)?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("{url} returned {}", resp.status().as_u16()));
+    }
+    Ok(read_certificates(&Http::body(resp)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_cert::der::DecodePem;
+
+    use super::*;
+
+    #[test]
+    fn test_ca_issuers() {
+        // Only the HTTP(S) caIssuers URL, not the OCSP or LDAP ones.
+        let cert = Certificate::from_pem(include_str!("../testdata/aia.pem")).unwrap();
+        assert_eq!(ca_issuers(&cert), ["http://example.com/issuer.crt"]);
+    }
+}
@@ -1,27 +1,74 @@
 use std::{
-    collections::HashSet,
-    fs::File,
-    io::{stdout, Write},
-    path::PathBuf,
+    fs::{self, File},
+    io::{stdin, stdout, BufReader, BufWriter, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Context, Result};
-use clap::{Args, Parser, Subcommand};
-use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
-use pem_rfc7468::LineEnding;
-use windows_ctl::CertificateTrustList;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use windows_ctl::{
+    store::{
+        read_certificates, CertificateCache, DerDirWriter, DirCache, HashedDirWriter, PemWriter,
+        Pkcs7Writer, StoreBuilder, StoreReport, TrustSnapshot, TrustStore,
+    },
+    BinaryEncoding, CertificateTrustList, CtlKind, EkuFormat, MetaEku, Purpose, RawCtl,
+    SequenceNumber, SerializeOptions, SubjectAlgorithm, SubjectId, TimeFormat, TrustedSubject,
+};
 use x509_cert::{
-    der::{Decode, EncodePem},
-    spki::ObjectIdentifier,
+    der::{
+        pem::{self, LineEnding},
+        Encode,
+    },
     Certificate,
 };
 
+mod bundle;
+mod ct;
+mod diff;
+mod dump;
+mod explain;
+mod fetcher;
+mod hexdump;
+mod history;
+mod intermediates;
+mod output;
+mod preset;
+mod progress;
+mod schema;
+mod status;
+
+use bundle::{read_bundle, JsonWriter};
+use ct::CtDataset;
+use dump::DumpDocument;
+use fetcher::{FetchOpts, Fetcher, Http, HttpOpts};
+use history::{Archive, ListName};
+use intermediates::Recorder;
+use output::OutputOpts;
+use preset::Preset;
+use progress::Progress;
+
 fn main() -> Result<()> {
     let args = Cli::parse();
 
+    let warnings = args.warnings;
+    let http = args.http;
     match args.command {
-        Commands::Dump(args) => dump(args),
-        Commands::Fetch(args) => fetch(args),
+        Commands::CrossCheck(args) => cross_check(args, warnings),
+        Commands::CtReport(args) => ct_report(args, warnings),
+        Commands::Diff(args) => diff(args, warnings),
+        Commands::Dump(args) => dump(args, warnings),
+        Commands::Explain(args) => explain(args, warnings),
+        Commands::Fetch(args) => fetch(args, &http, warnings),
+        Commands::FetchList(args) => fetch_list(args, &http),
+        Commands::Hash(args) => hash(args),
+        Commands::InspectCab(args) => inspect_cab(args),
+        Commands::Lookup(args) => lookup(args, warnings),
+        Commands::Schema(args) => print_schema(args),
+        Commands::Snapshot(args) => snapshot(args, &http, warnings),
+        Commands::Status(args) => status(args, &http),
+        Commands::SyncOpenssl(args) => sync_openssl(args, &http, warnings),
+        Commands::VerifyCerts(args) => verify_certs(args, warnings),
     }
 }
 
@@ -31,124 +78,896 @@ fn main() -> Result<()> {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// How to report non-fatal problems found in the CTL (on stderr)
+    #[arg(long, global = true, value_enum, default_value_t = WarningFormat::Text)]
+    warnings: WarningFormat,
+
+    #[command(flatten)]
+    http: HttpOpts,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum WarningFormat {
+    /// One human-readable line per warning
+    Text,
+    /// One JSON object per line
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Check a root list against the disallowed list for inconsistencies in Microsoft's data.
+    CrossCheck(CrossCheckArgs),
+    /// Report which of the given CTL's roots have certificates observed in certificate transparency, per an offline dataset.
+    CtReport(CtReportArgs),
+    /// Compare two generations of a list, optionally including the certificates that entries refer to.
+    Diff(DiffArgs),
     /// Dump the given CTL file as JSON.
     Dump(DumpArgs),
+    /// Describe the given CTL file and its entries in human-readable form.
+    Explain(ExplainArgs),
     /// Retrieve the certificates listed and create a PEM store from them.
     Fetch(FetchArgs),
+    /// Retrieve a past generation of a list, by sequence number, from an archive mirror or directory.
+    FetchList(FetchListArgs),
+    /// Print the CTL-style identifiers of the given certificate(s), and where they'd be downloaded from.
+    Hash(HashArgs),
+    /// List the members of a cabinet file, and which of them look like CTLs.
+    InspectCab(InspectCabArgs),
+    /// Look up a single entry in the given CTL file by its identifier.
+    Lookup(LookupArgs),
+    /// Print the JSON Schema of a kind of JSON document that ctltool emits.
+    Schema(SchemaArgs),
+    /// Retrieve the listed certificates and write them, with the list (and optionally the disallowed list), as a trust snapshot.
+    Snapshot(SnapshotArgs),
+    /// Check whether a snapshot's lists are outdated, by retrieving only their latest sequence numbers.
+    Status(StatusArgs),
+    /// Retrieve the TLS server roots and lay them out as an OpenSSL trust configuration.
+    SyncOpenssl(SyncOpensslArgs),
+    /// Check an existing store's certificates against the given CTL, without downloading anything.
+    VerifyCerts(VerifyCertsArgs),
+}
+
+#[derive(Args, Debug)]
+struct CrossCheckArgs {
+    /// The root CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// The disallowed CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    disallowed: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// The older CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    old: PathBuf,
+
+    /// The newer CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    new: PathBuf,
+
+    /// A certificate cache (as written by `fetch --cache`), to report removed certificates that were renewed or rekeyed
+    ///
+    /// Removed and added entries whose cached certificates have the same subject are
+    /// reported as one renewal (same key) or rekeying (new key). Nothing is downloaded.
+    #[arg(long, value_name = "DIR")]
+    certs: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct CtReportArgs {
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// A JSON file mapping root fingerprints to their CT observations
+    #[arg(long, value_name = "FILE")]
+    dataset: PathBuf,
+
+    /// Only report on the entries with these identifiers
+    #[arg(long = "id", value_name = "ID")]
+    ids: Vec<SubjectId>,
+
+    /// Emit the report as JSON, rather than as a table
+    #[arg(long)]
+    json: bool,
 }
 
 #[derive(Args, Debug)]
 struct DumpArgs {
-    /// The CTL file (in CAB or DER format)
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// Only dump the entries with these identifiers
+    #[arg(long = "id", value_name = "ID")]
+    ids: Vec<SubjectId>,
+
+    /// Also dump each entry's unrecognized attributes, as hexdumps
+    #[arg(long)]
+    full: bool,
+
+    /// Also dump each entry's attributes undecoded, as DER
+    #[arg(long)]
+    raw_attributes: bool,
+
+    /// How to encode identifiers and other binary values
+    #[arg(long, value_enum, default_value_t = Encoding::Hex)]
+    encoding: Encoding,
+
+    /// Dump EKUs by name (where known), rather than by OID
+    #[arg(long)]
+    eku_names: bool,
+
+    /// Represent times as seconds since the Unix epoch, rather than as RFC 3339 timestamps
+    #[arg(long)]
+    epoch_seconds: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+#[derive(Args, Debug)]
+struct ExplainArgs {
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
     input: PathBuf,
+
+    /// Only explain the entries with these identifiers
+    #[arg(long = "id", value_name = "ID")]
+    ids: Vec<SubjectId>,
 }
 
 #[derive(Args, Debug)]
 struct FetchArgs {
-    /// The CTL file (in CAB or DER format)
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
     input: PathBuf,
 
+    /// Skip the entries listing any of these purposes (a name like server-auth, or an OID)
     #[arg(short, long = "purpose", value_name = "PURPOSE")]
-    purposes: Vec<String>,
+    purposes: Vec<Purpose>,
 
-    /// The output file to write to (must not exist)
+    /// Only fetch the entries trusted (and not since distrusted) for a common use case
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+
+    /// Only fetch the entries with these identifiers
+    #[arg(long = "id", value_name = "ID")]
+    ids: Vec<SubjectId>,
+
+    #[command(flatten)]
+    fetch_opts: FetchOpts,
+
+    /// The output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pem)]
+    format: OutputFormat,
+
+    /// Also include the intermediates that the certificates' AIA extensions point to, for chain building
+    ///
+    /// These are written after the PEM bundle's roots, to an `intermediates` subdirectory
+    /// for `der-dir`, or under `intermediates` for `json`. They aren't supported for `p7b`.
+    #[arg(long)]
+    include_intermediates: bool,
+
+    #[command(flatten)]
+    output_opts: OutputOpts,
+
+    /// The output file (or directory, for `der-dir`) to write to
     output: PathBuf,
 }
 
-fn load_ctl(input: PathBuf) -> Result<CertificateTrustList> {
-    let file = File::open(&input)?;
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// A PEM bundle, with a human-readable summary preceding each certificate
+    Pem,
+    /// A directory of DER certificates, each named after its CTL identifier
+    DerDir,
+    /// A certs-only PKCS#7 bundle
+    P7b,
+    /// A single JSON document containing each entry's metadata and base64 DER certificate
+    Json,
+}
 
-    match input.extension().and_then(|s| s.to_str()) {
-        Some("der") | Some("stl") => {
-            CertificateTrustList::from_der(file).context("failed to load CTL from PKCS#7")
+#[derive(Args, Debug)]
+struct FetchListArgs {
+    /// Which list to retrieve
+    #[arg(long, value_enum, default_value = "authroot")]
+    list: ListName,
+
+    /// The sequence number of the generation to retrieve, in hex
+    #[arg(long, value_name = "HEX", value_parser = parse_sequence_number)]
+    sequence: SequenceNumber,
+
+    /// A URL template (with `{sequence}`, and optionally `{list}`, placeholders) or a directory of archived lists
+    #[arg(long, value_name = "TEMPLATE|DIR", value_parser = Archive::parse)]
+    archive: Archive,
+
+    /// Give up on the download after this many seconds [default: 30]
+    #[arg(long, value_name = "SECONDS", value_parser = fetcher::parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// The file to write the list to: as PKCS#7 DER (i.e. an `.stl`), or PEM-armored if it ends in `.pem`
+    output: PathBuf,
+
+    #[command(flatten)]
+    output_opts: OutputOpts,
+}
+
+#[derive(Args, Debug)]
+struct HashArgs {
+    /// The certificate file (PEM, DER, or a PKCS#7 bundle)
+    input: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct InspectCabArgs {
+    /// The cabinet file
+    input: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct LookupArgs {
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// The identifier to look up (e.g. `AB:CD:...` or `abcd...`)
+    id: SubjectId,
+}
+
+#[derive(Args, Debug)]
+struct SchemaArgs {
+    /// The kind of document
+    #[arg(value_enum)]
+    document: SchemaDocument,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SchemaDocument {
+    /// The output of `dump`
+    Dump,
+    /// The output of `lookup`
+    Lookup,
+}
+
+#[derive(Args, Debug)]
+struct SnapshotArgs {
+    /// The root CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// The disallowed CTL file (in CAB, DER, or PEM format), or `-` for standard input to include
+    #[arg(long, value_name = "FILE")]
+    disallowed: Option<PathBuf>,
+
+    #[command(flatten)]
+    fetch_opts: FetchOpts,
+
+    #[command(flatten)]
+    output_opts: OutputOpts,
+
+    /// The file to write the snapshot (as JSON) to
+    output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct StatusArgs {
+    /// The snapshot to check (as written by `ctltool snapshot`)
+    snapshot: PathBuf,
+
+    /// The URL to retrieve the lists' sequence files (`authrootseq.txt`, `disallowedcertseq.txt`) from
+    #[arg(long, value_name = "URL", default_value = status::CTL_BASE_URL)]
+    base_url: String,
+
+    /// Give up on each request after this many seconds [default: 30]
+    #[arg(long, value_name = "SECONDS", value_parser = fetcher::parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Fail if newer data is available, or if the latest sequence numbers can't be retrieved
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(Args, Debug)]
+struct SyncOpensslArgs {
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// The directory to write `ca-bundle.pem` and the hashed `certs/` directory into
+    output: PathBuf,
+
+    #[command(flatten)]
+    fetch_opts: FetchOpts,
+
+    #[command(flatten)]
+    output_opts: OutputOpts,
+}
+
+#[derive(Args, Debug)]
+struct VerifyCertsArgs {
+    /// The CTL file (in CAB, DER, or PEM format), or `-` for standard input
+    input: PathBuf,
+
+    /// The store to check: a PEM, DER, PKCS#7, or JSON bundle, or a directory of certificates
+    certs: PathBuf,
+}
+
+/// Loads a CTL from `input`, in the format its extension indicates, or from standard
+/// input if it's `-`, in which case the format is detected from the contents.
+fn load_ctl(input: PathBuf, warnings: WarningFormat) -> Result<CertificateTrustList> {
+    let ctl = if input.as_os_str() == "-" {
+        let mut contents = Vec::new();
+        stdin().lock().read_to_end(&mut contents)?;
+        parse_ctl(&contents)
+            .map(|(ctl, _)| ctl)
+            .context("failed to load CTL from standard input")
+    } else {
+        let file = File::open(&input)?;
+        match input.extension().and_then(|s| s.to_str()) {
+            Some("der") | Some("stl") => {
+                CertificateTrustList::from_der(file).context("failed to load CTL from PKCS#7")
+            }
+            Some("pem") => CertificateTrustList::from_pem(fs::read(&input)?)
+                .context("failed to load CTL from PEM-armored PKCS#7"),
+            Some("cab") => {
+                CertificateTrustList::from_cab(file).context("failed to load CTL from cabinet")
+            }
+            Some(other) => Err(anyhow!("unexpected file extension: {}", other)),
+            None => Err(anyhow!("missing or invalid file extension")),
         }
-        Some("cab") => {
-            let mut cabinet = cab::Cabinet::new(file).context("failed to parse cabinet")?;
-
-            // For the time being, we only bother to look for authroot.stl.
-            // If you have a disallowedcertstl.cab, you should just extract it first.
-            CertificateTrustList::from_der(
-                cabinet
-                    .read_file("authroot.stl")
-                    .context("failed to extract STL from cabinet")?,
-            )
-            .context("failed to load CTL from PKCS#7")
+    }?;
+
+    report_warnings(&ctl, warnings);
+    Ok(ctl)
+}
+
+/// Parses a CTL in any of the formats that lists are found in (a cabinet, PKCS#7 DER,
+/// or PEM-armored PKCS#7), as detected from its contents, returning it along with its
+/// PKCS#7 DER.
+fn parse_ctl(contents: &[u8]) -> Result<(CertificateTrustList, Vec<u8>)> {
+    let der = if contents.starts_with(b"MSCF") {
+        RawCtl::from_cab(Cursor::new(contents))?.as_bytes().to_vec()
+    } else if contents.starts_with(b"-----BEGIN") {
+        pem::decode_vec(contents)
+            .map_err(x509_cert::der::Error::from)?
+            .1
+    } else {
+        contents.to_vec()
+    };
+
+    let ctl = CertificateTrustList::from_der_slice(&der)?;
+    Ok((ctl, der))
+}
+
+/// Reports any non-fatal problems with the CTL on stderr.
+fn report_warnings(ctl: &CertificateTrustList, format: WarningFormat) {
+    for warning in ctl.warnings() {
+        match format {
+            WarningFormat::Text => eprintln!("warning: {warning}"),
+            WarningFormat::Json => eprintln!(
+                "{}",
+                schema::versioned(serde_json::json!({
+                    "warning": warning.to_string(),
+                    "subject": warning.subject().to_string(),
+                }))
+            ),
         }
-        Some(other) => Err(anyhow!("unexpected file extension: {}", other)),
-        None => Err(anyhow!("missing or invalid file extension")),
     }
 }
 
-fn dump(args: DumpArgs) -> Result<()> {
-    let ctl = load_ctl(args.input)?;
-    let entries = ctl.trusted_subjects.iter().flatten().collect::<Vec<_>>();
+/// Returns the CTL's entries, limited to those with the given identifiers (if any).
+fn select_entries<'a>(
+    ctl: &'a CertificateTrustList,
+    ids: &'a [SubjectId],
+) -> impl Iterator<Item = &'a TrustedSubject> + Clone + 'a {
+    ctl.trusted_subjects
+        .iter()
+        .flatten()
+        .filter(move |entry| ids.is_empty() || ids.iter().any(|id| entry.has_id(id)))
+}
+
+fn cross_check(args: CrossCheckArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    let disallowed = load_ctl(args.disallowed, warnings)?;
+    if disallowed.kind() != CtlKind::Disallowed {
+        return Err(anyhow!("the second list isn't a disallowed list"));
+    }
+    if ctl.subject_algorithm.oid != disallowed.subject_algorithm.oid {
+        eprintln!(
+            "warning: the lists identify entries with different algorithms, so none will match"
+        );
+    }
 
-    serde_json::to_writer(stdout(), &entries)?;
+    let inconsistencies = ctl.cross_check(&disallowed);
+    for inconsistency in &inconsistencies {
+        println!(
+            "{inconsistency}: {}",
+            explain::ekus_list(inconsistency.ekus())
+        );
+    }
+    eprintln!("{} inconsistencies", inconsistencies.len());
+
+    if !inconsistencies.is_empty() {
+        return Err(anyhow!("the lists are inconsistent"));
+    }
 
     Ok(())
 }
 
-fn fetch(args: FetchArgs) -> Result<()> {
-    let ctl = load_ctl(args.input)?;
-    let mut output = File::options()
-        .write(true)
-        .create_new(true)
-        .open(&args.output)
-        .with_context(|| format!("refusing to write to an extant file: {:?}", &args.output))?;
+fn diff(args: DiffArgs, warnings: WarningFormat) -> Result<()> {
+    let old = load_ctl(args.old, warnings)?;
+    let new = load_ctl(args.new, warnings)?;
+    if old.kind() != new.kind() {
+        eprintln!(
+            "warning: comparing different kinds of list ({:?} and {:?})",
+            old.kind(),
+            new.kind()
+        );
+    }
 
-    let purposes: HashSet<_> = args
-        .purposes
-        .iter()
-        .map(|p| ObjectIdentifier::new(p))
-        .collect::<Result<HashSet<_>, _>>()?;
-
-    let entries = ctl.trusted_subjects.iter().flatten().collect::<Vec<_>>();
-
-    let progress = ProgressBar::new(entries.len() as u64).with_style(ProgressStyle::with_template(
-        "[{elapsed_precise}] {wide_bar:.cyan/blue} {pos:>7}/{len:7} {msg}",
-    )?);
-    for entry in entries.iter().progress_with(progress.clone()) {
-        let ekus = entry
-            .extended_key_usages()
-            .collect::<Result<HashSet<_>, _>>()?;
-
-        // If the user supplied purposes to filter by and any of them intersect with
-        // the cert's EKUs, skip it.
-        if !purposes.is_empty() && !ekus.intersection(&purposes).collect::<Vec<_>>().is_empty() {
-            continue;
+    let mut cache = match &args.certs {
+        Some(dir) if !dir.is_dir() => {
+            return Err(anyhow!("certificate cache isn't a directory: {dir:?}"))
         }
+        Some(dir) => Some(DirCache::new(dir)?),
+        None => None,
+    };
+    let diff = diff::diff(
+        &old,
+        &new,
+        cache
+            .as_mut()
+            .map(|cache| cache as &mut dyn CertificateCache),
+    )?;
+
+    diff.report(stdout().lock())?;
+    eprintln!(
+        "{} added, {} removed, {} changed, {} renewed or rekeyed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.replaced.len()
+    );
+    if diff.unresolved > 0 {
+        eprintln!(
+            "{} added or removed entries had no cached certificate",
+            diff.unresolved
+        );
+    }
+    Ok(())
+}
+
+fn ct_report(args: CtReportArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    let contents = fs::read(&args.dataset)
+        .with_context(|| format!("failed to read CT dataset: {:?}", args.dataset))?;
+    let dataset = CtDataset::from_slice(&contents)?;
+
+    let mut roots = vec![];
+    for entry in select_entries(&ctl, &args.ids) {
+        roots.push((entry, entry.friendly_name()?, dataset.lookup(entry)?));
+    }
+    let observed = roots
+        .iter()
+        .filter(|(_, _, observation)| observation.is_some_and(|o| o.certificates > 0))
+        .count();
+
+    if args.json {
+        let roots = roots
+            .iter()
+            .map(|(entry, name, observation)| {
+                serde_json::json!({
+                    "identifier": entry.cert_id(),
+                    "friendly_name": name,
+                    "certificates": observation.map(|o| o.certificates),
+                    "last_seen": observation.and_then(|o| o.last_seen.as_ref()),
+                })
+            })
+            .collect::<Vec<_>>();
+        let report = schema::versioned(serde_json::json!({
+            "roots": roots,
+            "observed": observed,
+            "total": roots.len(),
+        }));
+        serde_json::to_writer(stdout(), &report)?;
+        return Ok(());
+    }
+
+    println!(
+        "{:<40}  {:>12}  {:<20}  NAME",
+        "IDENTIFIER", "CERTIFICATES", "LAST SEEN"
+    );
+    for (entry, name, observation) in &roots {
+        let (certificates, last_seen) = match observation {
+            Some(o) => (
+                o.certificates.to_string(),
+                o.last_seen.as_deref().unwrap_or("-"),
+            ),
+            None => ("unknown".into(), "-"),
+        };
+        println!(
+            "{:<40}  {certificates:>12}  {last_seen:<20}  {}",
+            entry.cert_id(),
+            name.as_deref().unwrap_or("-")
+        );
+    }
+    let unknown = roots.iter().filter(|(_, _, o)| o.is_none()).count();
+    println!(
+        "{observed} of {} roots have certificates observed in CT ({unknown} not in the dataset)",
+        roots.len()
+    );
+
+    Ok(())
+}
+
+fn dump(args: DumpArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    let entries = select_entries(&ctl, &args.ids);
+    let options = SerializeOptions {
+        raw_attributes: args.raw_attributes,
+        binary_encoding: match args.encoding {
+            Encoding::Hex => BinaryEncoding::Hex,
+            Encoding::Base64 => BinaryEncoding::Base64,
+        },
+        eku_format: if args.eku_names {
+            EkuFormat::Name
+        } else {
+            EkuFormat::Oid
+        },
+        time_format: if args.epoch_seconds {
+            TimeFormat::UnixSeconds
+        } else {
+            TimeFormat::Rfc3339
+        },
+    };
+
+    let mut out = BufWriter::new(stdout().lock());
+    serde_json::to_writer(
+        &mut out,
+        &DumpDocument::new(&ctl, entries, args.full, options)?,
+    )?;
+    out.flush()?;
+
+    Ok(())
+}
+
+fn explain(args: ExplainArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    explain::explain_list(&ctl)?;
+
+    for entry in select_entries(&ctl, &args.ids) {
+        println!();
+        explain::explain_entry(entry);
+    }
+
+    Ok(())
+}
+
+fn parse_sequence_number(hex: &str) -> Result<SequenceNumber> {
+    status::parse_sequence_number(hex).ok_or_else(|| anyhow!("invalid sequence number: {hex:?}"))
+}
+
+fn fetch_list(args: FetchListArgs, http: &HttpOpts) -> Result<()> {
+    let http = Http::new(http, args.timeout, None)?;
+    let (_, der) = args.archive.retrieve(&http, args.list, &args.sequence)?;
+    let contents = match args.output.extension().and_then(|s| s.to_str()) {
+        Some("pem") => RawCtl::new(der)?.to_pem(LineEnding::LF)?.into_bytes(),
+        _ => der,
+    };
+
+    let output = args.output_opts.create_file(&args.output)?;
+    output.file().write_all(&contents)?;
+    output.commit()
+}
+
+fn hash(args: HashArgs) -> Result<()> {
+    let contents = fs::read(&args.input)?;
+    let certs = read_certificates(&contents)
+        .with_context(|| format!("failed to read certificates from {:?}", args.input))?;
+
+    for cert in certs {
+        let der = cert.to_der()?;
+        let sha1 = SubjectAlgorithm::Sha1.identify(&der);
+
+        println!("subject: {}", cert.tbs_certificate.subject);
+        println!("sha1:    {sha1}");
+        println!("sha256:  {}", SubjectAlgorithm::Sha256.identify(&der));
+        println!("url:     {}", fetcher::cdn_url(&sha1));
+    }
+
+    Ok(())
+}
+
+fn inspect_cab(args: InspectCabArgs) -> Result<()> {
+    let file = File::open(&args.input)?;
+    let members = windows_ctl::cabinet_members(file).context("failed to read cabinet")?;
+
+    println!("{:<32} {:>10}  {:<20} CTL", "NAME", "SIZE", "MODIFIED");
+    for member in members {
+        let modified = member
+            .modified
+            .map(|dt| dt.to_string())
+            .unwrap_or_else(|| "-".into());
+        println!(
+            "{:<32} {:>10}  {:<20} {}",
+            member.name,
+            member.size,
+            modified,
+            if member.is_ctl { "yes" } else { "no" }
+        );
+    }
+
+    Ok(())
+}
+
+fn lookup(args: LookupArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    let entry = select_entries(&ctl, std::slice::from_ref(&args.id))
+        .next()
+        .ok_or_else(|| anyhow!("no entry with identifier {}", args.id))?;
+
+    serde_json::to_writer(stdout(), &schema::versioned(serde_json::to_value(entry)?))?;
+
+    Ok(())
+}
+
+/// Runs `builder`, reporting progress on stderr.
+fn build_store(builder: StoreBuilder) -> Result<StoreReport> {
+    let mut progress = Progress::new(0)?;
+    let report = builder
+        .observe(|p| {
+            progress.set_len(p.total);
+            progress.set_message(p.entry.cert_id().to_string());
+            progress.inc();
+        })
+        .build()?;
+    progress.finish();
+
+    Ok(report)
+}
 
-        let id = hex::encode(entry.cert_id());
-        let url = format!(
-            "http://www.download.windowsupdate.com/msdownload/update/v3/static/trustedr/en/{id}.crt"
+fn fetch(args: FetchArgs, http: &HttpOpts, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    if ctl.kind() == CtlKind::Disallowed {
+        eprintln!(
+            "note: this is a disallowed list, so its certificates are distrusted, not trusted"
         );
+    }
+    if args.include_intermediates && matches!(args.format, OutputFormat::P7b) {
+        return Err(anyhow!(
+            "--include-intermediates isn't supported with --format p7b, which can't keep them apart from the roots"
+        ));
+    }
+    let mut fetcher = Fetcher::new(&args.fetch_opts, http)?;
+    let http = fetcher.http();
+    let mut recorder = Recorder::default();
+
+    let purposes = args.purposes.iter().map(Purpose::oid).collect::<MetaEku>();
+
+    let builder = fetcher
+        .store_builder(&ctl)
+        .filter(|entry| Ok(args.ids.is_empty() || args.ids.iter().any(|id| entry.has_id(id))))
+        .filter(|entry| {
+            let ekus = entry
+                .extended_key_usages()
+                .collect::<Result<MetaEku, _>>()?;
+
+            // If the user supplied purposes to filter by and any of them intersect with
+            // the cert's EKUs, skip it.
+            Ok(purposes.is_empty() || !ekus.intersects(&purposes))
+        })
+        .filter(|entry| args.preset.map_or(Ok(true), |preset| preset.admits(entry)));
+    // JSON bundles chase the intermediates themselves, since they need them before finishing.
+    let builder = match args.format {
+        OutputFormat::Pem | OutputFormat::DerDir if args.include_intermediates => {
+            builder.writer(&mut recorder)
+        }
+        _ => builder,
+    };
+
+    let report = match args.format {
+        OutputFormat::Pem => {
+            let output = args.output_opts.create_file(&args.output)?;
+            let report = build_store(builder.writer(PemWriter::annotated(output.file())))?;
+            if args.include_intermediates {
+                let certs = intermediates::chase(&http, &recorder.certs);
+                intermediates::write_pem(output.file(), &certs)?;
+            }
+            output.commit()?;
+            report
+        }
+        OutputFormat::DerDir => {
+            let output = args.output_opts.create_dir(&args.output)?;
+            let report = build_store(builder.writer(DerDirWriter::new(output.path())))?;
+            if args.include_intermediates {
+                let certs = intermediates::chase(&http, &recorder.certs);
+                intermediates::write_der_dir(output.path(), &certs)?;
+            }
+            output.commit()?;
+            report
+        }
+        OutputFormat::P7b => {
+            let output = args.output_opts.create_file(&args.output)?;
+            let report = build_store(builder.writer(Pkcs7Writer::new(output.file())))?;
+            output.commit()?;
+            report
+        }
+        OutputFormat::Json => {
+            let output = args.output_opts.create_file(&args.output)?;
+            let writer = match args.include_intermediates {
+                true => JsonWriter::new(output.file()).with_intermediates(http),
+                false => JsonWriter::new(output.file()),
+            };
+            let report = build_store(builder.writer(writer))?;
+            output.commit()?;
+            report
+        }
+    };
+    fetcher.finish(&report)?;
+
+    Ok(())
+}
 
-        progress.set_message(id);
+fn print_schema(args: SchemaArgs) -> Result<()> {
+    let kind = match args.document {
+        SchemaDocument::Dump => schema::Document::Dump,
+        SchemaDocument::Lookup => schema::Document::Lookup,
+    };
+    let schema = schema::json_schema(kind).ok_or_else(|| anyhow!("no schema for {kind:?}"))?;
+    serde_json::to_writer_pretty(stdout(), &schema)?;
+    println!();
 
-        let resp = reqwest::blocking::get(&url)?;
-        if !resp.status().is_success() {
-            return Err(anyhow!(
-                "cert retrieval failed: {} returned {}",
-                &url,
-                resp.status().as_u16()
-            ));
+    Ok(())
+}
+
+fn snapshot(args: SnapshotArgs, http: &HttpOpts, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    if ctl.kind() == CtlKind::Disallowed {
+        return Err(anyhow!(
+            "the snapshot's root list is a disallowed list; pass it with --disallowed instead"
+        ));
+    }
+    let disallowed = args
+        .disallowed
+        .map(|path| load_ctl(path, warnings))
+        .transpose()?;
+    if disallowed
+        .as_ref()
+        .is_some_and(|list| list.kind() != CtlKind::Disallowed)
+    {
+        return Err(anyhow!("--disallowed isn't a disallowed list"));
+    }
+    let mut fetcher = Fetcher::new(&args.fetch_opts, http)?;
+
+    let mut snapshot = TrustSnapshot::new(ctl.clone(), disallowed);
+    let report = build_store(fetcher.store_builder(&ctl).writer(&mut snapshot))?;
+
+    let output = args.output_opts.create_file(&args.output)?;
+    let mut writer = BufWriter::new(output.file());
+    serde_json::to_writer(&mut writer, &snapshot)?;
+    writer.flush()?;
+    drop(writer);
+    output.commit()?;
+    fetcher.finish(&report)?;
+
+    Ok(())
+}
+
+fn status(args: StatusArgs, http: &HttpOpts) -> Result<()> {
+    let snapshot: TrustSnapshot = serde_json::from_reader(BufReader::new(
+        File::open(&args.snapshot)
+            .with_context(|| format!("failed to open snapshot: {:?}", &args.snapshot))?,
+    ))
+    .context("invalid snapshot")?;
+
+    let http = Http::new(http, args.timeout, None)?;
+    let statuses = status::check(&http, &args.base_url, &snapshot);
+    status::report(stdout(), &statuses, SystemTime::now())?;
+
+    if args.check {
+        if let Some(status) = statuses.iter().find(|status| status.latest.is_err()) {
+            return Err(anyhow!("couldn't check the {} list", status.name));
+        }
+        if let Some(status) = statuses.iter().find(|status| status.is_outdated()) {
+            return Err(anyhow!("the snapshot's {} list is outdated", status.name));
         }
+    }
+
+    Ok(())
+}
+
+fn sync_openssl(args: SyncOpensslArgs, http: &HttpOpts, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    if ctl.kind() == CtlKind::Disallowed {
+        return Err(anyhow!(
+            "refusing to configure a disallowed list's certificates as trusted roots"
+        ));
+    }
+    let mut fetcher = Fetcher::new(&args.fetch_opts, http)?;
+
+    fs::create_dir_all(&args.output)
+        .with_context(|| format!("failed to create output directory: {:?}", &args.output))?;
+
+    let certs_dir = args.output.join("certs");
+    let certs_output = args.output_opts.create_dir(&certs_dir)?;
+
+    let bundle_path = args.output.join("ca-bundle.pem");
+    let bundle_output = args.output_opts.create_file(&bundle_path)?;
+
+    let report = build_store(
+        fetcher
+            .store_builder(&ctl)
+            .filter(|entry| Preset::TlsServer.admits(entry))
+            .writer(PemWriter::new(bundle_output.file()))
+            .writer(HashedDirWriter::new(certs_output.path())),
+    )?;
+    bundle_output.commit()?;
+    certs_output.commit()?;
+    fetcher.finish(&report)?;
+
+    let bundle_path = bundle_path.canonicalize()?;
+    let certs_dir = certs_dir.canonicalize()?;
+    println!("export SSL_CERT_FILE={}", bundle_path.display());
+    println!("export SSL_CERT_DIR={}", certs_dir.display());
+
+    Ok(())
+}
+
+/// Reads every certificate in the given store, which is either a single bundle
+/// or a directory of them.
+fn load_certs(path: &Path) -> Result<Vec<Certificate>> {
+    let read = |path: &Path| -> Result<Vec<Certificate>> {
+        let contents = fs::read(path)?;
+        let certs = if contents.trim_ascii_start().starts_with(b"{") {
+            read_bundle(&contents)
+        } else {
+            Ok(read_certificates(&contents)?)
+        };
+        certs.with_context(|| format!("failed to read certificates from {path:?}"))
+    };
+
+    if !path.is_dir() {
+        return read(path);
+    }
+
+    let mut paths = fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut certs = vec![];
+    for path in paths.iter().filter(|path| path.is_file()) {
+        certs.extend(read(path)?);
+    }
+    Ok(certs)
+}
+
+fn verify_certs(args: VerifyCertsArgs, warnings: WarningFormat) -> Result<()> {
+    let ctl = load_ctl(args.input, warnings)?;
+    let certs = load_certs(&args.certs)?;
+    let audit = TrustStore::new(&ctl).audit(certs);
+
+    for (entry, _) in &audit.matched {
+        println!("ok {}", entry.cert_id());
+    }
+    for (entry, _, err) in &audit.mismatched {
+        println!("mismatch {}: {err}", entry.cert_id());
+    }
+    for cert in &audit.extra {
+        println!("extra {}", cert.tbs_certificate.subject);
+    }
+    for entry in &audit.missing {
+        println!("missing {}", entry.cert_id());
+    }
+
+    eprintln!(
+        "{} matched, {} mismatched, {} extra, {} missing",
+        audit.matched.len(),
+        audit.mismatched.len(),
+        audit.extra.len(),
+        audit.missing.len()
+    );
 
-        // TODO: verify bytes against cert_id here.
-        let contents = resp.bytes()?;
-        let cert = Certificate::from_der(&contents).context("failed to load X.509")?;
-        let tbs_cert = &cert.tbs_certificate;
-
-        writeln!(output, "Serial: {}", tbs_cert.serial_number)?;
-        writeln!(output, "Issuer: {}", tbs_cert.issuer)?;
-        writeln!(output, "Subject: {}", tbs_cert.subject)?;
-        writeln!(output, "Not Before: {}", tbs_cert.validity.not_before)?;
-        writeln!(output, "Not After: {}", tbs_cert.validity.not_after)?;
-        writeln!(output, "{}", cert.to_pem(LineEnding::LF)?)?;
+    if !audit.is_clean() {
+        return Err(anyhow!("store doesn't match the CTL"));
     }
 
     Ok(())
@@ -0,0 +1,343 @@
+//! Atomic, permission-aware creation of output files and directories.
+//!
+//! Outputs are staged under a temporary name next to their destination and
+//! renamed into place only once they've been completely written, so that an
+//! interrupted or failed run never leaves a half-written trust store behind.
+//!
+//! When an existing output is replaced, the certificates that changed are
+//! reported, and the previous version can optionally be kept as a timestamped backup.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Args;
+use sha1::{Digest, Sha1};
+use x509_cert::{
+    der::{DateTime, Decode, Encode},
+    Certificate,
+};
+
+/// Options controlling how outputs are written.
+#[derive(Args, Debug)]
+pub struct OutputOpts {
+    /// Replace existing outputs, rather than refusing to write to them
+    #[arg(long)]
+    force: bool,
+
+    /// The (octal) permissions to give output files; directories additionally get execute bits
+    #[arg(long, value_name = "MODE", default_value = "644", value_parser = parse_mode)]
+    mode: u32,
+
+    /// When replacing an output, keep up to this many timestamped backups of previous versions
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    backups: usize,
+}
+
+fn parse_mode(mode: &str) -> Result<u32> {
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| anyhow!("invalid mode: {mode:?} (expected octal, e.g. 644)"))
+}
+
+impl OutputOpts {
+    /// Begins writing a file that will eventually be moved to `dest`.
+    pub fn create_file(&self, dest: &Path) -> Result<StagedFile> {
+        self.check_dest(dest)?;
+
+        let staging = staging_path(dest, "tmp");
+        let file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&staging)
+            .with_context(|| format!("failed to create {staging:?}"))?;
+
+        Ok(StagedFile {
+            file,
+            staging,
+            dest: dest.into(),
+            mode: self.mode,
+            backups: self.backups,
+            committed: false,
+        })
+    }
+
+    /// Begins populating a directory that will eventually be moved to `dest`.
+    pub fn create_dir(&self, dest: &Path) -> Result<StagedDir> {
+        self.check_dest(dest)?;
+
+        let staging = staging_path(dest, "tmp");
+        fs::create_dir(&staging).with_context(|| format!("failed to create {staging:?}"))?;
+
+        Ok(StagedDir {
+            staging,
+            dest: dest.into(),
+            mode: self.mode,
+            backups: self.backups,
+            committed: false,
+        })
+    }
+
+    fn check_dest(&self, dest: &Path) -> Result<()> {
+        if dest.exists() && !self.force {
+            return Err(anyhow!(
+                "refusing to write to an extant path: {dest:?} (use --force to replace it)"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns a hidden sibling path of `dest`, unique to this process.
+fn staging_path(dest: &Path, kind: &str) -> PathBuf {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dest.with_file_name(format!(".{name}.{kind}-{}", std::process::id()))
+}
+
+/// Returns the path to back `dest` up to, e.g. `ca-bundle.pem.20240101T000000Z.bak`.
+fn backup_path(dest: &Path) -> Result<PathBuf> {
+    let now = DateTime::from_system_time(SystemTime::now())?;
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(dest.with_file_name(format!(
+        "{name}.{:04}{:02}{:02}T{:02}{:02}{:02}Z.bak",
+        now.year(),
+        now.month(),
+        now.day(),
+        now.hour(),
+        now.minutes(),
+        now.seconds()
+    )))
+}
+
+/// Removes all but the newest `keep` backups of `dest`.
+fn prune_backups(dest: &Path, keep: usize) -> Result<()> {
+    let Some(name) = dest.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return Ok(());
+    };
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut backups = fs::read_dir(parent)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(&format!("{name}.")) && n.ends_with(".bak"))
+        })
+        .collect::<Vec<_>>();
+
+    // Timestamps sort lexicographically, so the oldest backups come first.
+    backups.sort();
+    for old in backups.iter().take(backups.len().saturating_sub(keep)) {
+        if old.is_dir() {
+            fs::remove_dir_all(old)?;
+        } else {
+            fs::remove_file(old)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads every certificate in the given output (a PEM bundle, or a directory of
+/// PEM or DER certificates), keyed by SHA-1 fingerprint.
+///
+/// Returns `None` for outputs whose format isn't understood.
+fn load_certificates(path: &Path) -> Option<BTreeMap<String, Certificate>> {
+    let mut certs = vec![];
+    if path.is_dir() {
+        for entry in fs::read_dir(path).ok()? {
+            let path = entry.ok()?.path();
+            if path.is_dir() {
+                continue;
+            }
+            let contents = fs::read(path).ok()?;
+            certs.extend(
+                Certificate::load_pem_chain(&contents)
+                    .or_else(|_| Certificate::from_der(&contents).map(|cert| vec![cert]))
+                    .ok()?,
+            );
+        }
+    } else {
+        certs = Certificate::load_pem_chain(&fs::read(path).ok()?).ok()?;
+    }
+
+    certs
+        .into_iter()
+        .map(|cert| Some((hex::encode(Sha1::digest(cert.to_der().ok()?)), cert)))
+        .collect()
+}
+
+/// Reports the certificates that differ between the `old` and `new` versions of an output.
+fn report_changes(old: &Path, new: &Path, dest: &Path) {
+    let (Some(old), Some(new)) = (load_certificates(old), load_certificates(new)) else {
+        eprintln!("note: replacing {dest:?} (changes can't be summarized for this format)");
+        return;
+    };
+
+    eprintln!("changes to {dest:?}:");
+    let mut changed = false;
+    for (fingerprint, cert) in new.iter().filter(|(fp, _)| !old.contains_key(*fp)) {
+        eprintln!("+ {fingerprint} {}", cert.tbs_certificate.subject);
+        changed = true;
+    }
+    for (fingerprint, cert) in old.iter().filter(|(fp, _)| !new.contains_key(*fp)) {
+        eprintln!("- {fingerprint} {}", cert.tbs_certificate.subject);
+        changed = true;
+    }
+    if !changed {
+        eprintln!("  (no certificates added or removed)");
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("failed to set permissions on {path:?}"))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Sets the permissions of everything in `dir` (recursively) to `mode`, and of `dir`
+/// and its subdirectories to `mode` plus execute bits.
+fn set_dir_mode(dir: &Path, mode: u32) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            set_dir_mode(&path, mode)?;
+        } else {
+            set_mode(&path, mode)?;
+        }
+    }
+    // Directories need to be searchable by whoever can read their contents.
+    set_mode(dir, mode | ((mode & 0o444) >> 2))
+}
+
+/// An output file being written under a temporary name.
+pub struct StagedFile {
+    file: File,
+    staging: PathBuf,
+    dest: PathBuf,
+    mode: u32,
+    backups: usize,
+    committed: bool,
+}
+
+impl StagedFile {
+    /// Returns the file to write the output into.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Moves the completed file into place.
+    pub fn commit(mut self) -> Result<()> {
+        self.file.sync_all()?;
+        set_mode(&self.staging, self.mode)?;
+
+        let replacing = self.dest.exists();
+        if replacing {
+            report_changes(&self.dest, &self.staging, &self.dest);
+            if self.backups > 0 {
+                // Hard-link the backup so that the rename below remains atomic.
+                let backup = backup_path(&self.dest)?;
+                fs::hard_link(&self.dest, &backup)
+                    .or_else(|_| fs::copy(&self.dest, &backup).map(|_| ()))
+                    .with_context(|| format!("failed to back up {:?}", self.dest))?;
+            }
+        }
+
+        fs::rename(&self.staging, &self.dest)
+            .with_context(|| format!("failed to move output into place: {:?}", self.dest))?;
+        self.committed = true;
+
+        if replacing && self.backups > 0 {
+            prune_backups(&self.dest, self.backups)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.staging);
+        }
+    }
+}
+
+/// An output directory being populated under a temporary name.
+pub struct StagedDir {
+    staging: PathBuf,
+    dest: PathBuf,
+    mode: u32,
+    backups: usize,
+    committed: bool,
+}
+
+impl StagedDir {
+    /// Returns the directory to populate.
+    pub fn path(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Moves the completed directory into place, replacing any existing one.
+    pub fn commit(mut self) -> Result<()> {
+        set_dir_mode(&self.staging, self.mode)?;
+
+        // Directories can't be atomically replaced, so the best we can do is swap
+        // the old one out of the way immediately before moving the new one in.
+        let replacing = self.dest.exists();
+        let old = if self.backups > 0 {
+            backup_path(&self.dest)?
+        } else {
+            staging_path(&self.dest, "old")
+        };
+        if replacing {
+            report_changes(&self.dest, &self.staging, &self.dest);
+            fs::rename(&self.dest, &old)
+                .with_context(|| format!("failed to move aside {:?}", self.dest))?;
+        }
+        fs::rename(&self.staging, &self.dest)
+            .with_context(|| format!("failed to move output into place: {:?}", self.dest))?;
+        self.committed = true;
+
+        if replacing {
+            if self.backups > 0 {
+                prune_backups(&self.dest, self.backups)?;
+            } else {
+                fs::remove_dir_all(&old).with_context(|| format!("failed to remove {old:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedDir {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.staging);
+        }
+    }
+}
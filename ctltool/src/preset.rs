@@ -0,0 +1,39 @@
+//! Well-known purposes to build stores for.
+
+use clap::ValueEnum;
+use windows_ctl::{CtlError, Purpose, TrustedSubject};
+
+/// A common use case for a store, expanding to the purpose it requires and the
+/// distrust policy that goes along with it.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Preset {
+    /// Roots trusted for TLS server authentication
+    TlsServer,
+    /// Roots trusted for TLS client authentication
+    TlsClient,
+    /// Roots trusted for code signing
+    CodeSigning,
+    /// Roots trusted for S/MIME
+    Email,
+    /// Roots trusted for timestamping
+    Timestamping,
+}
+
+impl Preset {
+    /// Returns the purpose that this preset requires.
+    pub fn purpose(&self) -> Purpose {
+        match self {
+            Preset::TlsServer => Purpose::ServerAuth,
+            Preset::TlsClient => Purpose::ClientAuth,
+            Preset::CodeSigning => Purpose::CodeSigning,
+            Preset::Email => Purpose::EmailProtection,
+            Preset::Timestamping => Purpose::TimeStamping,
+        }
+    }
+
+    /// Returns whether `entry` belongs in a store for this preset: that is, whether
+    /// it's trusted for the preset's purpose and hasn't since been distrusted for it.
+    pub fn admits(&self, entry: &TrustedSubject) -> Result<bool, CtlError> {
+        entry.is_trusted_for(self.purpose())
+    }
+}
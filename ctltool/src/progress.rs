@@ -0,0 +1,112 @@
+//! Progress reporting that degrades gracefully when not attached to a terminal.
+
+use std::{
+    io::{stderr, IsTerminal},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// How often to emit a progress line when not attached to a terminal.
+const LINE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reports progress through a list of `len` items.
+///
+/// On a terminal this is an `indicatif` progress bar. Elsewhere (e.g. in CI logs)
+/// it periodically emits machine-parseable `key=value` lines to stderr instead,
+/// like:
+///
+/// ```text
+/// progress pos=120 len=400 elapsed=5.0s id=cabd2a79a1076a31f21d253635cb039d4329a5e8
+/// ```
+pub enum Progress {
+    Bar(ProgressBar),
+    Lines {
+        len: u64,
+        pos: u64,
+        message: String,
+        started: Instant,
+        last_emitted: Instant,
+    },
+}
+
+impl Progress {
+    /// Creates a new progress reporter for `len` items, appropriate for stderr.
+    pub fn new(len: usize) -> Result<Self> {
+        let len = len as u64;
+
+        if stderr().is_terminal() {
+            Ok(Self::Bar(ProgressBar::new(len).with_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] {wide_bar:.cyan/blue} {pos:>7}/{len:7} {msg}",
+                )?,
+            )))
+        } else {
+            let now = Instant::now();
+            Ok(Self::Lines {
+                len,
+                pos: 0,
+                message: String::new(),
+                started: now,
+                last_emitted: now,
+            })
+        }
+    }
+
+    /// Sets the number of items, if it wasn't known up front.
+    pub fn set_len(&mut self, new_len: usize) {
+        match self {
+            Self::Bar(bar) => bar.set_length(new_len as u64),
+            Self::Lines { len, .. } => *len = new_len as u64,
+        }
+    }
+
+    /// Sets the message describing the most recently processed item.
+    pub fn set_message(&mut self, msg: impl Into<String>) {
+        match self {
+            Self::Bar(bar) => bar.set_message(msg.into()),
+            Self::Lines { message, .. } => *message = msg.into(),
+        }
+    }
+
+    /// Marks the current item as done.
+    pub fn inc(&mut self) {
+        match self {
+            Self::Bar(bar) => bar.inc(1),
+            Self::Lines {
+                pos, last_emitted, ..
+            } => {
+                *pos += 1;
+                if last_emitted.elapsed() >= LINE_INTERVAL {
+                    *last_emitted = Instant::now();
+                    self.emit_line();
+                }
+            }
+        }
+    }
+
+    /// Marks all items as done.
+    pub fn finish(&mut self) {
+        match self {
+            Self::Bar(bar) => bar.finish(),
+            Self::Lines { .. } => self.emit_line(),
+        }
+    }
+
+    fn emit_line(&self) {
+        if let Self::Lines {
+            len,
+            pos,
+            message,
+            started,
+            ..
+        } = self
+        {
+            eprintln!(
+                "progress pos={pos} len={len} elapsed={:.1}s id={message}",
+                started.elapsed().as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Versioning for the JSON that `ctltool` emits.
+//!
+//! Every JSON document (or, for line-oriented output, every line) carries a top-level
+//! `schema_version`. Within a version, fields may be added but are never removed,
+//! renamed, or given a different meaning; any such change bumps [`SCHEMA_VERSION`].
+//!
+//! Output from before versioning was introduced is treated as version 0, which differs
+//! from version 1 only in lacking `schema_version` (and, for `dump`, in being a bare
+//! array of entries rather than an object with an `entries` array).
+
+use anyhow::{anyhow, Result};
+use schemars::schema_for;
+use serde_json::{json, Value};
+use windows_ctl::{CertificateTrustList, TrustedSubject};
+
+/// The current version of `ctltool`'s JSON output.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The kinds of JSON document that `ctltool` emits.
+#[allow(dead_code)] // Only bundles are read back in, so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Document {
+    /// The output of `dump`.
+    Dump,
+    /// The output of `lookup`.
+    Lookup,
+    /// A `--manifest` of fetched entries.
+    Manifest,
+    /// A `fetch --format json` bundle.
+    Bundle,
+    /// A single `--warnings json` line.
+    Warning,
+    /// The output of `ct-report --json`.
+    CtReport,
+}
+
+/// Stamps `value` (which must be a JSON object) with the current schema version.
+pub fn versioned(mut value: Value) -> Value {
+    value["schema_version"] = json!(SCHEMA_VERSION);
+    value
+}
+
+/// Returns a JSON Schema for the current version of the given kind of document, if
+/// one is available.
+pub fn json_schema(kind: Document) -> Option<Value> {
+    let (schema, entry) = match kind {
+        Document::Dump => (schema_for!(CertificateTrustList), "/$defs/TrustedSubject"),
+        Document::Lookup => (schema_for!(TrustedSubject), ""),
+        _ => return None,
+    };
+    let mut schema = schema.to_value();
+
+    // ctltool's additions to the library's serialized forms.
+    schema["properties"]["schema_version"] = json!({ "type": "integer", "const": SCHEMA_VERSION });
+    schema["required"]
+        .as_array_mut()?
+        .push(json!("schema_version"));
+    if kind == Document::Dump {
+        schema.pointer_mut(entry)?["properties"]["unknown_attributes"] = json!({
+            "description": "The entry's undecoded attributes, documented or not, with `--full`.",
+            "type": "array",
+        });
+    }
+
+    Some(schema)
+}
+
+/// Converts a previously emitted document of the given kind to the current schema
+/// version, rejecting documents from newer (or unknown) versions.
+pub fn upgrade(kind: Document, value: Value) -> Result<Value> {
+    let version = match value.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| anyhow!("invalid schema_version: {version}"))?,
+    };
+
+    match version {
+        0 => match (kind, value) {
+            (Document::Dump, Value::Array(entries)) => Ok(versioned(json!({ "entries": entries }))),
+            (_, value @ Value::Object(_)) if kind != Document::Dump => Ok(versioned(value)),
+            _ => Err(anyhow!("malformed {kind:?} document")),
+        },
+        SCHEMA_VERSION => Ok(value),
+        _ => Err(anyhow!(
+            "unsupported schema_version {version} (this ctltool understands up to {SCHEMA_VERSION})"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade() {
+        let entry = json!({ "identifier": "abcd" });
+
+        assert_eq!(
+            upgrade(Document::Dump, json!([entry])).unwrap(),
+            json!({ "schema_version": 1, "entries": [entry] })
+        );
+        assert_eq!(
+            upgrade(Document::Lookup, entry.clone()).unwrap(),
+            json!({ "schema_version": 1, "identifier": "abcd" })
+        );
+
+        let current = versioned(json!({ "entries": [] }));
+        assert_eq!(upgrade(Document::Dump, current.clone()).unwrap(), current);
+
+        assert!(upgrade(Document::Dump, entry).is_err());
+        assert!(upgrade(Document::Lookup, json!({ "schema_version": 2 })).is_err());
+        assert!(upgrade(Document::Lookup, json!({ "schema_version": "1" })).is_err());
+    }
+
+    #[test]
+    fn test_json_schema() {
+        for kind in [Document::Dump, Document::Lookup] {
+            let schema = json_schema(kind).unwrap();
+            assert_eq!(
+                schema["properties"]["schema_version"]["const"],
+                SCHEMA_VERSION
+            );
+            assert!(schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("schema_version")));
+        }
+        assert!(
+            json_schema(Document::Dump).unwrap()["$defs"]["TrustedSubject"]["properties"]
+                ["unknown_attributes"]
+                .is_object()
+        );
+        assert_eq!(json_schema(Document::Bundle), None);
+    }
+}
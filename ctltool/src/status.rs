@@ -0,0 +1,149 @@
+//! Checking whether a snapshot's lists are still current, without downloading them.
+//!
+//! Alongside each list's cabinet, Microsoft publishes a tiny sequence file holding the
+//! latest release's sequence number (in hex), which is what Windows' AutoUpdate polls.
+
+use std::{
+    io::Write,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Result};
+use reqwest::StatusCode;
+use windows_ctl::{store::TrustSnapshot, CertificateTrustList, SequenceNumber};
+use x509_cert::der::DateTime;
+
+use crate::fetcher::Http;
+
+/// The base URL that Microsoft serves the lists, and their sequence files, from.
+pub const CTL_BASE_URL: &str =
+    "http://ctldl.windowsupdate.com/msdownload/update/v3/static/trustedr/en";
+
+/// The status of one of a snapshot's lists, relative to the latest release.
+pub struct ListStatus<'a> {
+    /// A short name for the list, e.g. `authroot`.
+    pub name: &'static str,
+    /// The snapshot's copy of the list.
+    pub local: &'a CertificateTrustList,
+    /// The latest release's sequence number, or why it couldn't be retrieved.
+    pub latest: Result<SequenceNumber>,
+}
+
+impl ListStatus<'_> {
+    /// Returns whether a release newer than the local copy is known to exist.
+    ///
+    /// A local copy without a sequence number is taken to be outdated.
+    pub fn is_outdated(&self) -> bool {
+        self.latest.as_ref().is_ok_and(|latest| {
+            self.local
+                .sequence_number
+                .as_ref()
+                .is_none_or(|local| latest > local)
+        })
+    }
+}
+
+/// Retrieves the latest sequence numbers of the lists in `snapshot`, from the
+/// sequence files under `base_url`.
+pub fn check<'a>(http: &Http, base_url: &str, snapshot: &'a TrustSnapshot) -> Vec<ListStatus<'a>> {
+    let base_url = base_url.trim_end_matches('/');
+    let lists = [
+        ("authroot", "authrootseq.txt", Some(snapshot.roots_list())),
+        (
+            "disallowed",
+            "disallowedcertseq.txt",
+            snapshot.disallowed_list(),
+        ),
+    ];
+
+    lists
+        .into_iter()
+        .filter_map(|(name, file, local)| {
+            Some(ListStatus {
+                name,
+                local: local?,
+                latest: latest_sequence_number(http, &format!("{base_url}/{file}")),
+            })
+        })
+        .collect()
+}
+
+/// Writes a line per list, describing how its local copy compares to the latest release.
+pub fn report(mut sink: impl Write, statuses: &[ListStatus<'_>], now: SystemTime) -> Result<()> {
+    for status in statuses {
+        let local = &status.local;
+        let sequence_number = local
+            .sequence_number
+            .as_ref()
+            .map_or_else(|| "none".into(), SequenceNumber::to_hex);
+        let this_update = local.this_update.to_system_time();
+        let age = now
+            .duration_since(this_update)
+            .unwrap_or(Duration::ZERO)
+            .as_secs()
+            / 86_400;
+        let published = DateTime::from_system_time(this_update).map_or_else(
+            |_| format!("{:?}", local.this_update),
+            |time| time.to_string(),
+        );
+
+        write!(
+            sink,
+            "{}: local {sequence_number} (published {published}, {age} days ago), ",
+            status.name
+        )?;
+        match &status.latest {
+            Ok(latest) if status.is_outdated() => {
+                writeln!(sink, "latest {latest}: newer data is available")?
+            }
+            Ok(latest) => writeln!(sink, "latest {latest}: up to date")?,
+            Err(e) => writeln!(sink, "latest unknown: {e:#}")?,
+        }
+    }
+    Ok(sink.flush()?)
+}
+
+/// Retrieves and parses the sequence file at `url`.
+fn latest_sequence_number(http: &Http, url: &str) -> Result<SequenceNumber> {
+    let resp = http.get(url)?;
+    match resp.status() {
+        StatusCode::OK => {}
+        status => return Err(anyhow!("{url} returned {}", status.as_u16())),
+    }
+    let body = Http::body(resp)?;
+    parse_sequence_number(&String::from_utf8_lossy(&body))
+        .ok_or_else(|| anyhow!("{url} isn't a sequence file"))
+}
+
+/// Parses a hex sequence number, e.g. the contents of a sequence file (which may be
+/// followed by a newline).
+pub fn parse_sequence_number(contents: &str) -> Option<SequenceNumber> {
+    let hex = contents.trim();
+    if hex.is_empty() {
+        return None;
+    }
+    let padded = match hex.len() % 2 {
+        0 => hex.to_string(),
+        _ => format!("0{hex}"),
+    };
+    SequenceNumber::new(&hex::decode(padded).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sequence_number() {
+        let parse = |contents| parse_sequence_number(contents).map(|seq| seq.to_hex());
+
+        assert_eq!(
+            parse("1D9F2E3A4B5C6D7\r\n").as_deref(),
+            Some("01d9f2e3a4b5c6d7")
+        );
+        assert_eq!(parse("0123").as_deref(), Some("0123"));
+        for invalid in ["", "\n", "not hex", "12 34"] {
+            assert_eq!(parse(invalid), None, "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Time and peak memory for each way of parsing a large, disallowed-style list.
+//!
+//! Run with `cargo bench --bench large_list`, optionally passing the number of entries
+//! (which defaults to 100,000).
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use std::io::Cursor;
+use std::time::Instant;
+
+use support::{disallowed_list, measure, LARGE_LIST_ENTRIES};
+use windows_ctl::{CertificateTrustList, RawCtl};
+
+const ITERATIONS: u32 = 10;
+
+fn main() {
+    let entries = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(LARGE_LIST_ENTRIES);
+    let der = disallowed_list(entries);
+    let raw = RawCtl::new(der.clone()).unwrap();
+    println!("{entries} entries, {} bytes of DER", der.len());
+
+    bench("peek_header", || {
+        CertificateTrustList::peek_header(&der).unwrap();
+    });
+    bench("RawCtl::new (with a copy)", || {
+        RawCtl::new(der.clone()).unwrap();
+    });
+    bench("RawCtl::ctl_ref", || {
+        raw.ctl_ref().unwrap();
+    });
+    bench("CertificateTrustList::from_der", || {
+        CertificateTrustList::from_der(Cursor::new(&der)).unwrap();
+    });
+    bench("CertificateTrustList::from_der_slice", || {
+        CertificateTrustList::from_der_slice(&der).unwrap();
+    });
+}
+
+/// Runs `f` a few times, reporting its mean time and peak allocation.
+fn bench(name: &str, mut f: impl FnMut()) {
+    let ((), peak) = measure(&mut f);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+
+    println!(
+        "{name:<38} {elapsed:>12.3?} {:>10.1} MiB peak",
+        peak as f64 / 1048576.0
+    );
+}
@@ -0,0 +1,99 @@
+//! Makes an HTTPS request with a rustls client that trusts (only) the roots in
+//! Microsoft's current trusted root CTL.
+//!
+//! ```console
+//! $ cargo run -p windows-ctl --example rustls_client --features cab,rustls -- www.rust-lang.org
+//! ```
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use windows_ctl::store::{CertificateFetcher, RootCertStoreWriter, StoreBuilder};
+use windows_ctl::{eku, CertificateTrustList, CtlError, TrustedSubject};
+
+/// Where Microsoft publishes the current trusted root CTL.
+const AUTHROOT_URL: &str =
+    "http://ctldl.windowsupdate.com/msdownload/update/v3/static/trustedr/en/authrootstl.cab";
+
+/// Where Microsoft publishes the certificates that the CTL refers to.
+const CDN_BASE_URL: &str =
+    "http://www.download.windowsupdate.com/msdownload/update/v3/static/trustedr/en";
+
+/// Retrieves certificates from Microsoft's CDN.
+struct CdnFetcher(reqwest::blocking::Client);
+
+impl CertificateFetcher for CdnFetcher {
+    fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+        let id = entry.cert_id();
+        let fetch = || -> reqwest::Result<Option<Vec<u8>>> {
+            let resp = self.0.get(format!("{CDN_BASE_URL}/{id}.crt")).send()?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Ok(Some(resp.error_for_status()?.bytes()?.to_vec()))
+        };
+        fetch().map_err(|e| CtlError::Fetch(id.clone(), e.into()))
+    }
+}
+
+/// Whether `entry` should be a trust anchor for TLS servers.
+///
+/// Trust anchors can't express the CTL's distrust dates, so entries with any are
+/// skipped outright.
+fn trusted_for_tls(entry: &TrustedSubject) -> Result<bool, CtlError> {
+    let decoded = entry.decoded()?;
+    Ok(decoded.extended_key_usages().contains(&eku::SERVER_AUTH)
+        && decoded.disallowed_at().is_none()
+        && decoded.not_before().is_none())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let host = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "www.rust-lang.org".into());
+
+    let client = reqwest::blocking::Client::new();
+    let cab = client
+        .get(AUTHROOT_URL)
+        .send()?
+        .error_for_status()?
+        .bytes()?;
+    let ctl = CertificateTrustList::from_cab(Cursor::new(cab))?;
+
+    let mut roots = RootCertStore::empty();
+    let report = StoreBuilder::new(&ctl, CdnFetcher(client))
+        .filter(trusted_for_tls)
+        .writer(RootCertStoreWriter::new(&mut roots))
+        .build()?;
+    eprintln!(
+        "trusting {} roots ({} filtered, {} without certificates)",
+        report.written.len(),
+        report.filtered,
+        report.metadata_only.len()
+    );
+
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.clone())?;
+    let conn = ClientConnection::new(Arc::new(config), server_name)?;
+    let sock = TcpStream::connect((host.as_str(), 443))?;
+    let mut tls = StreamOwned::new(conn, sock);
+    write!(
+        tls,
+        "HEAD / HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut status = String::new();
+    BufReader::new(tls).read_line(&mut status)?;
+    println!("{}", status.trim_end());
+
+    Ok(())
+}
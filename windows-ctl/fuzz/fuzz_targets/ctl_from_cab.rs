@@ -0,0 +1,10 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use windows_ctl::CertificateTrustList;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CertificateTrustList::all_from_cab(Cursor::new(data));
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use windows_ctl::{CertificateTrustList, RawCtl};
+
+fuzz_target!(|data: &[u8]| {
+    let from_reader = CertificateTrustList::from_der(Cursor::new(data)).ok();
+    let from_slice = CertificateTrustList::from_der_slice(data).ok();
+    assert_eq!(from_reader, from_slice);
+
+    if let Ok(header) = CertificateTrustList::peek_header(data) {
+        header.subject_usage().for_each(drop);
+    }
+
+    if let Ok(raw) = RawCtl::new(data.to_vec()) {
+        let _ = raw.ctl_ref();
+    }
+});
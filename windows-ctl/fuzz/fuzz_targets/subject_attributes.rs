@@ -0,0 +1,24 @@
+#![no_main]
+
+use der::Decode;
+use libfuzzer_sys::fuzz_target;
+use windows_ctl::TrustedSubject;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(subject) = TrustedSubject::from_der(data) else {
+        return;
+    };
+
+    subject.decoded_attributes().for_each(drop);
+    subject.extended_key_usages().for_each(drop);
+    subject.not_before_ekus().for_each(drop);
+    subject.disallowed_ekus().for_each(drop);
+    let _ = subject.friendly_name();
+    let _ = subject.sha256_fingerprint();
+    let _ = subject.key_identifier();
+    let _ = subject.subject_name_md5();
+    let _ = subject.root_program_cert_policies();
+    let _ = subject.root_program_chain_policies();
+    let _ = subject.disallowed_at();
+    let _ = subject.not_before();
+});
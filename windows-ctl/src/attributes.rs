@@ -0,0 +1,827 @@
+//! Typed decoding of the Microsoft properties attached to [`TrustedSubject`]s.
+//!
+//! Each property is an X.509 attribute whose OID is under Microsoft's
+//! `1.3.6.1.4.1.311.10.11` (`szOID_CERT_PROP_ID_PREFIX`) arc, and whose values are
+//! `OCTET STRING`s wrapping the property's (little-endian, Win32-style) encoding.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use der::asn1::{Any, ObjectIdentifier, OctetStringRef};
+use der::{Decode, Encode, Tag};
+
+use crate::{
+    oids, utf16, CertificatePolicy, CtlError, MetaEku, TrustedSubject,
+    MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+    MS_CERT_PROP_ID_FRIENDLY_NAME_OID, MS_CERT_PROP_ID_KEY_IDENTIFIER_OID,
+    MS_CERT_PROP_ID_METAEKUS_OID, MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+    MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+    MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID, MS_CERT_PROP_ID_SHA256_HASH_OID,
+    MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+};
+
+/// The number of seconds between the FILETIME epoch (1601-01-01) and the Unix epoch.
+const FILETIME_UNIX_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+/// A single decoded property of a [`TrustedSubject`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SubjectAttribute {
+    /// The subject's display name (`CERT_FRIENDLY_NAME_PROP_ID`).
+    FriendlyName(String),
+
+    /// The EKUs the subject is trusted for (`CERT_ENHKEY_USAGE_PROP_ID`).
+    MetaEkus(MetaEku),
+
+    /// When the subject became distrusted (`CERT_DISALLOWED_FILETIME_PROP_ID`).
+    DisallowedFiletime(SystemTime),
+
+    /// The EKUs the subject is distrusted for (`CERT_DISALLOWED_ENHKEY_USAGE_PROP_ID`).
+    DisallowedEkus(MetaEku),
+
+    /// The time after which certificates issued by the subject are no longer trusted
+    /// (`CERT_NOT_BEFORE_FILETIME_PROP_ID`).
+    NotBeforeFiletime(SystemTime),
+
+    /// The EKUs that the subject's not-before time applies to
+    /// (`CERT_NOT_BEFORE_ENHKEY_USAGE_PROP_ID`).
+    NotBeforeEkus(MetaEku),
+
+    /// The SHA-256 hash of the subject's certificate (`CERT_AUTH_ROOT_SHA256_HASH_PROP_ID`).
+    Sha256Hash([u8; 32]),
+
+    /// The subject's key identifier (`CERT_KEY_IDENTIFIER_PROP_ID`).
+    KeyId(Vec<u8>),
+
+    /// The certificate policies the subject is granted by the root program
+    /// (`CERT_ROOT_PROGRAM_CERT_POLICIES_PROP_ID`).
+    RootProgramCertPolicies(Vec<CertificatePolicy>),
+
+    /// The chain policies the root program applies to the subject
+    /// (`CERT_ROOT_PROGRAM_CHAIN_POLICIES_PROP_ID`).
+    RootProgramChainPolicies(ChainPolicies),
+
+    /// The MD5 hash of the subject's encoded subject name
+    /// (`CERT_SUBJECT_NAME_MD5_HASH_PROP_ID`).
+    SubjectNameMd5Hash([u8; 16]),
+
+    /// A property that this crate doesn't model.
+    Unknown {
+        /// The property's OID.
+        oid: ObjectIdentifier,
+        /// The property's raw value.
+        raw: Any,
+    },
+}
+
+impl SubjectAttribute {
+    /// Decodes a single value of the attribute identified by `oid`.
+    pub(crate) fn decode(oid: ObjectIdentifier, value: &Any) -> Result<Self, CtlError> {
+        let bytes =
+            || -> Result<&[u8], CtlError> { Ok(value.decode_as::<OctetStringRef>()?.as_bytes()) };
+
+        Ok(match oid {
+            MS_CERT_PROP_ID_FRIENDLY_NAME_OID => Self::FriendlyName(decode_utf16(oid, bytes()?)?),
+            MS_CERT_PROP_ID_METAEKUS_OID => Self::MetaEkus(MetaEku::from_der(bytes()?)?),
+            MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID => {
+                Self::DisallowedFiletime(decode_filetime(oid, bytes()?)?)
+            }
+            MS_CERT_PROP_ID_DISALLOWED_EKUS_OID => {
+                Self::DisallowedEkus(MetaEku::from_der(bytes()?)?)
+            }
+            MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID => {
+                Self::NotBeforeFiletime(decode_filetime(oid, bytes()?)?)
+            }
+            MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID => {
+                Self::NotBeforeEkus(MetaEku::from_der(bytes()?)?)
+            }
+            MS_CERT_PROP_ID_SHA256_HASH_OID => Self::Sha256Hash(decode_hash(oid, bytes()?)?),
+            MS_CERT_PROP_ID_KEY_IDENTIFIER_OID => Self::KeyId(bytes()?.to_vec()),
+            MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID => {
+                Self::RootProgramCertPolicies(Vec::from_der(bytes()?)?)
+            }
+            MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID => {
+                Self::RootProgramChainPolicies(ChainPolicies::from_der(bytes()?)?)
+            }
+            MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID => {
+                Self::SubjectNameMd5Hash(decode_hash(oid, bytes()?)?)
+            }
+            _ => Self::Unknown {
+                oid,
+                raw: value.clone(),
+            },
+        })
+    }
+}
+
+impl SubjectAttribute {
+    /// Returns the OID of the property this attribute is a value of.
+    pub fn oid(&self) -> ObjectIdentifier {
+        match self {
+            Self::FriendlyName(_) => MS_CERT_PROP_ID_FRIENDLY_NAME_OID,
+            Self::MetaEkus(_) => MS_CERT_PROP_ID_METAEKUS_OID,
+            Self::DisallowedFiletime(_) => MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+            Self::DisallowedEkus(_) => MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+            Self::NotBeforeFiletime(_) => MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+            Self::NotBeforeEkus(_) => MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+            Self::Sha256Hash(_) => MS_CERT_PROP_ID_SHA256_HASH_OID,
+            Self::KeyId(_) => MS_CERT_PROP_ID_KEY_IDENTIFIER_OID,
+            Self::RootProgramCertPolicies(_) => MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+            Self::RootProgramChainPolicies(_) => MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID,
+            Self::SubjectNameMd5Hash(_) => MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+            Self::Unknown { oid, .. } => *oid,
+        }
+    }
+
+    /// Encodes this attribute's value the way Microsoft does: as an `OCTET STRING`
+    /// wrapping the property's encoding. The inverse of decoding; [`Unknown`](Self::Unknown)
+    /// values are returned as-is.
+    pub fn encode_value(&self) -> Result<Any, CtlError> {
+        let filetime = |time| {
+            encode_filetime(time).ok_or(CtlError::Attribute {
+                oid: self.oid(),
+                reason: "FILETIME out of range",
+            })
+        };
+
+        let bytes = match self {
+            Self::FriendlyName(name) => utf16::encode(name),
+            Self::MetaEkus(ekus) | Self::DisallowedEkus(ekus) | Self::NotBeforeEkus(ekus) => {
+                ekus.to_der()?
+            }
+            Self::DisallowedFiletime(time) | Self::NotBeforeFiletime(time) => {
+                filetime(*time)?.to_vec()
+            }
+            Self::Sha256Hash(hash) => hash.to_vec(),
+            Self::KeyId(id) => id.clone(),
+            Self::RootProgramCertPolicies(policies) => policies.to_der()?,
+            Self::RootProgramChainPolicies(policies) => policies.0.to_der()?,
+            Self::SubjectNameMd5Hash(hash) => hash.to_vec(),
+            Self::Unknown { raw, .. } => return Ok(raw.clone()),
+        };
+
+        Ok(Any::new(Tag::OctetString, bytes)?)
+    }
+}
+
+/// The chain policies that Microsoft's root program applies to a subject.
+///
+/// Like [`MetaEku`], this is completely undocumented by MS. As best I can tell
+/// it's a list of policy OIDs:
+///
+/// ```asn1
+/// ChainPolicies ::= SEQUENCE OF OBJECT IDENTIFIER
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct ChainPolicies(pub Vec<ObjectIdentifier>);
+
+impl<'a> Decode<'a> for ChainPolicies {
+    fn decode<R: der::Reader<'a>>(reader: &mut R) -> der::Result<Self> {
+        Ok(Self(Vec::decode(reader)?))
+    }
+}
+
+impl ChainPolicies {
+    /// Returns an iterator over the policy OIDs.
+    pub fn iter(&self) -> impl Iterator<Item = &ObjectIdentifier> {
+        self.0.iter()
+    }
+}
+
+/// Decodes a NUL-terminated UTF-16LE string, as Windows stores string properties.
+fn decode_utf16(oid: ObjectIdentifier, bytes: &[u8]) -> Result<String, CtlError> {
+    utf16::decode(bytes).map_err(|reason| CtlError::Attribute { oid, reason })
+}
+
+/// Decodes a fixed-size hash.
+fn decode_hash<const N: usize>(oid: ObjectIdentifier, bytes: &[u8]) -> Result<[u8; N], CtlError> {
+    bytes.try_into().map_err(|_| CtlError::Attribute {
+        oid,
+        reason: "unexpected hash length",
+    })
+}
+
+/// Encodes a Win32 `FILETIME`, the inverse of [`decode_filetime`]. Returns `None` for
+/// times that a `FILETIME` can't represent.
+pub(crate) fn encode_filetime(time: SystemTime) -> Option<[u8; 8]> {
+    let epoch =
+        SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs(FILETIME_UNIX_EPOCH_OFFSET))?;
+    let since_1601 = time.duration_since(epoch).ok()?;
+    let ticks = u64::try_from(since_1601.as_nanos() / 100).ok()?;
+    Some(ticks.to_le_bytes())
+}
+
+/// Decodes a Win32 `FILETIME`: a little-endian count of 100ns intervals since 1601-01-01.
+fn decode_filetime(oid: ObjectIdentifier, bytes: &[u8]) -> Result<SystemTime, CtlError> {
+    let ticks = u64::from_le_bytes(bytes.try_into().map_err(|_| CtlError::Attribute {
+        oid,
+        reason: "expected an 8-byte FILETIME",
+    })?);
+
+    let since_1601 = Duration::new(ticks / 10_000_000, (ticks % 10_000_000) as u32 * 100);
+    let epoch_offset = Duration::from_secs(FILETIME_UNIX_EPOCH_OFFSET);
+
+    let time = if since_1601 >= epoch_offset {
+        SystemTime::UNIX_EPOCH.checked_add(since_1601 - epoch_offset)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(epoch_offset - since_1601)
+    };
+
+    time.ok_or(CtlError::Attribute {
+        oid,
+        reason: "FILETIME out of range",
+    })
+}
+
+impl TrustedSubject {
+    /// Returns an iterator over every property attached to this `TrustedSubject`,
+    /// decoded into [`SubjectAttribute`]s.
+    ///
+    /// Multi-valued attributes yield one item per value.
+    pub fn decoded_attributes(
+        &self,
+    ) -> impl Iterator<Item = Result<SubjectAttribute, CtlError>> + '_ {
+        self.attributes
+            .iter()
+            .flat_map(|attrs| attrs.iter())
+            .flat_map(|attr| {
+                attr.values
+                    .iter()
+                    .map(|value| SubjectAttribute::decode(attr.oid, value))
+            })
+    }
+
+    /// Returns an iterator over every property value attached to this `TrustedSubject`
+    /// whose OID isn't [known](crate::oids::is_known), as `(oid, bytes)` pairs.
+    ///
+    /// Properties that are documented but not modeled by [`SubjectAttribute`] (such as
+    /// `CERT_ROOT_PROGRAM_NAME_CONSTRAINTS_PROP_ID`) are skipped, so that only property
+    /// types that are new to the list stand out; they're still available, undecoded,
+    /// from [`TrustedSubject::decoded_attributes`].
+    ///
+    /// For `OCTET STRING` values (as Microsoft uses for all of its properties), the
+    /// bytes are the string's contents; otherwise they're the value's undecoded contents.
+    pub fn unknown_attributes(&self) -> impl Iterator<Item = (ObjectIdentifier, &[u8])> + '_ {
+        self.attributes
+            .iter()
+            .flat_map(|attrs| attrs.iter())
+            .filter(|attr| !oids::is_known(&attr.oid))
+            .flat_map(|attr| {
+                attr.values.iter().map(|value| {
+                    let bytes = value
+                        .decode_as::<OctetStringRef>()
+                        .map(|o| o.as_bytes())
+                        .unwrap_or_else(|_| value.value());
+                    (attr.oid, bytes)
+                })
+            })
+    }
+
+    /// Returns every property value attached to this `TrustedSubject`, grouped by OID.
+    ///
+    /// Values are left undecoded; OIDs that appear in more than one attribute
+    /// have their values merged, in encoding order.
+    pub fn attributes_by_oid(&self) -> BTreeMap<ObjectIdentifier, Vec<&Any>> {
+        let mut map = BTreeMap::<_, Vec<_>>::new();
+        for attr in self.attributes.iter().flat_map(|attrs| attrs.iter()) {
+            map.entry(attr.oid).or_default().extend(attr.values.iter());
+        }
+        map
+    }
+
+    /// Returns the contents of the first value of the property identified by `oid`, if any.
+    fn property(&self, oid: ObjectIdentifier) -> Result<Option<&[u8]>, CtlError> {
+        let value = self
+            .attributes
+            .iter()
+            .flat_map(|attrs| attrs.iter())
+            .filter(|attr| attr.oid == oid)
+            .flat_map(|attr| attr.values.iter())
+            .next();
+
+        match value {
+            Some(value) => Ok(Some(value.decode_as::<OctetStringRef>()?.as_bytes())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns this subject's display name (`CERT_FRIENDLY_NAME_PROP_ID`), if it has one.
+    pub fn friendly_name(&self) -> Result<Option<String>, CtlError> {
+        self.property(MS_CERT_PROP_ID_FRIENDLY_NAME_OID)?
+            .map(|bytes| decode_utf16(MS_CERT_PROP_ID_FRIENDLY_NAME_OID, bytes))
+            .transpose()
+    }
+
+    /// Returns the SHA-256 hash of this subject's certificate
+    /// (`CERT_AUTH_ROOT_SHA256_HASH_PROP_ID`), if listed.
+    ///
+    /// Unlike the subject's (SHA-1) identifier, this is suitable for verifying
+    /// that a retrieved certificate is the one the CTL refers to.
+    pub fn sha256_fingerprint(&self) -> Result<Option<[u8; 32]>, CtlError> {
+        self.property(MS_CERT_PROP_ID_SHA256_HASH_OID)?
+            .map(|bytes| decode_hash(MS_CERT_PROP_ID_SHA256_HASH_OID, bytes))
+            .transpose()
+    }
+
+    /// Returns the MD5 hash of this subject's encoded subject name
+    /// (`CERT_SUBJECT_NAME_MD5_HASH_PROP_ID`), if listed.
+    ///
+    /// Windows uses the same hash to key certificates in the registry, so this can be
+    /// used to match entries against certificate blobs found there.
+    pub fn subject_name_md5(&self) -> Result<Option<[u8; 16]>, CtlError> {
+        self.property(MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID)?
+            .map(|bytes| decode_hash(MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID, bytes))
+            .transpose()
+    }
+
+    /// Returns this subject's key identifier (`CERT_KEY_IDENTIFIER_PROP_ID`), if listed.
+    ///
+    /// This is the certificate's Subject Key Identifier, and so can be correlated with
+    /// the SKI and AKI extensions of locally held certificates.
+    pub fn key_identifier(&self) -> Result<Option<&[u8]>, CtlError> {
+        self.property(MS_CERT_PROP_ID_KEY_IDENTIFIER_OID)
+    }
+
+    /// Returns the certificate policies that Microsoft's root program grants this subject
+    /// (`CERT_ROOT_PROGRAM_CERT_POLICIES_PROP_ID`), if listed.
+    ///
+    /// For roots that are enabled for EV, these are the EV policy OIDs that
+    /// certificates chaining to the root may assert.
+    pub fn root_program_cert_policies(&self) -> Result<Option<Vec<CertificatePolicy>>, CtlError> {
+        Ok(self
+            .property(MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID)?
+            .map(Vec::from_der)
+            .transpose()?)
+    }
+
+    /// Returns the chain policies that Microsoft's root program applies to this subject
+    /// (`CERT_ROOT_PROGRAM_CHAIN_POLICIES_PROP_ID`), if listed.
+    pub fn root_program_chain_policies(&self) -> Result<Option<ChainPolicies>, CtlError> {
+        Ok(self
+            .property(MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID)?
+            .map(ChainPolicies::from_der)
+            .transpose()?)
+    }
+
+    /// Returns when this subject was distrusted (`CERT_DISALLOWED_FILETIME_PROP_ID`), if it has
+    /// been. This is typically only present on entries in the disallowed list.
+    pub fn disallowed_at(&self) -> Result<Option<SystemTime>, CtlError> {
+        self.property(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID)?
+            .map(|bytes| decode_filetime(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, bytes))
+            .transpose()
+    }
+
+    /// Returns the time after which certificates issued by this subject are no longer
+    /// trusted (`CERT_NOT_BEFORE_FILETIME_PROP_ID`), if there is one.
+    ///
+    /// This is how Microsoft implements "distrust new issuance": certificates whose
+    /// `notBefore` is after this time shouldn't chain to this subject, while those
+    /// issued before it remain trusted until they expire.
+    pub fn not_before(&self) -> Result<Option<SystemTime>, CtlError> {
+        self.property(MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID)?
+            .map(|bytes| decode_filetime(MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, bytes))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::Encode;
+
+    use super::*;
+    use crate::{oids::MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID, testing};
+
+    /// The OIDs of every property that [`SubjectAttribute`] models.
+    const MODELED_OIDS: &[ObjectIdentifier] = &[
+        MS_CERT_PROP_ID_FRIENDLY_NAME_OID,
+        MS_CERT_PROP_ID_METAEKUS_OID,
+        MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+        MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+        MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+        MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+        MS_CERT_PROP_ID_SHA256_HASH_OID,
+        MS_CERT_PROP_ID_KEY_IDENTIFIER_OID,
+        MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+        MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID,
+        MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+    ];
+
+    #[test]
+    fn test_decoded_attributes() {
+        let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+        let unknown_oid = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.999");
+        let name = "ISRG Root X1\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        // 2020-01-01T00:00:00Z as a FILETIME.
+        let filetime = 132_223_104_000_000_000u64.to_le_bytes();
+
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![
+                testing::attribute(MS_CERT_PROP_ID_FRIENDLY_NAME_OID, &name),
+                testing::attribute(
+                    MS_CERT_PROP_ID_METAEKUS_OID,
+                    &vec![server_auth].to_der().unwrap(),
+                ),
+                testing::attribute(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, &filetime),
+                testing::attribute(MS_CERT_PROP_ID_SHA256_HASH_OID, &[0xaa; 32]),
+                testing::attribute(MS_CERT_PROP_ID_KEY_IDENTIFIER_OID, &[0xbb; 20]),
+                testing::attribute(unknown_oid, b"???"),
+            ],
+        );
+
+        let attrs = subject
+            .decoded_attributes()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        // Attributes are a SET, so they come back in DER order rather than insertion order.
+        assert_eq!(attrs.len(), 6);
+        assert!(attrs.contains(&SubjectAttribute::FriendlyName("ISRG Root X1".into())));
+        assert!(attrs.contains(&SubjectAttribute::MetaEkus(MetaEku(vec![server_auth]))));
+        assert!(attrs.contains(&SubjectAttribute::DisallowedFiletime(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800)
+        )));
+        assert!(attrs.contains(&SubjectAttribute::Sha256Hash([0xaa; 32])));
+        assert!(attrs.contains(&SubjectAttribute::KeyId(vec![0xbb; 20])));
+        assert!(attrs.contains(&SubjectAttribute::Unknown {
+            oid: unknown_oid,
+            raw: testing::octet_string_value(b"???"),
+        }));
+    }
+
+    #[test]
+    fn test_unknown_attributes() {
+        let unknown_oid = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.999");
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![
+                testing::attribute(MS_CERT_PROP_ID_KEY_IDENTIFIER_OID, &[0xbb; 20]),
+                testing::attribute(
+                    MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID,
+                    b"\x30\x00",
+                ),
+                testing::attribute(unknown_oid, b"???"),
+            ],
+        );
+
+        // Properties that are documented, but not modeled, are skipped along with the
+        // modeled ones.
+        assert_eq!(
+            subject.unknown_attributes().collect::<Vec<_>>(),
+            [(unknown_oid, &b"???"[..])]
+        );
+        assert!(subject.decoded_attributes().any(|attr| matches!(
+            attr,
+            Ok(SubjectAttribute::Unknown { oid, .. })
+                if oid == MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID
+        )));
+
+        // Every modeled OID is known, and actually decoded as something other than
+        // `Unknown`.
+        for oid in MODELED_OIDS {
+            assert!(oids::is_known(oid), "{oid}");
+            let decoded = SubjectAttribute::decode(*oid, &testing::octet_string_value(&[]));
+            assert!(
+                !matches!(decoded, Ok(SubjectAttribute::Unknown { .. })),
+                "{oid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_value() {
+        let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+        let unknown_oid = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.999");
+
+        for attr in [
+            SubjectAttribute::FriendlyName("ISRG Root X1".into()),
+            SubjectAttribute::MetaEkus(MetaEku(vec![server_auth])),
+            SubjectAttribute::DisallowedFiletime(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800),
+            ),
+            SubjectAttribute::NotBeforeFiletime(
+                SystemTime::UNIX_EPOCH - Duration::from_secs(FILETIME_UNIX_EPOCH_OFFSET),
+            ),
+            SubjectAttribute::Sha256Hash([0xaa; 32]),
+            SubjectAttribute::KeyId(vec![0xbb; 20]),
+            SubjectAttribute::RootProgramChainPolicies(ChainPolicies(vec![server_auth])),
+            SubjectAttribute::SubjectNameMd5Hash([0xcc; 16]),
+            SubjectAttribute::Unknown {
+                oid: unknown_oid,
+                raw: testing::octet_string_value(b"???"),
+            },
+        ] {
+            let value = attr.encode_value().unwrap();
+            assert_eq!(SubjectAttribute::decode(attr.oid(), &value).unwrap(), attr);
+        }
+
+        // Before FILETIME's epoch.
+        let attr = SubjectAttribute::DisallowedFiletime(
+            SystemTime::UNIX_EPOCH - Duration::from_secs(FILETIME_UNIX_EPOCH_OFFSET + 1),
+        );
+        assert!(matches!(
+            attr.encode_value(),
+            Err(CtlError::Attribute { oid, .. }) if oid == MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID
+        ));
+    }
+
+    #[test]
+    fn test_attributes_by_oid() {
+        let unknown_oid = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.999");
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![
+                testing::attribute(MS_CERT_PROP_ID_KEY_IDENTIFIER_OID, &[0xbb; 20]),
+                testing::attribute(unknown_oid, b"???"),
+            ],
+        );
+
+        let map = subject.attributes_by_oid();
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map[&MS_CERT_PROP_ID_KEY_IDENTIFIER_OID],
+            [&testing::octet_string_value(&[0xbb; 20])]
+        );
+        assert_eq!(map[&unknown_oid], [&testing::octet_string_value(b"???")]);
+        assert!(!map.contains_key(&MS_CERT_PROP_ID_SHA256_HASH_OID));
+
+        assert!(testing::subject(&[0x11; 20], vec![])
+            .attributes_by_oid()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_SHA256_HASH_OID,
+                &[0xaa; 20],
+            )],
+        );
+
+        assert!(matches!(
+            subject.decoded_attributes().next(),
+            Some(Err(CtlError::Attribute { oid, .. })) if oid == MS_CERT_PROP_ID_SHA256_HASH_OID
+        ));
+    }
+
+    #[test]
+    fn test_friendly_name() {
+        let name = "Baltimore CyberTrust Root\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(MS_CERT_PROP_ID_FRIENDLY_NAME_OID, &name)],
+        );
+        assert_eq!(
+            subject.friendly_name().unwrap().as_deref(),
+            Some("Baltimore CyberTrust Root")
+        );
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.friendly_name().unwrap(), None);
+
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_FRIENDLY_NAME_OID,
+                b"abc",
+            )],
+        );
+        assert!(subject.friendly_name().is_err());
+    }
+
+    #[test]
+    fn test_sha256_fingerprint() {
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_SHA256_HASH_OID,
+                &[0xaa; 32],
+            )],
+        );
+        assert_eq!(subject.sha256_fingerprint().unwrap(), Some([0xaa; 32]));
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.sha256_fingerprint().unwrap(), None);
+
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_SHA256_HASH_OID,
+                &[0xaa; 20],
+            )],
+        );
+        assert!(subject.sha256_fingerprint().is_err());
+    }
+
+    #[test]
+    fn test_subject_name_md5() {
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+                &[0xcc; 16],
+            )],
+        );
+        assert_eq!(subject.subject_name_md5().unwrap(), Some([0xcc; 16]));
+        assert!(subject
+            .decoded_attributes()
+            .any(|attr| attr.unwrap() == SubjectAttribute::SubjectNameMd5Hash([0xcc; 16])));
+
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+                &[0xcc; 20],
+            )],
+        );
+        assert!(subject.subject_name_md5().is_err());
+    }
+
+    #[test]
+    fn test_root_program_cert_policies() {
+        let ev_policy = ObjectIdentifier::new_unwrap("2.23.140.1.1");
+        let policies = vec![CertificatePolicy {
+            policy_identifier: ev_policy,
+            policy_qualifiers: None,
+        }];
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+                &policies.to_der().unwrap(),
+            )],
+        );
+        assert_eq!(
+            subject.root_program_cert_policies().unwrap(),
+            Some(policies.clone())
+        );
+        assert!(subject.decoded_attributes().any(
+            |attr| attr.unwrap() == SubjectAttribute::RootProgramCertPolicies(policies.clone())
+        ));
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.root_program_cert_policies().unwrap(), None);
+    }
+
+    #[test]
+    fn test_root_program_chain_policies() {
+        let policy = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.60.3.1");
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID,
+                &vec![policy].to_der().unwrap(),
+            )],
+        );
+
+        let policies = subject.root_program_chain_policies().unwrap().unwrap();
+        assert_eq!(policies.iter().collect::<Vec<_>>(), [&policy]);
+        assert!(subject
+            .decoded_attributes()
+            .any(|attr| attr.unwrap()
+                == SubjectAttribute::RootProgramChainPolicies(policies.clone())));
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.root_program_chain_policies().unwrap(), None);
+    }
+
+    #[test]
+    fn test_key_identifier() {
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_KEY_IDENTIFIER_OID,
+                &[0xbb; 20],
+            )],
+        );
+        assert_eq!(subject.key_identifier().unwrap(), Some(&[0xbb; 20][..]));
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.key_identifier().unwrap(), None);
+    }
+
+    #[test]
+    fn test_disallowed_at() {
+        // 1601-01-01, 1970-01-01, and 2020-01-01 as FILETIMEs.
+        for (ticks, expected) in [
+            (
+                0,
+                SystemTime::UNIX_EPOCH - Duration::from_secs(FILETIME_UNIX_EPOCH_OFFSET),
+            ),
+            (116_444_736_000_000_000, SystemTime::UNIX_EPOCH),
+            (
+                132_223_104_000_000_000,
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800),
+            ),
+        ] {
+            let subject = testing::subject(
+                &[0x11; 20],
+                vec![testing::attribute(
+                    MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+                    &u64::to_le_bytes(ticks),
+                )],
+            );
+            assert_eq!(subject.disallowed_at().unwrap(), Some(expected));
+        }
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.disallowed_at().unwrap(), None);
+
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+                &[0; 4],
+            )],
+        );
+        assert!(matches!(
+            subject.disallowed_at(),
+            Err(CtlError::Attribute { oid, .. }) if oid == MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID
+        ));
+    }
+
+    #[test]
+    fn test_disallowed_ekus() {
+        let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+                &vec![server_auth].to_der().unwrap(),
+            )],
+        );
+
+        assert_eq!(
+            subject
+                .disallowed_ekus()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            [server_auth]
+        );
+        assert_eq!(subject.extended_key_usages().count(), 0);
+        assert!(subject
+            .decoded_attributes()
+            .any(|attr| attr.unwrap()
+                == SubjectAttribute::DisallowedEkus(MetaEku(vec![server_auth]))));
+    }
+
+    #[test]
+    fn test_not_before() {
+        // 2020-01-01T00:00:00Z as a FILETIME.
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+                &132_223_104_000_000_000u64.to_le_bytes(),
+            )],
+        );
+        assert_eq!(
+            subject.not_before().unwrap(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800))
+        );
+        // Only the not-before property is consulted.
+        assert_eq!(subject.disallowed_at().unwrap(), None);
+
+        let subject = testing::subject(&[0x11; 20], vec![]);
+        assert_eq!(subject.not_before().unwrap(), None);
+    }
+
+    #[test]
+    fn test_not_before_ekus() {
+        let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+        let code_signing = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![
+                testing::attribute(
+                    MS_CERT_PROP_ID_METAEKUS_OID,
+                    &vec![server_auth, code_signing].to_der().unwrap(),
+                ),
+                testing::attribute(
+                    MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+                    &vec![server_auth].to_der().unwrap(),
+                ),
+            ],
+        );
+
+        assert_eq!(
+            subject
+                .not_before_ekus()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            [server_auth]
+        );
+        assert_eq!(subject.extended_key_usages().count(), 2);
+        assert!(subject.decoded_attributes().any(
+            |attr| attr.unwrap() == SubjectAttribute::NotBeforeEkus(MetaEku(vec![server_auth]))
+        ));
+    }
+}
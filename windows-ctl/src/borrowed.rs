@@ -0,0 +1,178 @@
+//! Zero-copy views of CTLs, for lists too large to comfortably decode into owned types.
+//!
+//! The owned [`CertificateTrustList`](crate::CertificateTrustList) makes several small
+//! allocations per entry, which adds up for lists with tens of thousands of entries
+//! (like the disallowed list). The types here instead borrow from the encoded list,
+//! so their memory usage is proportional to the size of the input.
+
+use der::asn1::{AnyRef, ObjectIdentifier, OctetStringRef, UintRef};
+use der::{Decode, Encode, Reader, Sequence, SliceReader, Tag, Tagged};
+use spki::AlgorithmIdentifierRef;
+use x509_cert::time::Time;
+
+use crate::{subject_id, CtlError, CtlVersion, SubjectUsage, TrustedSubject};
+
+/// A borrowed [`TrustedSubject`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Sequence)]
+pub struct TrustedSubjectRef<'a> {
+    identifier: OctetStringRef<'a>,
+    /// The encoded X.509 attributes attached to this subject, if any.
+    pub attributes: Option<AnyRef<'a>>,
+}
+
+impl<'a> TrustedSubjectRef<'a> {
+    /// Returns the certificate's ID, as bytes.
+    pub fn cert_id(&self) -> &'a [u8] {
+        self.identifier.as_bytes()
+    }
+
+    /// Returns whether this subject's identifier is `id`.
+    pub fn has_id(&self, id: impl AsRef<[u8]>) -> bool {
+        self.cert_id() == id.as_ref()
+    }
+
+    /// Returns whether this subject's identifier is equal to the given hex string,
+    /// without allocating. See [`SubjectId::eq_hex`](crate::SubjectId::eq_hex).
+    pub fn has_hex_id(&self, hex: &str) -> bool {
+        subject_id::eq_hex(self.cert_id(), hex)
+    }
+
+    /// Decodes this subject into an owned [`TrustedSubject`], e.g. to inspect its attributes.
+    pub fn to_subject(&self) -> Result<TrustedSubject, CtlError> {
+        Ok(TrustedSubject::from_der(&self.to_der()?)?)
+    }
+}
+
+/// A borrowed [`CertificateTrustList`](crate::CertificateTrustList).
+///
+/// See [`RawCtl::ctl_ref`](crate::RawCtl::ctl_ref).
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+pub struct CertificateTrustListRef<'a> {
+    /// This trust list's version. The default version is 1.
+    #[asn1(default = "Default::default")]
+    pub version: CtlVersion,
+
+    /// X.509-style usage.
+    pub subject_usage: SubjectUsage,
+
+    /// See [MS-CAESO](https://yossarian.net/junk/hard_to_find/ms-caeso-v20090709.pdf) page 48.
+    pub list_identifier: Option<OctetStringRef<'a>>,
+
+    /// This list's sequence number. See [`SequenceNumber`](crate::SequenceNumber).
+    pub sequence_number: Option<UintRef<'a>>,
+
+    /// X.509-style time for when this CTL was produced/released.
+    pub this_update: Time,
+
+    /// X.509-style time for when the next CTL will be produced/released.
+    pub next_update: Option<Time>,
+
+    /// Presumably the digest algorithm used to compute each subject's identifier.
+    pub subject_algorithm: AlgorithmIdentifierRef<'a>,
+
+    /// The list of trusted subjects in this CTL.
+    pub trusted_subjects: Option<Vec<TrustedSubjectRef<'a>>>,
+
+    /// Any X.509 style extensions.
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    pub ctl_extensions: Option<AnyRef<'a>>,
+}
+
+/// The leading fields of a CTL, as returned by
+/// [`CertificateTrustList::peek_header`](crate::CertificateTrustList::peek_header).
+///
+/// Decoding these doesn't touch the list's subjects (or allocate), so it's cheap
+/// even for very large lists.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CtlHeader<'a> {
+    /// This trust list's version. The default version is 1.
+    pub version: CtlVersion,
+
+    /// The encoded X.509-style usage. See [`CtlHeader::subject_usage`].
+    subject_usage: AnyRef<'a>,
+
+    /// See [MS-CAESO](https://yossarian.net/junk/hard_to_find/ms-caeso-v20090709.pdf) page 48.
+    pub list_identifier: Option<OctetStringRef<'a>>,
+
+    /// This list's sequence number. See [`SequenceNumber`](crate::SequenceNumber).
+    pub sequence_number: Option<UintRef<'a>>,
+
+    /// X.509-style time for when this CTL was produced/released.
+    pub this_update: Time,
+
+    /// X.509-style time for when the next CTL will be produced/released.
+    pub next_update: Option<Time>,
+}
+
+impl<'a> CtlHeader<'a> {
+    /// Decodes the header fields from the start of an encoded `certTrustList`,
+    /// ignoring everything after them.
+    pub(crate) fn decode_prefix(ctl: AnyRef<'a>) -> Result<Self, CtlError> {
+        ctl.tag().assert_eq(Tag::Sequence)?;
+        let mut reader = SliceReader::new(ctl.value())?;
+
+        let version = match reader.peek_tag()? {
+            Tag::Integer => CtlVersion::decode(&mut reader)?,
+            _ => CtlVersion::default(),
+        };
+
+        let subject_usage: AnyRef<'a> = reader.decode()?;
+        subject_usage.tag().assert_eq(Tag::Sequence)?;
+
+        Ok(Self {
+            version,
+            subject_usage,
+            list_identifier: reader.decode()?,
+            sequence_number: reader.decode()?,
+            this_update: reader.decode()?,
+            next_update: reader.decode()?,
+        })
+    }
+
+    /// Returns an iterator over the OIDs in this list's X.509-style usage, decoding
+    /// them as it goes.
+    pub fn subject_usage(&self) -> impl Iterator<Item = Result<ObjectIdentifier, der::Error>> + 'a {
+        let mut reader = SliceReader::new(self.subject_usage.value()).ok();
+        std::iter::from_fn(move || {
+            let current = reader.as_mut()?;
+            if current.is_finished() {
+                return None;
+            }
+
+            let oid = current.decode();
+            if oid.is_err() {
+                // Don't keep reading past malformed input.
+                reader = None;
+            }
+            Some(oid)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing, RawCtl};
+
+    #[test]
+    fn test_ctl_ref() {
+        let ctl = testing::sample_ctl();
+        let raw = RawCtl::new(testing::sign_ctl(&ctl)).unwrap();
+        let ctl_ref = raw.ctl_ref().unwrap();
+
+        assert_eq!(ctl_ref.subject_usage, ctl.subject_usage);
+        assert_eq!(ctl_ref.this_update, ctl.this_update);
+        assert_eq!(ctl_ref.subject_algorithm.oid, ctl.subject_algorithm.oid);
+
+        let subjects = ctl_ref.trusted_subjects.unwrap();
+        let expected = ctl.trusted_subjects.unwrap();
+        assert_eq!(subjects.len(), expected.len());
+        for (subject, expected) in subjects.iter().zip(&expected) {
+            assert!(subject.has_id(expected.cert_id()));
+            assert_eq!(&subject.to_subject().unwrap(), expected);
+        }
+
+        // Identifiers point into the original encoding.
+        let content = raw.content().as_ptr_range();
+        assert!(content.contains(&subjects[0].cert_id().as_ptr()));
+    }
+}
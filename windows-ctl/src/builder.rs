@@ -0,0 +1,348 @@
+//! Authoring new certificate trust lists.
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::SystemTime;
+
+use der::asn1::{Any, GeneralizedTime, ObjectIdentifier, OctetString, SetOfVec, UtcTime};
+use der::DateTime;
+use spki::AlgorithmIdentifier;
+use x509_cert::attr::Attribute;
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::time::Time;
+#[cfg(feature = "store")]
+use x509_cert::Certificate;
+
+use crate::{
+    utf16, CertificateTrustList, CtlError, CtlVersion, SequenceNumber, SubjectAlgorithm,
+    SubjectAttribute, SubjectId, TrustedSubject,
+};
+
+/// Builds a new [`CertificateTrustList`], e.g. for distributing an enterprise's own
+/// trust decisions via Group Policy.
+///
+/// Subjects are added either by certificate, in which case their identifiers are
+/// computed with the list's [subject algorithm](CtlBuilder::subject_algorithm), or by
+/// identifier directly. Each subject's properties are given as [`SubjectAttribute`]s,
+/// and encoded the way Microsoft encodes them.
+///
+/// ```
+/// # use windows_ctl::{eku, CtlBuilder, CtlError, ListUsage, MetaEku, SubjectAttribute, SubjectId};
+/// # fn example(id: SubjectId) -> Result<(), CtlError> {
+/// let ctl = CtlBuilder::new()
+///     .usage(ListUsage::RootListSigner)
+///     .name("Contoso Roots")
+///     .add_subject_id(
+///         id,
+///         [
+///             SubjectAttribute::FriendlyName("Contoso Root CA".into()),
+///             SubjectAttribute::MetaEkus(MetaEku(vec![eku::SERVER_AUTH])),
+///         ],
+///     )
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The result is unsigned: see [`CertificateTrustList::to_der`] for its encoding,
+/// which can then be signed.
+#[derive(Clone, Debug, Default)]
+pub struct CtlBuilder {
+    usage: Vec<ObjectIdentifier>,
+    name: Option<String>,
+    sequence_number: Option<SequenceNumber>,
+    this_update: Option<SystemTime>,
+    next_update: Option<SystemTime>,
+    algorithm: Option<SubjectAlgorithm>,
+    subjects: Vec<(PendingSubject, Vec<SubjectAttribute>)>,
+}
+
+/// A subject that's been added to a [`CtlBuilder`], but not yet identified.
+#[derive(Clone, Debug)]
+enum PendingSubject {
+    #[cfg(feature = "store")]
+    Certificate(Box<Certificate>),
+    Id(SubjectId),
+}
+
+impl CtlBuilder {
+    /// Creates a builder for an empty list, with no usage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a usage to the list. At least one is required.
+    pub fn usage(mut self, usage: impl Into<ObjectIdentifier>) -> Self {
+        self.usage.push(usage.into());
+        self
+    }
+
+    /// Sets the list's [name](CertificateTrustList::name), which is encoded as its
+    /// list identifier.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the list's sequence number.
+    pub fn sequence_number(mut self, sequence_number: SequenceNumber) -> Self {
+        self.sequence_number = Some(sequence_number);
+        self
+    }
+
+    /// Sets when the list was produced. Defaults to when it's [built](CtlBuilder::build).
+    pub fn this_update(mut self, time: SystemTime) -> Self {
+        self.this_update = Some(time);
+        self
+    }
+
+    /// Sets when the next list will be produced.
+    pub fn next_update(mut self, time: SystemTime) -> Self {
+        self.next_update = Some(time);
+        self
+    }
+
+    /// Sets the algorithm that subjects added by certificate are identified by.
+    /// Defaults to SHA-1, as Microsoft's lists use.
+    pub fn subject_algorithm(mut self, algorithm: SubjectAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    /// Adds a subject for `cert`, with the given attributes.
+    ///
+    /// Unless the attributes already include one, the subject is also given a
+    /// [`Sha256Hash`](SubjectAttribute::Sha256Hash) of the certificate, as Microsoft's
+    /// entries have, so that consumers can verify the certificate they retrieve.
+    #[cfg(feature = "store")]
+    pub fn add_subject(
+        mut self,
+        cert: &Certificate,
+        attributes: impl IntoIterator<Item = SubjectAttribute>,
+    ) -> Self {
+        self.subjects.push((
+            PendingSubject::Certificate(Box::new(cert.clone())),
+            attributes.into_iter().collect(),
+        ));
+        self
+    }
+
+    /// Adds a subject by its identifier, with the given attributes.
+    ///
+    /// The identifier is used as-is, so it should have been computed with the list's
+    /// [subject algorithm](CtlBuilder::subject_algorithm).
+    pub fn add_subject_id(
+        mut self,
+        id: SubjectId,
+        attributes: impl IntoIterator<Item = SubjectAttribute>,
+    ) -> Self {
+        self.subjects
+            .push((PendingSubject::Id(id), attributes.into_iter().collect()));
+        self
+    }
+
+    /// Builds the list.
+    ///
+    /// This fails if the list has no usage, if two subjects have the same identifier,
+    /// or if any of its times or attributes can't be encoded.
+    pub fn build(self) -> Result<CertificateTrustList, CtlError> {
+        if self.usage.is_empty() {
+            return Err(CtlError::InvalidList("no subject usage"));
+        }
+
+        let algorithm = self.algorithm.unwrap_or(SubjectAlgorithm::Sha1);
+        let mut seen = HashSet::new();
+        let subjects = self
+            .subjects
+            .into_iter()
+            .map(|(subject, attributes)| {
+                let (id, attributes) = match subject {
+                    #[cfg(feature = "store")]
+                    PendingSubject::Certificate(cert) => {
+                        use der::Encode;
+                        use sha2::{Digest, Sha256};
+
+                        let der = cert.to_der()?;
+                        let mut attributes = attributes;
+                        if !attributes
+                            .iter()
+                            .any(|attr| matches!(attr, SubjectAttribute::Sha256Hash(_)))
+                        {
+                            attributes
+                                .push(SubjectAttribute::Sha256Hash(Sha256::digest(&der).into()));
+                        }
+                        (algorithm.identify(&der), attributes)
+                    }
+                    PendingSubject::Id(id) => (id, attributes),
+                };
+
+                if !seen.insert(id.clone()) {
+                    return Err(CtlError::InvalidList("duplicate subject identifier"));
+                }
+                encode_subject(&id, &attributes)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(CertificateTrustList {
+            version: CtlVersion::V1,
+            subject_usage: ExtendedKeyUsage(self.usage),
+            list_identifier: self
+                .name
+                .map(|name| OctetString::new(utf16::encode(&name)))
+                .transpose()?,
+            sequence_number: self.sequence_number,
+            this_update: encode_time(self.this_update.unwrap_or_else(SystemTime::now))?,
+            next_update: self.next_update.map(encode_time).transpose()?,
+            subject_algorithm: AlgorithmIdentifier {
+                oid: algorithm.oid(),
+                parameters: None,
+            },
+            trusted_subjects: Some(subjects),
+            ctl_extensions: None,
+        })
+    }
+}
+
+/// Encodes a subject, grouping its attributes' values by OID.
+fn encode_subject(
+    id: &SubjectId,
+    attributes: &[SubjectAttribute],
+) -> Result<TrustedSubject, CtlError> {
+    let mut values = BTreeMap::<_, Vec<Any>>::new();
+    for attr in attributes {
+        values
+            .entry(attr.oid())
+            .or_default()
+            .push(attr.encode_value()?);
+    }
+
+    let attributes = values
+        .into_iter()
+        .map(|(oid, values)| {
+            Ok(Attribute {
+                oid,
+                values: SetOfVec::try_from(values)?,
+            })
+        })
+        .collect::<Result<Vec<_>, CtlError>>()?;
+
+    Ok(TrustedSubject {
+        identifier: OctetString::new(id.as_bytes())?,
+        attributes: (!attributes.is_empty())
+            .then(|| SetOfVec::try_from(attributes))
+            .transpose()?,
+    })
+}
+
+/// Encodes a time as a `UTCTime` where possible (i.e. before 2050), as RFC 5280 requires.
+fn encode_time(time: SystemTime) -> Result<Time, CtlError> {
+    let time = DateTime::from_system_time(time)?;
+    Ok(UtcTime::from_date_time(time)
+        .map(Time::UtcTime)
+        .unwrap_or_else(|_| Time::GeneralTime(GeneralizedTime::from_date_time(time))))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{eku, ListUsage, MetaEku};
+
+    fn unix(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_build() {
+        let ekus = MetaEku(vec![eku::SERVER_AUTH, eku::CODE_SIGNING]);
+        let ctl = CtlBuilder::new()
+            .usage(ListUsage::RootListSigner)
+            .name("Contoso Roots")
+            .sequence_number(SequenceNumber::new(&[0x01, 0x23]).unwrap())
+            .this_update(unix(1_700_000_000))
+            .next_update(unix(4_102_444_800))
+            .add_subject_id(
+                SubjectId::from(vec![0x11; 20]),
+                [
+                    SubjectAttribute::FriendlyName("Contoso Root CA".into()),
+                    SubjectAttribute::MetaEkus(ekus.clone()),
+                ],
+            )
+            .add_subject_id(SubjectId::from(vec![0x22; 20]), [])
+            .build()
+            .unwrap();
+
+        assert_eq!(ctl.kind(), crate::CtlKind::AuthRoot);
+        assert_eq!(ctl.name().unwrap().as_deref(), Some("Contoso Roots"));
+        assert_eq!(ctl.sequence_number.as_ref().unwrap().to_hex(), "0123");
+        assert!(matches!(ctl.this_update, Time::UtcTime(_)));
+        assert!(matches!(ctl.next_update, Some(Time::GeneralTime(_))));
+        assert_eq!(ctl.this_update.to_system_time(), unix(1_700_000_000));
+        assert_eq!(ctl.identifier_algorithm(), Some(SubjectAlgorithm::Sha1));
+
+        let subjects = ctl.trusted_subjects.as_ref().unwrap();
+        assert_eq!(subjects.len(), 2);
+        assert_eq!(
+            subjects[0].friendly_name().unwrap().as_deref(),
+            Some("Contoso Root CA")
+        );
+        assert_eq!(
+            subjects[0]
+                .extended_key_usages()
+                .collect::<Result<MetaEku, _>>()
+                .unwrap(),
+            ekus
+        );
+        assert_eq!(subjects[1].attributes, None);
+
+        // The list survives a round trip through DER.
+        let der = ctl.to_der().unwrap();
+        assert_eq!(
+            <CertificateTrustList as der::Decode>::from_der(&der).unwrap(),
+            ctl
+        );
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(matches!(
+            CtlBuilder::new().build(),
+            Err(CtlError::InvalidList(_))
+        ));
+
+        let id = SubjectId::from(vec![0x11; 20]);
+        assert!(matches!(
+            CtlBuilder::new()
+                .usage(ListUsage::RootListSigner)
+                .add_subject_id(id.clone(), [])
+                .add_subject_id(id, [])
+                .build(),
+            Err(CtlError::InvalidList(_))
+        ));
+    }
+
+    #[cfg(feature = "store")]
+    #[test]
+    fn test_build_from_certificate() {
+        use der::{DecodePem, Encode};
+        use sha2::{Digest, Sha256};
+
+        let cert = Certificate::from_pem(crate::testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+
+        for algorithm in [SubjectAlgorithm::Sha1, SubjectAlgorithm::Sha256] {
+            let ctl = CtlBuilder::new()
+                .usage(ListUsage::RootListSigner)
+                .add_subject(&cert, [])
+                .subject_algorithm(algorithm)
+                .build()
+                .unwrap();
+
+            let entry = ctl.find(&der).unwrap();
+            assert_eq!(
+                entry.sha256_fingerprint().unwrap(),
+                Some(Sha256::digest(&der).into())
+            );
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Loading CTLs from Windows cabinet (`.cab`) archives.
+
+use std::io::{Read, Seek};
+
+use der::DateTime;
+
+use crate::{stream, CertificateTrustList, CtlError, CtlMessage, RawCtl};
+
+/// Metadata about a single member of a cabinet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CabinetMember {
+    /// The member's name.
+    pub name: String,
+    /// The member's uncompressed size, in bytes.
+    pub size: u32,
+    /// The member's modification time, if valid. Cabinets record local time,
+    /// so this has no particular timezone.
+    pub modified: Option<DateTime>,
+    /// Whether the member looks like a CTL (i.e. would be loaded by
+    /// [`CertificateTrustList::all_from_cab`]).
+    pub is_ctl: bool,
+}
+
+/// Lists every member of the given cabinet, without decompressing any of them.
+pub fn cabinet_members<R: Read + Seek>(source: R) -> Result<Vec<CabinetMember>, CtlError> {
+    let cabinet = cab::Cabinet::new(source)?;
+
+    Ok(cabinet
+        .folder_entries()
+        .flat_map(|folder| folder.file_entries())
+        .map(|file| CabinetMember {
+            name: file.name().into(),
+            size: file.uncompressed_size(),
+            modified: file.datetime().and_then(|dt| {
+                DateTime::new(
+                    dt.year().try_into().ok()?,
+                    dt.month().into(),
+                    dt.day(),
+                    dt.hour(),
+                    dt.minute(),
+                    dt.second(),
+                )
+                .ok()
+            }),
+            is_ctl: is_ctl_member(file.name()),
+        })
+        .collect())
+}
+
+/// Returns whether the given cabinet member looks like a CTL, based on its name.
+fn is_ctl_member(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".stl")
+}
+
+impl CertificateTrustList {
+    /// Load a `CertificateTrustList` from the given cabinet (e.g. `authrootstl.cab`).
+    ///
+    /// The cabinet must contain exactly one CTL (`.stl`) member; use
+    /// [`CertificateTrustList::all_from_cab`] for cabinets that contain several.
+    ///
+    /// The CTL is decoded as it's decompressed, rather than being decompressed into
+    /// memory first.
+    pub fn from_cab<R: Read + Seek>(source: R) -> Result<Self, CtlError> {
+        let mut cabinet = cab::Cabinet::new(source)?;
+        let name = single_ctl_member(&cabinet)?;
+        stream::decode(cabinet.read_file(&name)?)
+    }
+
+    /// Load every `CertificateTrustList` from the given cabinet, each tagged with the name
+    /// of the cabinet member it was loaded from.
+    ///
+    /// Members are considered CTLs if they have an `.stl` extension.
+    pub fn all_from_cab<R: Read + Seek>(source: R) -> Result<Vec<(String, Self)>, CtlError> {
+        let mut cabinet = cab::Cabinet::new(source)?;
+
+        ctl_members(&cabinet)
+            .into_iter()
+            .map(|name| {
+                let ctl = stream::decode(cabinet.read_file(&name)?)?;
+                Ok((name, ctl))
+            })
+            .collect()
+    }
+}
+
+impl RawCtl {
+    /// Load the raw DER of the CTL in the given cabinet, e.g. to extract it unchanged.
+    ///
+    /// As with [`CertificateTrustList::from_cab`], the cabinet must contain exactly one
+    /// CTL (`.stl`) member.
+    pub fn from_cab<R: Read + Seek>(source: R) -> Result<Self, CtlError> {
+        let mut cabinet = cab::Cabinet::new(source)?;
+        let name = single_ctl_member(&cabinet)?;
+        Self::new(read_member(&mut cabinet, &name)?)
+    }
+}
+
+impl CtlMessage {
+    /// Load a `CtlMessage` from the given cabinet, retaining the envelope of the CTL in
+    /// it. See [`CertificateTrustList::from_cab`].
+    pub fn from_cab<R: Read + Seek>(source: R) -> Result<Self, CtlError> {
+        RawCtl::from_cab(source)?.try_into()
+    }
+}
+
+/// Returns the name of the only CTL member in the given cabinet.
+///
+/// This checks for ambiguity before decompressing anything, so that only the one CTL
+/// that's going to be returned is ever read.
+fn single_ctl_member<R: Read + Seek>(cabinet: &cab::Cabinet<R>) -> Result<String, CtlError> {
+    let mut names = ctl_members(cabinet);
+    match names.len() {
+        0 => Err(CtlError::MissingCabinetCtl),
+        1 => Ok(names.remove(0)),
+        _ => Err(CtlError::AmbiguousCabinet(names)),
+    }
+}
+
+/// Decompresses the named member of the given cabinet in full.
+///
+/// The buffer is sized from the member's entry, rather than by seeking its reader,
+/// which would decompress it again.
+fn read_member<R: Read + Seek>(
+    cabinet: &mut cab::Cabinet<R>,
+    name: &str,
+) -> Result<Vec<u8>, CtlError> {
+    let size = cabinet
+        .get_file_entry(name)
+        .map_or(0, |file| file.uncompressed_size());
+    let mut der = Vec::with_capacity(size as usize);
+    cabinet.read_file(name)?.read_to_end(&mut der)?;
+    Ok(der)
+}
+
+/// Returns the names of every CTL member in the given cabinet.
+fn ctl_members<R: Read + Seek>(cabinet: &cab::Cabinet<R>) -> Vec<String> {
+    cabinet
+        .folder_entries()
+        .flat_map(|folder| folder.file_entries())
+        .map(|file| file.name())
+        .filter(|name| is_ctl_member(name))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+    use crate::testing;
+
+    fn cabinet(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = cab::CabinetBuilder::new();
+        let folder = builder.add_folder(cab::CompressionType::MsZip);
+        for (name, _) in members {
+            folder.add_file(*name);
+        }
+
+        let mut writer = builder.build(Cursor::new(vec![])).unwrap();
+        for (_, contents) in members {
+            writer
+                .next_file()
+                .unwrap()
+                .unwrap()
+                .write_all(contents)
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_all_from_cab() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        let cab = cabinet(&[
+            ("authroot.stl", &der),
+            ("README.txt", b"not a CTL"),
+            ("Disallowed.STL", &der),
+        ]);
+
+        let ctls = CertificateTrustList::all_from_cab(Cursor::new(&cab)).unwrap();
+        assert_eq!(ctls.len(), 2);
+        assert_eq!(ctls[0], ("authroot.stl".into(), ctl.clone()));
+        assert_eq!(ctls[1], ("Disallowed.STL".into(), ctl));
+
+        assert!(matches!(
+            CertificateTrustList::from_cab(Cursor::new(&cab)),
+            Err(CtlError::AmbiguousCabinet(names)) if names.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_cabinet_members() {
+        let der = testing::sign_ctl(&testing::sample_ctl());
+        let cab = cabinet(&[("authroot.stl", &der), ("README.txt", b"not a CTL")]);
+
+        let members = cabinet_members(Cursor::new(&cab)).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "authroot.stl");
+        assert_eq!(members[0].size as usize, der.len());
+        assert!(members[0].is_ctl);
+        assert_eq!(members[1].name, "README.txt");
+        assert_eq!(members[1].size, 9);
+        assert!(!members[1].is_ctl);
+    }
+
+    #[test]
+    fn test_from_cab() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        let cab = cabinet(&[("authroot.stl", &der)]);
+        assert_eq!(
+            CertificateTrustList::from_cab(Cursor::new(&cab)).unwrap(),
+            ctl
+        );
+        assert_eq!(RawCtl::from_cab(Cursor::new(&cab)).unwrap().as_bytes(), der);
+        let message = CtlMessage::from_cab(Cursor::new(&cab)).unwrap();
+        assert_eq!(message.ctl(), &ctl);
+        assert_eq!(message.raw().as_bytes(), der);
+
+        // Ambiguity is detected before any members are parsed.
+        let cab = cabinet(&[("authroot.stl", &der), ("junk.stl", b"not DER")]);
+        assert!(matches!(
+            CertificateTrustList::from_cab(Cursor::new(&cab)),
+            Err(CtlError::AmbiguousCabinet(_))
+        ));
+
+        let cab = cabinet(&[("README.txt", b"not a CTL")]);
+        assert!(matches!(
+            CertificateTrustList::from_cab(Cursor::new(&cab)),
+            Err(CtlError::MissingCabinetCtl)
+        ));
+        assert!(matches!(
+            RawCtl::from_cab(Cursor::new(&cab)),
+            Err(CtlError::MissingCabinetCtl)
+        ));
+    }
+}
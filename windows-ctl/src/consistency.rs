@@ -0,0 +1,204 @@
+//! Consistency checks between a root list and the disallowed list.
+//!
+//! Each list is well-formed on its own (see [`CertificateTrustList::warnings`]), but
+//! Microsoft's data doesn't always agree with itself. The checks here surface the
+//! inconsistencies that consumers have to decide how to handle.
+
+use std::collections::HashSet;
+
+use der::asn1::ObjectIdentifier;
+use thiserror::Error;
+
+use crate::{CertificateTrustList, DecodedTrustedSubject, MetaEku, SubjectId};
+
+/// An inconsistency between a root list and the disallowed list, or within a root
+/// list entry.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum Inconsistency {
+    /// An identifier that the disallowed list distrusts, but that the root list still
+    /// trusts for some EKUs.
+    #[error("{subject}: disallowed, but still trusted by the root list for {} EKU(s)", trusted_for.len())]
+    TrustedAndDisallowed {
+        /// The entry's identifier.
+        subject: SubjectId,
+        /// The EKUs the root list still trusts the entry for.
+        trusted_for: MetaEku,
+    },
+
+    /// A root list entry that's distrusted for EKUs that its MetaEKUs don't trust it
+    /// for, so that either its MetaEKUs or its distrust is out of date.
+    #[error("{subject}: distrusted for {} EKU(s) it isn't trusted for", ekus.len())]
+    ContradictoryEkus {
+        /// The entry's identifier.
+        subject: SubjectId,
+        /// The disallowed EKUs that aren't among the entry's MetaEKUs.
+        ekus: MetaEku,
+    },
+}
+
+impl Inconsistency {
+    /// Returns the identifier of the entry this inconsistency concerns.
+    pub fn subject(&self) -> &SubjectId {
+        match self {
+            Self::TrustedAndDisallowed { subject, .. } => subject,
+            Self::ContradictoryEkus { subject, .. } => subject,
+        }
+    }
+
+    /// Returns the EKUs this inconsistency concerns.
+    pub fn ekus(&self) -> &MetaEku {
+        match self {
+            Self::TrustedAndDisallowed { trusted_for, .. } => trusted_for,
+            Self::ContradictoryEkus { ekus, .. } => ekus,
+        }
+    }
+}
+
+impl CertificateTrustList {
+    /// Checks this (root) list against `disallowed`, the disallowed list, returning an
+    /// [`Inconsistency`] for each one found.
+    ///
+    /// Entries are matched by identifier, so both lists should use the same
+    /// `subjectAlgorithm`. Entries with malformed attributes are skipped; they're
+    /// reported by [`warnings`](CertificateTrustList::warnings) instead.
+    pub fn cross_check(&self, disallowed: &CertificateTrustList) -> Vec<Inconsistency> {
+        let distrusted = disallowed
+            .decoded_subjects()
+            .flatten()
+            .map(|entry| entry.subject().cert_id())
+            .collect::<HashSet<_>>();
+
+        let mut inconsistencies = vec![];
+        for entry in self.decoded_subjects().flatten() {
+            let subject = entry.subject().cert_id();
+            let ekus = entry.extended_key_usages();
+
+            if let Some(disallowed_ekus) = entry.disallowed_ekus() {
+                let untrusted = disallowed_ekus
+                    .iter()
+                    .filter(|eku| !ekus.contains(eku))
+                    .copied()
+                    .collect::<MetaEku>();
+                if !ekus.is_empty() && !untrusted.is_empty() {
+                    inconsistencies.push(Inconsistency::ContradictoryEkus {
+                        subject: subject.clone(),
+                        ekus: untrusted,
+                    });
+                }
+            }
+
+            if distrusted.contains(&subject) {
+                let trusted_for = ekus
+                    .iter()
+                    .filter(|eku| !distrusts(&entry, eku))
+                    .copied()
+                    .collect::<MetaEku>();
+                if !trusted_for.is_empty() {
+                    inconsistencies.push(Inconsistency::TrustedAndDisallowed {
+                        subject,
+                        trusted_for,
+                    });
+                }
+            }
+        }
+
+        inconsistencies
+    }
+}
+
+/// Whether `entry` is itself distrusted for `eku`: either it's explicitly disallowed
+/// for it, or it's been disallowed without any EKUs (i.e. for all of them).
+fn distrusts(entry: &DecodedTrustedSubject<'_>, eku: &ObjectIdentifier) -> bool {
+    match entry.disallowed_ekus() {
+        Some(disallowed) => disallowed.contains(eku),
+        None => entry.disallowed_at().is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::Encode;
+
+    use super::*;
+    use crate::{
+        eku, testing, usage, MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+        MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, MS_CERT_PROP_ID_METAEKUS_OID,
+    };
+
+    #[test]
+    fn test_cross_check() {
+        let ekus = |oids: &[_]| MetaEku(oids.to_vec()).to_der().unwrap();
+        let meta = |oids| testing::attribute(MS_CERT_PROP_ID_METAEKUS_OID, &ekus(oids));
+        let disallowed_ekus =
+            |oids| testing::attribute(MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, &ekus(oids));
+        let disallowed_at = testing::attribute(
+            MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+            &132_223_104_000_000_000u64.to_le_bytes(),
+        );
+
+        let mut roots = testing::sample_ctl();
+        roots.trusted_subjects = Some(vec![
+            // Disallowed, and still trusted for everything.
+            testing::subject(&[0x11; 20], vec![meta(&[eku::SERVER_AUTH])]),
+            // Disallowed, and distrusted by the root list too.
+            testing::subject(
+                &[0x22; 20],
+                vec![meta(&[eku::SERVER_AUTH]), disallowed_at.clone()],
+            ),
+            // Disallowed, and only partially distrusted by the root list.
+            testing::subject(
+                &[0x33; 20],
+                vec![
+                    meta(&[eku::SERVER_AUTH, eku::CODE_SIGNING]),
+                    disallowed_at,
+                    disallowed_ekus(&[eku::SERVER_AUTH]),
+                ],
+            ),
+            // Not disallowed, but distrusted for an EKU it isn't trusted for.
+            testing::subject(
+                &[0x44; 20],
+                vec![
+                    meta(&[eku::SERVER_AUTH]),
+                    disallowed_ekus(&[eku::SERVER_AUTH, eku::CODE_SIGNING]),
+                ],
+            ),
+        ]);
+
+        let mut disallowed = testing::sample_ctl();
+        disallowed.subject_usage.0 = vec![usage::DISALLOWED_LIST];
+        disallowed.trusted_subjects = Some(
+            [0x11, 0x22, 0x33]
+                .map(|id| testing::subject(&[id; 20], vec![]))
+                .to_vec(),
+        );
+
+        assert_eq!(
+            roots.cross_check(&disallowed),
+            [
+                Inconsistency::TrustedAndDisallowed {
+                    subject: SubjectId::from(vec![0x11; 20]),
+                    trusted_for: MetaEku(vec![eku::SERVER_AUTH]),
+                },
+                Inconsistency::TrustedAndDisallowed {
+                    subject: SubjectId::from(vec![0x33; 20]),
+                    trusted_for: MetaEku(vec![eku::CODE_SIGNING]),
+                },
+                Inconsistency::ContradictoryEkus {
+                    subject: SubjectId::from(vec![0x44; 20]),
+                    ekus: MetaEku(vec![eku::CODE_SIGNING]),
+                },
+            ]
+        );
+
+        let inconsistencies = roots.cross_check(&disallowed);
+        assert_eq!(
+            inconsistencies[2].subject(),
+            &SubjectId::from(vec![0x44; 20])
+        );
+        assert_eq!(inconsistencies[2].ekus(), &MetaEku(vec![eku::CODE_SIGNING]));
+
+        disallowed.trusted_subjects = None;
+        assert_eq!(roots.cross_check(&disallowed).len(), 1);
+    }
+}
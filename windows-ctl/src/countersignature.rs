@@ -0,0 +1,284 @@
+//! Timestamps on a [`CtlMessage`](crate::CtlMessage)'s signatures.
+//!
+//! A signer's `signingTime` (see [`SignerMetadata::signing_time`]) is only its own
+//! claim. A timestamp is a third party's signature over the signer's signature,
+//! vouching that it existed at a given time. Signers attach timestamps as unsigned
+//! attributes, either as PKCS#7 countersignatures
+//! ([RFC 2315, section 11.4](https://www.rfc-editor.org/rfc/rfc2315#section-11.4)) or
+//! as RFC 3161 timestamp tokens
+//! ([RFC 3161, appendix A](https://www.rfc-editor.org/rfc/rfc3161#appendix-A)).
+//!
+//! Extracting a timestamp doesn't verify it: see `Verifier::verify_timestamp` (with
+//! the `verify` feature).
+
+use std::time::SystemTime;
+
+use der::asn1::{GeneralizedTime, IntRef, ObjectIdentifier, OctetStringRef};
+use der::referenced::RefToOwned;
+use der::{Decode, DecodeValue, Encode, FixedTag, Header, Reader, Sequence, Tag};
+use pkcs7::ContentInfo;
+use spki::{AlgorithmIdentifierOwned, AlgorithmIdentifierRef};
+use x509_cert::Certificate;
+
+use crate::envelope::{embedded_certificates, signers};
+use crate::{CtlError, SignerMetadata, MESSAGE_DIGEST_OID};
+
+/// The PKCS#9 `countersignature` attribute, whose values are `SignerInfo`s over the
+/// signature they're attached to.
+pub const COUNTERSIGNATURE_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.6");
+
+/// The `id-aa-timeStampToken` attribute, whose values are RFC 3161 timestamp tokens.
+pub const TIMESTAMP_TOKEN_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.14");
+
+/// Microsoft's `szOID_RFC3161_counterSign` attribute, which Authenticode uses in place
+/// of [`TIMESTAMP_TOKEN_OID`].
+pub const MS_TIMESTAMP_TOKEN_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.3.3.1");
+
+/// The `id-ct-TSTInfo` content type, which timestamp tokens encapsulate.
+pub(crate) const TST_INFO_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// How a [`Timestamp`] is encoded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TimestampKind {
+    /// A PKCS#9 countersignature, whose signer asserts a `signingTime`.
+    Countersignature,
+    /// An RFC 3161 timestamp token, issued by a time-stamping authority.
+    Token,
+}
+
+/// A timestamp on one of a message's signatures, as extracted by
+/// [`SignerMetadata::timestamps`].
+///
+/// Nothing here has been verified: this only reports what the timestamp claims.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Timestamp {
+    /// How the timestamp is encoded.
+    pub kind: TimestampKind,
+    /// The time the timestamp vouches for: a token's `genTime`, or a
+    /// countersignature's `signingTime`.
+    pub time: SystemTime,
+    /// The algorithm that [`imprint`](Self::imprint) was digested with.
+    pub imprint_algorithm: AlgorithmIdentifierOwned,
+    /// The digest of the timestamped signature: a token's `messageImprint`, or a
+    /// countersignature's `messageDigest`.
+    pub imprint: Vec<u8>,
+    /// Whoever signed the timestamp: the time-stamping authority, or the
+    /// countersigner.
+    pub signer: SignerMetadata,
+    /// The certificates embedded in a timestamp token. Countersignatures have none of
+    /// their own (their signers' certificates are embedded in the message itself), so
+    /// their [`signer`](Self::signer)'s certificate is never filled in.
+    pub certificates: Vec<Certificate>,
+    content: Vec<u8>,
+}
+
+impl Timestamp {
+    /// Returns what the timestamp's [`signer`](Self::signer) signed: a token's
+    /// DER-encoded `TSTInfo`, or the countersigned signature.
+    pub fn signed_content(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl SignerMetadata {
+    /// Extracts the timestamps on this signer's signature from its unsigned
+    /// attributes.
+    ///
+    /// Fails if any countersignature or timestamp token is malformed, including
+    /// countersignatures that don't assert a `signingTime`.
+    pub fn timestamps(&self) -> Result<Vec<Timestamp>, CtlError> {
+        let mut timestamps = vec![];
+        for attr in &self.unsigned_attributes {
+            let extract = match attr.oid {
+                COUNTERSIGNATURE_OID => countersignature,
+                TIMESTAMP_TOKEN_OID | MS_TIMESTAMP_TOKEN_OID => token,
+                _ => continue,
+            };
+            for value in attr.values.iter() {
+                let timestamp = extract(&value.to_der()?, &self.signature).map_err(|reason| {
+                    CtlError::Attribute {
+                        oid: attr.oid,
+                        reason,
+                    }
+                })?;
+                timestamps.push(timestamp);
+            }
+        }
+        Ok(timestamps)
+    }
+}
+
+/// Extracts a countersignature, whose `SignerInfo` (encoded as `der`) signs
+/// `signature`.
+fn countersignature(der: &[u8], signature: &[u8]) -> Result<Timestamp, &'static str> {
+    let signer = SignerMetadata::from_der(der, &[]).map_err(|_| "malformed countersignature")?;
+    let time = signer
+        .signing_time()
+        .map_err(|_| "malformed signing time")?
+        .ok_or("countersignature has no signing time")?;
+    let imprint = signer
+        .signed_attribute_value(MESSAGE_DIGEST_OID)
+        .map_err(|_| "countersignature has no message digest")?
+        .decode_as::<OctetStringRef<'_>>()
+        .map_err(|_| "malformed message digest")?
+        .as_bytes()
+        .to_vec();
+
+    Ok(Timestamp {
+        kind: TimestampKind::Countersignature,
+        time,
+        imprint_algorithm: signer.digest_algorithm.clone(),
+        imprint,
+        signer,
+        certificates: vec![],
+        content: signature.to_vec(),
+    })
+}
+
+/// Extracts an RFC 3161 timestamp token, encoded as `der`.
+fn token(der: &[u8], _signature: &[u8]) -> Result<Timestamp, &'static str> {
+    let ContentInfo::SignedData(signed_data) =
+        ContentInfo::from_der(der).map_err(|_| "malformed timestamp token")?
+    else {
+        return Err("timestamp token isn't SignedData");
+    };
+
+    let encap = signed_data.encap_content_info;
+    if encap.e_content_type != TST_INFO_OID {
+        return Err("timestamp token doesn't hold a TSTInfo");
+    }
+    let content = encap
+        .e_content
+        .ok_or("timestamp token has no TSTInfo")?
+        .decode_as::<OctetStringRef<'_>>()
+        .map_err(|_| "malformed TSTInfo")?
+        .as_bytes()
+        .to_vec();
+    let tst_info = TstInfo::from_der(&content).map_err(|_| "malformed TSTInfo")?;
+
+    let certificates = embedded_certificates(&signed_data);
+    let signers = signers(der, &certificates).map_err(|_| "malformed timestamp token")?;
+    let Ok([signer]) = <[_; 1]>::try_from(signers) else {
+        return Err("timestamp token must have exactly one signer");
+    };
+
+    Ok(Timestamp {
+        kind: TimestampKind::Token,
+        time: tst_info.gen_time.to_system_time(),
+        imprint_algorithm: tst_info.message_imprint.hash_algorithm.ref_to_owned(),
+        imprint: tst_info.message_imprint.hashed_message.as_bytes().to_vec(),
+        signer,
+        certificates,
+        content,
+    })
+}
+
+/// The fields of an RFC 3161 `TSTInfo` that describe what was timestamped, and when.
+/// The rest (such as its accuracy and nonce) are skipped.
+struct TstInfo<'a> {
+    message_imprint: MessageImprint<'a>,
+    gen_time: GeneralizedTime,
+}
+
+/// An RFC 3161 `MessageImprint`: the digest of the timestamped data.
+#[derive(Sequence)]
+pub(crate) struct MessageImprint<'a> {
+    pub(crate) hash_algorithm: AlgorithmIdentifierRef<'a>,
+    pub(crate) hashed_message: OctetStringRef<'a>,
+}
+
+impl<'a> DecodeValue<'a> for TstInfo<'a> {
+    fn decode_value<R: Reader<'a>>(reader: &mut R, header: Header) -> der::Result<Self> {
+        reader.read_nested(header.length, |reader| {
+            let _version = u8::decode(reader)?;
+            let _policy = ObjectIdentifier::decode(reader)?;
+            let message_imprint = reader.decode()?;
+            let _serial_number = IntRef::decode(reader)?;
+            let gen_time = reader.decode()?;
+
+            let rest = reader.remaining_len();
+            reader.read_slice(rest)?;
+            Ok(Self {
+                message_imprint,
+                gen_time,
+            })
+        })
+    }
+}
+
+impl FixedTag for TstInfo<'_> {
+    const TAG: Tag = Tag::Sequence;
+}
+
+#[cfg(test)]
+mod tests {
+    use der::asn1::{Any, SetOfVec};
+    use der::DecodePem;
+    use x509_cert::attr::Attribute;
+
+    use super::*;
+    use crate::{testing, CtlMessage};
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_timestamps() {
+        use std::time::Duration;
+
+        use sha2::{Digest, Sha256};
+
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000);
+        let tsa = Certificate::from_pem(testing::TSA).unwrap();
+        let der = testing::timestamped_ctl(time, &tsa);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let signer = &message.signers()[0];
+
+        let timestamps = signer.timestamps().unwrap();
+        assert_eq!(timestamps.len(), 2);
+        for timestamp in &timestamps {
+            assert_eq!(timestamp.time, time);
+            assert_eq!(
+                timestamp.imprint,
+                Sha256::digest(&signer.signature).as_slice()
+            );
+        }
+
+        let of_kind = |kind| timestamps.iter().find(|ts| ts.kind == kind).unwrap();
+        let token = of_kind(TimestampKind::Token);
+        assert_eq!(token.certificates, std::slice::from_ref(&tsa));
+        assert_eq!(token.signer.certificate, Some(tsa));
+        assert!(TstInfo::from_der(token.signed_content()).is_ok());
+
+        let countersignature = of_kind(TimestampKind::Countersignature);
+        assert!(countersignature.certificates.is_empty());
+        assert_eq!(countersignature.signer.certificate, None);
+        assert_eq!(countersignature.signed_content(), signer.signature);
+    }
+
+    #[test]
+    fn test_timestamps_malformed() {
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = testing::sign_ctl_with(&ctl, Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(message.signers()[0].timestamps().unwrap().is_empty());
+
+        let der = testing::add_unsigned_attribute(
+            &der,
+            Attribute {
+                oid: MS_TIMESTAMP_TOKEN_OID,
+                values: SetOfVec::try_from(vec![Any::from(der::asn1::Null)]).unwrap(),
+            },
+        );
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(matches!(
+            message.signers()[0].timestamps(),
+            Err(CtlError::Attribute { oid, .. }) if oid == MS_TIMESTAMP_TOKEN_OID
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+//! Conversions of lists' and subjects' times into `time::OffsetDateTime`s (with the
+//! `time` feature) and `chrono::DateTime<Utc>`s (with the `chrono` feature).
+
+use std::time::SystemTime;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeDelta, Utc};
+use der::asn1::ObjectIdentifier;
+#[cfg(feature = "time")]
+use time::OffsetDateTime;
+use x509_cert::time::Time;
+
+use crate::{
+    CertificateTrustList, CtlError, TrustedSubject, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+    MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+};
+
+#[cfg(feature = "time")]
+impl CertificateTrustList {
+    /// Returns [`this_update`](CertificateTrustList::this_update) as an [`OffsetDateTime`]
+    /// (in UTC).
+    pub fn this_update_datetime(&self) -> OffsetDateTime {
+        from_time(&self.this_update)
+    }
+
+    /// Returns [`next_update`](CertificateTrustList::next_update) as an [`OffsetDateTime`]
+    /// (in UTC), if the list has one.
+    pub fn next_update_datetime(&self) -> Option<OffsetDateTime> {
+        self.next_update.as_ref().map(from_time)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TrustedSubject {
+    /// Returns [`disallowed_at`](TrustedSubject::disallowed_at) as an [`OffsetDateTime`]
+    /// (in UTC).
+    ///
+    /// `FILETIME`s reach far beyond the years that `OffsetDateTime` can represent;
+    /// those are reported as malformed.
+    pub fn disallowed_at_datetime(&self) -> Result<Option<OffsetDateTime>, CtlError> {
+        self.disallowed_at()?
+            .map(|time| from_filetime(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, time))
+            .transpose()
+    }
+
+    /// Returns [`not_before`](TrustedSubject::not_before) as an [`OffsetDateTime`] (in
+    /// UTC).
+    ///
+    /// As with [`disallowed_at_datetime`](TrustedSubject::disallowed_at_datetime), times
+    /// that `OffsetDateTime` can't represent are reported as malformed.
+    pub fn not_before_datetime(&self) -> Result<Option<OffsetDateTime>, CtlError> {
+        self.not_before()?
+            .map(|time| from_filetime(MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, time))
+            .transpose()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl CertificateTrustList {
+    /// Returns [`this_update`](CertificateTrustList::this_update) as a [`DateTime<Utc>`].
+    pub fn this_update_chrono(&self) -> DateTime<Utc> {
+        chrono_from_time(&self.this_update)
+    }
+
+    /// Returns [`next_update`](CertificateTrustList::next_update) as a [`DateTime<Utc>`],
+    /// if the list has one.
+    pub fn next_update_chrono(&self) -> Option<DateTime<Utc>> {
+        self.next_update.as_ref().map(chrono_from_time)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TrustedSubject {
+    /// Returns [`disallowed_at`](TrustedSubject::disallowed_at) as a [`DateTime<Utc>`].
+    ///
+    /// Unlike `OffsetDateTime`, `DateTime` reaches beyond every `FILETIME`, so this
+    /// only fails if the attribute itself is malformed.
+    pub fn disallowed_at_chrono(&self) -> Result<Option<DateTime<Utc>>, CtlError> {
+        self.disallowed_at()?
+            .map(|time| chrono_from_filetime(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, time))
+            .transpose()
+    }
+
+    /// Returns [`not_before`](TrustedSubject::not_before) as a [`DateTime<Utc>`].
+    pub fn not_before_chrono(&self) -> Result<Option<DateTime<Utc>>, CtlError> {
+        self.not_before()?
+            .map(|time| chrono_from_filetime(MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, time))
+            .transpose()
+    }
+}
+
+/// X.509 times only go up to 9999, so they always fit.
+#[cfg(feature = "time")]
+fn from_time(time: &Time) -> OffsetDateTime {
+    OffsetDateTime::UNIX_EPOCH + time.to_unix_duration()
+}
+
+/// As with [`from_time`], X.509 times always fit.
+#[cfg(feature = "chrono")]
+fn chrono_from_time(time: &Time) -> DateTime<Utc> {
+    time.to_system_time().into()
+}
+
+#[cfg(feature = "time")]
+fn from_filetime(oid: ObjectIdentifier, time: SystemTime) -> Result<OffsetDateTime, CtlError> {
+    let nanos = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(before) => -(before.duration().as_nanos() as i128),
+    };
+
+    OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| CtlError::Attribute {
+        oid,
+        reason: "FILETIME out of range",
+    })
+}
+
+#[cfg(feature = "chrono")]
+fn chrono_from_filetime(
+    oid: ObjectIdentifier,
+    time: SystemTime,
+) -> Result<DateTime<Utc>, CtlError> {
+    let delta = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => TimeDelta::from_std(since).ok(),
+        Err(before) => TimeDelta::from_std(before.duration())
+            .ok()
+            .map(|delta| -delta),
+    };
+
+    delta
+        .and_then(|delta| DateTime::UNIX_EPOCH.checked_add_signed(delta))
+        .ok_or(CtlError::Attribute {
+            oid,
+            reason: "FILETIME out of range",
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use der::asn1::GeneralizedTime;
+
+    use super::*;
+    use crate::testing;
+
+    #[cfg(feature = "time")]
+    fn unix(secs: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(secs).unwrap()
+    }
+
+    #[cfg(feature = "chrono")]
+    fn chrono_unix(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    fn filetime(ticks: u64) -> x509_cert::attr::Attribute {
+        testing::attribute(
+            MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+            &ticks.to_le_bytes(),
+        )
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_ctl_datetimes() {
+        let mut ctl = testing::sample_ctl();
+        assert_eq!(ctl.this_update_datetime(), unix(1_700_000_000));
+        assert_eq!(ctl.next_update_datetime(), None);
+
+        ctl.next_update = Some(Time::GeneralTime(
+            GeneralizedTime::from_unix_duration(Duration::from_secs(4_102_444_800)).unwrap(),
+        ));
+        assert_eq!(ctl.next_update_datetime(), Some(unix(4_102_444_800)));
+        assert_eq!(ctl.next_update_datetime().unwrap().year(), 2100);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_subject_datetimes() {
+        // 2020-01-01.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(132_223_104_000_000_000)]);
+        assert_eq!(
+            subject.disallowed_at_datetime().unwrap(),
+            Some(unix(1_577_836_800))
+        );
+        assert_eq!(subject.not_before_datetime().unwrap(), None);
+
+        // FILETIME's epoch, before the Unix epoch.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(0)]);
+        assert_eq!(
+            subject.disallowed_at_datetime().unwrap(),
+            Some(unix(-11_644_473_600))
+        );
+
+        // Far beyond what `OffsetDateTime` can represent.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(u64::MAX)]);
+        assert!(matches!(
+            subject.disallowed_at_datetime(),
+            Err(CtlError::Attribute { .. })
+        ));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_ctl_chrono() {
+        use chrono::Datelike;
+
+        let mut ctl = testing::sample_ctl();
+        assert_eq!(ctl.this_update_chrono(), chrono_unix(1_700_000_000));
+        assert_eq!(ctl.next_update_chrono(), None);
+
+        ctl.next_update = Some(Time::GeneralTime(
+            GeneralizedTime::from_unix_duration(Duration::from_secs(4_102_444_800)).unwrap(),
+        ));
+        assert_eq!(ctl.next_update_chrono(), Some(chrono_unix(4_102_444_800)));
+        assert_eq!(ctl.next_update_chrono().unwrap().year(), 2100);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_subject_chrono() {
+        use chrono::Datelike;
+
+        // 2020-01-01.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(132_223_104_000_000_000)]);
+        assert_eq!(
+            subject.disallowed_at_chrono().unwrap(),
+            Some(chrono_unix(1_577_836_800))
+        );
+        assert_eq!(subject.not_before_chrono().unwrap(), None);
+
+        // FILETIME's epoch, before the Unix epoch.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(0)]);
+        assert_eq!(
+            subject.disallowed_at_chrono().unwrap(),
+            Some(chrono_unix(-11_644_473_600))
+        );
+
+        // Unlike `OffsetDateTime`, `DateTime` reaches as far as `FILETIME` does.
+        let subject = testing::subject(&[0x11; 20], vec![filetime(u64::MAX)]);
+        assert_eq!(
+            subject.disallowed_at_chrono().unwrap().unwrap().year(),
+            60056
+        );
+    }
+}
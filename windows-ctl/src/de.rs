@@ -0,0 +1,262 @@
+//! [`Deserialize`] implementations mirroring the [`Serialize`](serde::Serialize) ones,
+//! so that serialized lists (such as `ctltool dump`'s output) can be loaded back in.
+//!
+//! Serialization is lossy: entries only carry their identifier, friendly name, EKUs,
+//! and distrust times, so deserialized entries only have those attributes. Similarly, deserialized
+//! lists have no extensions, and no `subjectAlgorithm` parameters.
+
+use std::str::FromStr;
+
+use der::asn1::{Any, GeneralizedTime, ObjectIdentifier, OctetString, SetOfVec, UtcTime};
+use der::{DateTime, Encode, Tag};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use spki::AlgorithmIdentifier;
+use x509_cert::attr::Attribute;
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::time::Time;
+
+use crate::attributes::encode_filetime;
+use crate::{
+    timestamp, utf16, CertificateTrustList, CtlVersion, MetaEku, SequenceNumber, SubjectId,
+    TrustedSubject, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, MS_CERT_PROP_ID_FRIENDLY_NAME_OID,
+    MS_CERT_PROP_ID_METAEKUS_OID, MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+};
+
+/// The serialized form of a [`TrustedSubject`].
+#[derive(Deserialize)]
+struct SubjectRepr {
+    identifier: SubjectId,
+    #[serde(default)]
+    friendly_name: Option<String>,
+    #[serde(default)]
+    ekus: Vec<String>,
+    #[serde(default)]
+    disallowed_at: Option<String>,
+    #[serde(default)]
+    not_before: Option<String>,
+}
+
+/// The serialized form of a [`CertificateTrustList`].
+#[derive(Deserialize)]
+struct ListRepr {
+    version: u8,
+    usage: Vec<String>,
+    #[serde(default)]
+    list_identifier: Option<String>,
+    #[serde(default)]
+    sequence_number: Option<SequenceNumber>,
+    this_update: String,
+    #[serde(default)]
+    next_update: Option<String>,
+    subject_algorithm: String,
+    #[serde(default)]
+    entries: Vec<TrustedSubject>,
+}
+
+/// Deserializes an entry from its identifier, friendly name, EKUs, and distrust times.
+///
+/// The friendly name, EKUs, and times are re-encoded as attributes the way Microsoft encodes
+/// them, and an empty list of EKUs is treated as the attribute's absence.
+impl<'de> Deserialize<'de> for TrustedSubject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SubjectRepr::deserialize(deserializer)?;
+
+        let mut attributes = vec![];
+        if let Some(name) = repr.friendly_name {
+            let name = utf16::encode(&name);
+            attributes.push(property(MS_CERT_PROP_ID_FRIENDLY_NAME_OID, &name)?);
+        }
+        if !repr.ekus.is_empty() {
+            let ekus = repr
+                .ekus
+                .iter()
+                .map(|oid| oid_from_str(oid))
+                .collect::<Result<MetaEku, _>>()?;
+            let der = ekus.to_der().map_err(D::Error::custom)?;
+            attributes.push(property(MS_CERT_PROP_ID_METAEKUS_OID, &der)?);
+        }
+
+        for (oid, time) in [
+            (MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, repr.disallowed_at),
+            (MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, repr.not_before),
+        ] {
+            if let Some(time) = time {
+                let filetime = timestamp::parse(&time)
+                    .and_then(encode_filetime)
+                    .ok_or_else(|| D::Error::custom(format!("invalid time {time:?}")))?;
+                attributes.push(property(oid, &filetime)?);
+            }
+        }
+
+        Ok(TrustedSubject {
+            identifier: OctetString::new(repr.identifier.as_bytes()).map_err(D::Error::custom)?,
+            attributes: (!attributes.is_empty())
+                .then(|| SetOfVec::try_from(attributes))
+                .transpose()
+                .map_err(D::Error::custom)?,
+        })
+    }
+}
+
+/// Deserializes a list from its fields and entries, as serialized.
+///
+/// A list serialized without entries is deserialized with an empty
+/// [`trusted_subjects`](CertificateTrustList::trusted_subjects), rather than none.
+impl<'de> Deserialize<'de> for CertificateTrustList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ListRepr::deserialize(deserializer)?;
+
+        let version = match repr.version {
+            1 => CtlVersion::V1,
+            version => return Err(D::Error::custom(format!("unknown version: {version}"))),
+        };
+        let list_identifier = repr
+            .list_identifier
+            .map(|id| {
+                let bytes = hex::decode(&id).map_err(D::Error::custom)?;
+                OctetString::new(bytes).map_err(D::Error::custom)
+            })
+            .transpose()?;
+
+        Ok(CertificateTrustList {
+            version,
+            subject_usage: ExtendedKeyUsage(
+                repr.usage
+                    .iter()
+                    .map(|oid| oid_from_str(oid))
+                    .collect::<Result<_, _>>()?,
+            ),
+            list_identifier,
+            sequence_number: repr.sequence_number,
+            this_update: time_from_str(&repr.this_update)?,
+            next_update: repr.next_update.as_deref().map(time_from_str).transpose()?,
+            subject_algorithm: AlgorithmIdentifier {
+                oid: oid_from_str(&repr.subject_algorithm)?,
+                parameters: None,
+            },
+            trusted_subjects: Some(repr.entries),
+            ctl_extensions: None,
+        })
+    }
+}
+
+/// Builds an attribute with a single `OCTET STRING` value, the way Microsoft encodes
+/// its properties.
+fn property<E: Error>(oid: ObjectIdentifier, value: &[u8]) -> Result<Attribute, E> {
+    let value = Any::new(Tag::OctetString, value).map_err(E::custom)?;
+    Ok(Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![value]).map_err(E::custom)?,
+    })
+}
+
+fn oid_from_str<E: Error>(oid: &str) -> Result<ObjectIdentifier, E> {
+    ObjectIdentifier::new(oid).map_err(|e| E::custom(format!("invalid OID {oid:?}: {e}")))
+}
+
+/// Parses a time, encoding it as a `UTCTime` where possible (i.e. before 2050), as
+/// RFC 5280 requires.
+fn time_from_str<E: Error>(time: &str) -> Result<Time, E> {
+    let time =
+        DateTime::from_str(time).map_err(|e| E::custom(format!("invalid time {time:?}: {e}")))?;
+    Ok(UtcTime::from_date_time(time)
+        .map(Time::UtcTime)
+        .unwrap_or_else(|_| Time::GeneralTime(GeneralizedTime::from_date_time(time))))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_deserialize_round_trip() {
+        let mut ctl = testing::sample_ctl();
+        ctl.sequence_number = Some(SequenceNumber::new(&[0x01, 0x02]).unwrap());
+        ctl.list_identifier = Some(OctetString::new([0xab, 0xcd]).unwrap());
+        ctl.next_update = Some(time_from_str::<serde_json::Error>("2051-01-01T00:00:00Z").unwrap());
+
+        let name = "Test Root"
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let subjects = ctl.trusted_subjects.as_mut().unwrap();
+        subjects[1] = testing::subject(
+            &[0x22; 20],
+            vec![
+                testing::attribute(MS_CERT_PROP_ID_FRIENDLY_NAME_OID, &name),
+                // 1601-01-01T00:00:00.0000001Z
+                testing::attribute(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, &1u64.to_le_bytes()),
+                // 2020-01-01T00:00:00Z
+                testing::attribute(
+                    MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+                    &132_223_104_000_000_000u64.to_le_bytes(),
+                ),
+            ],
+        );
+
+        let json = serde_json::to_value(&ctl).unwrap();
+        assert_eq!(
+            json["entries"][1]["disallowed_at"],
+            "1601-01-01T00:00:00.0000001Z"
+        );
+        assert_eq!(json["entries"][1]["not_before"], "2020-01-01T00:00:00Z");
+        assert_eq!(json["entries"][0].get("disallowed_at"), None);
+        let parsed: CertificateTrustList = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed, ctl);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn test_deserialize_dump() {
+        // `ctltool dump --full` output, with its extra fields.
+        let dump = json!({
+            "schema_version": 1,
+            "version": 1,
+            "usage": ["1.3.6.1.4.1.311.10.3.9"],
+            "list_identifier": null,
+            "sequence_number": "0123",
+            "subject_algorithm": "1.3.14.3.2.26",
+            "this_update": "2023-11-14T22:13:20Z",
+            "next_update": null,
+            "entries": [{
+                "identifier": "cabd2a79a1076a31f21d253635cb039d4329a5e8",
+                "friendly_name": "ISRG Root X1",
+                "ekus": ["1.3.6.1.5.5.7.3.1"],
+                "unknown_attributes": [],
+            }],
+        });
+
+        let ctl: CertificateTrustList = serde_json::from_value(dump).unwrap();
+        assert_eq!(ctl.sequence_number.unwrap().to_hex(), "0123");
+        let subject = &ctl.trusted_subjects.unwrap()[0];
+        assert_eq!(
+            subject.friendly_name().unwrap().as_deref(),
+            Some("ISRG Root X1")
+        );
+        assert_eq!(
+            subject
+                .extended_key_usages()
+                .collect::<Result<MetaEku, _>>()
+                .unwrap(),
+            MetaEku(vec![crate::eku::SERVER_AUTH])
+        );
+
+        for bad in [
+            json!({ "identifier": "xyz" }),
+            json!({ "identifier": "abcd", "ekus": ["not an OID"] }),
+            json!({ "identifier": "abcd", "disallowed_at": "2020-01-01" }),
+            json!({ "identifier": "abcd", "not_before": "1600-12-31T23:59:59Z" }),
+        ] {
+            assert!(serde_json::from_value::<TrustedSubject>(bad).is_err());
+        }
+
+        let mut bad = serde_json::to_value(testing::sample_ctl()).unwrap();
+        bad["version"] = json!(2);
+        assert!(serde_json::from_value::<CertificateTrustList>(bad).is_err());
+    }
+}
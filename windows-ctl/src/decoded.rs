@@ -0,0 +1,207 @@
+//! Trusted subjects with their attributes decoded up front.
+
+use std::time::SystemTime;
+
+use crate::{CertificateTrustList, CtlError, MetaEku, SubjectAttribute, TrustedSubject};
+
+/// A [`TrustedSubject`] whose attributes have all been decoded once, up front.
+///
+/// [`TrustedSubject`]'s accessors decode their attribute on every call, which adds up
+/// when a large list (such as the disallowed list) is queried repeatedly. This does
+/// the decoding once, so that repeated queries are cheap.
+///
+/// Like [`TrustedSubject::extended_key_usages`] and friends, the MetaEKU-valued
+/// attributes are merged across all of their values. Other attributes take their
+/// first value.
+#[derive(Clone, Debug)]
+pub struct DecodedTrustedSubject<'a> {
+    subject: &'a TrustedSubject,
+    attributes: Vec<SubjectAttribute>,
+    extended_key_usages: MetaEku,
+    disallowed_ekus: Option<MetaEku>,
+    not_before_ekus: Option<MetaEku>,
+}
+
+impl<'a> DecodedTrustedSubject<'a> {
+    fn new(subject: &'a TrustedSubject) -> Result<Self, CtlError> {
+        let attributes = subject
+            .decoded_attributes()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut extended_key_usages = MetaEku::default();
+        let mut disallowed_ekus: Option<MetaEku> = None;
+        let mut not_before_ekus: Option<MetaEku> = None;
+        for attr in &attributes {
+            match attr {
+                SubjectAttribute::MetaEkus(ekus) => extended_key_usages.0.extend(ekus),
+                SubjectAttribute::DisallowedEkus(ekus) => disallowed_ekus
+                    .get_or_insert_with(Default::default)
+                    .0
+                    .extend(ekus),
+                SubjectAttribute::NotBeforeEkus(ekus) => not_before_ekus
+                    .get_or_insert_with(Default::default)
+                    .0
+                    .extend(ekus),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            subject,
+            attributes,
+            extended_key_usages,
+            disallowed_ekus,
+            not_before_ekus,
+        })
+    }
+
+    /// Returns the subject these attributes were decoded from.
+    pub fn subject(&self) -> &'a TrustedSubject {
+        self.subject
+    }
+
+    /// Returns every decoded attribute, in encoding order.
+    pub fn attributes(&self) -> &[SubjectAttribute] {
+        &self.attributes
+    }
+
+    /// See [`TrustedSubject::extended_key_usages`].
+    pub fn extended_key_usages(&self) -> &MetaEku {
+        &self.extended_key_usages
+    }
+
+    /// See [`TrustedSubject::disallowed_ekus`]. Returns `None` if the subject doesn't
+    /// have the attribute, i.e. if any distrust applies to all EKUs.
+    pub fn disallowed_ekus(&self) -> Option<&MetaEku> {
+        self.disallowed_ekus.as_ref()
+    }
+
+    /// See [`TrustedSubject::not_before_ekus`]. Returns `None` if the subject doesn't
+    /// have the attribute, i.e. if any not-before distrust applies to all EKUs.
+    pub fn not_before_ekus(&self) -> Option<&MetaEku> {
+        self.not_before_ekus.as_ref()
+    }
+
+    /// See [`TrustedSubject::friendly_name`].
+    pub fn friendly_name(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|attr| match attr {
+            SubjectAttribute::FriendlyName(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// See [`TrustedSubject::sha256_fingerprint`].
+    pub fn sha256_fingerprint(&self) -> Option<&[u8; 32]> {
+        self.attributes.iter().find_map(|attr| match attr {
+            SubjectAttribute::Sha256Hash(hash) => Some(hash),
+            _ => None,
+        })
+    }
+
+    /// See [`TrustedSubject::disallowed_at`].
+    pub fn disallowed_at(&self) -> Option<SystemTime> {
+        self.attributes.iter().find_map(|attr| match attr {
+            SubjectAttribute::DisallowedFiletime(time) => Some(*time),
+            _ => None,
+        })
+    }
+
+    /// See [`TrustedSubject::not_before`].
+    pub fn not_before(&self) -> Option<SystemTime> {
+        self.attributes.iter().find_map(|attr| match attr {
+            SubjectAttribute::NotBeforeFiletime(time) => Some(*time),
+            _ => None,
+        })
+    }
+}
+
+impl TrustedSubject {
+    /// Decodes all of this subject's attributes at once, failing if any is malformed.
+    ///
+    /// See [`DecodedTrustedSubject`].
+    pub fn decoded(&self) -> Result<DecodedTrustedSubject<'_>, CtlError> {
+        DecodedTrustedSubject::new(self)
+    }
+}
+
+impl CertificateTrustList {
+    /// Returns an iterator over this list's subjects, each with its attributes
+    /// [decoded](TrustedSubject::decoded).
+    pub fn decoded_subjects(
+        &self,
+    ) -> impl Iterator<Item = Result<DecodedTrustedSubject<'_>, CtlError>> + '_ {
+        self.trusted_subjects
+            .iter()
+            .flatten()
+            .map(TrustedSubject::decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use der::Encode;
+
+    use super::*;
+    use crate::{
+        testing, MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+        MS_CERT_PROP_ID_METAEKUS_OID, MS_CERT_PROP_ID_SHA256_HASH_OID,
+    };
+
+    #[test]
+    fn test_decoded() {
+        let ctl = testing::sample_ctl();
+        let decoded = ctl
+            .decoded_subjects()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded.len(), 2);
+
+        for subject in &decoded {
+            let ekus = subject
+                .subject()
+                .extended_key_usages()
+                .collect::<Result<MetaEku, _>>()
+                .unwrap();
+            assert_eq!(subject.extended_key_usages(), &ekus);
+            assert_eq!(subject.disallowed_ekus(), None);
+            assert_eq!(subject.friendly_name(), None);
+        }
+
+        // A distrusted subject, with a mix of attributes.
+        let eku = MetaEku(vec![crate::eku::CODE_SIGNING]).to_der().unwrap();
+        let filetime = (116_444_736_000_000_000u64 + 10_000_000).to_le_bytes();
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![
+                testing::attribute(MS_CERT_PROP_ID_METAEKUS_OID, &eku),
+                testing::attribute(MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, &eku),
+                testing::attribute(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, &filetime),
+                testing::attribute(MS_CERT_PROP_ID_SHA256_HASH_OID, &[0xaa; 32]),
+            ],
+        );
+        let decoded = subject.decoded().unwrap();
+        assert_eq!(
+            decoded.extended_key_usages(),
+            &MetaEku(vec![crate::eku::CODE_SIGNING])
+        );
+        assert_eq!(
+            decoded.disallowed_ekus(),
+            Some(&MetaEku(vec![crate::eku::CODE_SIGNING]))
+        );
+        assert_eq!(
+            decoded.disallowed_at(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+        );
+        assert_eq!(decoded.sha256_fingerprint(), Some(&[0xaa; 32]));
+        assert_eq!(decoded.not_before(), None);
+
+        // A malformed attribute fails the whole subject.
+        let subject = testing::subject(
+            &[0x11; 20],
+            vec![testing::attribute(MS_CERT_PROP_ID_SHA256_HASH_OID, &[0; 4])],
+        );
+        assert!(subject.decoded().is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Diagnostics for the fetching and verification paths.
+//!
+//! With the `log` feature enabled, these are emitted through the [`log`] facade, so
+//! that they show up in whichever logger the host application has installed.
+//! Without it, they compile to nothing.
+
+// Not every macro is used under every combination of features.
+#![allow(unused_macros)]
+
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::debug!(target: "windows_ctl", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+macro_rules! warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::warn!(target: "windows_ctl", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)+);
+    }};
+}
@@ -0,0 +1,196 @@
+//! Well-known Extended Key Usage (EKU) OIDs, and their human-readable names.
+//!
+//! These are the purposes that show up in [`MetaEku`](crate::MetaEku)-valued
+//! attributes, such as [`TrustedSubject::extended_key_usages`](crate::TrustedSubject::extended_key_usages).
+
+use der::asn1::ObjectIdentifier;
+
+/// `anyExtendedKeyUsage`: any purpose.
+pub const ANY: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.37.0");
+
+/// `id-kp-serverAuth`: TLS server authentication.
+pub const SERVER_AUTH: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+
+/// `id-kp-clientAuth`: TLS client authentication.
+pub const CLIENT_AUTH: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.2");
+
+/// `id-kp-codeSigning`: code signing.
+pub const CODE_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+
+/// `id-kp-emailProtection`: S/MIME.
+pub const EMAIL_PROTECTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.4");
+
+/// `id-kp-ipsecEndSystem`: IPsec end systems.
+pub const IPSEC_END_SYSTEM: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.5");
+
+/// `id-kp-ipsecTunnel`: IPsec tunnel termination.
+pub const IPSEC_TUNNEL: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.6");
+
+/// `id-kp-ipsecUser`: IPsec users.
+pub const IPSEC_USER: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.7");
+
+/// `id-kp-timeStamping`: RFC 3161 timestamping.
+pub const TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+
+/// `id-kp-OCSPSigning`: OCSP response signing.
+pub const OCSP_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.9");
+
+/// `id-kp-ipsecIKE`: IPsec IKE intermediates.
+pub const IPSEC_IKE_INTERMEDIATE: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.5.5.8.2.2");
+
+/// `szOID_KP_CTL_USAGE_SIGNING`: Microsoft trust list signing.
+pub const MS_TRUST_LIST_SIGNING: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.1");
+
+/// `szOID_KP_TIME_STAMP_SIGNING`: Microsoft (Authenticode) timestamping.
+pub const MS_TIME_STAMP_SIGNING: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.2");
+
+/// `szOID_SERVER_GATED_CRYPTO`: Microsoft Server Gated Cryptography.
+pub const MS_SERVER_GATED_CRYPTO: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.3");
+
+/// `szOID_KP_EFS`: the Encrypting File System.
+pub const MS_EFS: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.4");
+
+/// `szOID_EFS_RECOVERY`: Encrypting File System recovery.
+pub const MS_EFS_RECOVERY: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.4.1");
+
+/// `szOID_WHQL_CRYPTO`: Windows hardware driver verification.
+pub const MS_WHQL_CRYPTO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.5");
+
+/// `szOID_NT5_CRYPTO`: Windows system component verification.
+pub const MS_NT5_CRYPTO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.6");
+
+/// `szOID_OEM_WHQL_CRYPTO`: OEM Windows system component verification.
+pub const MS_OEM_WHQL_CRYPTO: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.7");
+
+/// `szOID_EMBEDDED_NT_CRYPTO`: embedded Windows system component verification.
+pub const MS_EMBEDDED_NT_CRYPTO: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.8");
+
+/// `szOID_ROOT_LIST_SIGNER`: Microsoft root list signing.
+pub const MS_ROOT_LIST_SIGNER: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.9");
+
+/// `szOID_KP_QUALIFIED_SUBORDINATION`: qualified subordination.
+pub const MS_QUALIFIED_SUBORDINATION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.10");
+
+/// `szOID_KP_KEY_RECOVERY`: key recovery.
+pub const MS_KEY_RECOVERY: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.11");
+
+/// `szOID_KP_DOCUMENT_SIGNING`: document signing.
+pub const MS_DOCUMENT_SIGNING: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.12");
+
+/// `szOID_KP_LIFETIME_SIGNING`: lifetime signing, i.e. signatures that expire with the
+/// signing certificate.
+pub const MS_LIFETIME_SIGNING: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.13");
+
+/// `szOID_DISALLOWED_LIST`: Microsoft disallowed (distrusted) certificate lists.
+pub const MS_DISALLOWED_LIST: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.30");
+
+/// `szOID_PIN_RULES_SIGNER`: Microsoft certificate pinning rule list signing.
+pub const MS_PIN_RULES_SIGNER: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.31");
+
+/// `szOID_PIN_RULES_CTL`: Microsoft certificate pinning rule lists.
+pub const MS_PIN_RULES_CTL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.3.32");
+
+/// `szOID_DRM`: digital rights management.
+pub const MS_DRM: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.5.1");
+
+/// `szOID_LICENSES`: key pack licenses.
+pub const MS_LICENSES: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.6.1");
+
+/// `szOID_LICENSE_SERVER`: license server verification.
+pub const MS_LICENSE_SERVER: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.6.2");
+
+/// `szOID_KP_SMARTCARD_LOGON`: smart card logon.
+pub const MS_SMARTCARD_LOGON: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.20.2.2");
+
+/// `szOID_KP_KERNEL_MODE_CODE_SIGNING`: kernel-mode code signing.
+pub const MS_KERNEL_MODE_CODE_SIGNING: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.61.1.1");
+
+/// Every EKU in this module, along with its name.
+const NAMES: &[(ObjectIdentifier, &str)] = &[
+    (ANY, "Any Purpose"),
+    (SERVER_AUTH, "Server Authentication"),
+    (CLIENT_AUTH, "Client Authentication"),
+    (CODE_SIGNING, "Code Signing"),
+    (EMAIL_PROTECTION, "Secure Email"),
+    (IPSEC_END_SYSTEM, "IP Security End System"),
+    (IPSEC_TUNNEL, "IP Security Tunnel Termination"),
+    (IPSEC_USER, "IP Security User"),
+    (TIME_STAMPING, "Time Stamping"),
+    (OCSP_SIGNING, "OCSP Signing"),
+    (IPSEC_IKE_INTERMEDIATE, "IP Security IKE Intermediate"),
+    (MS_TRUST_LIST_SIGNING, "Microsoft Trust List Signing"),
+    (MS_TIME_STAMP_SIGNING, "Microsoft Time Stamping"),
+    (MS_SERVER_GATED_CRYPTO, "Microsoft Server Gated Crypto"),
+    (MS_EFS, "Encrypting File System"),
+    (MS_EFS_RECOVERY, "File Recovery"),
+    (MS_WHQL_CRYPTO, "Windows Hardware Driver Verification"),
+    (MS_NT5_CRYPTO, "Windows System Component Verification"),
+    (
+        MS_OEM_WHQL_CRYPTO,
+        "OEM Windows System Component Verification",
+    ),
+    (
+        MS_EMBEDDED_NT_CRYPTO,
+        "Embedded Windows System Component Verification",
+    ),
+    (MS_ROOT_LIST_SIGNER, "Root List Signer"),
+    (MS_QUALIFIED_SUBORDINATION, "Qualified Subordination"),
+    (MS_KEY_RECOVERY, "Key Recovery"),
+    (MS_DOCUMENT_SIGNING, "Document Signing"),
+    (MS_LIFETIME_SIGNING, "Lifetime Signing"),
+    (MS_DISALLOWED_LIST, "Disallowed List"),
+    (MS_PIN_RULES_SIGNER, "Pin Rules Signer"),
+    (MS_PIN_RULES_CTL, "Pin Rules List"),
+    (MS_DRM, "Digital Rights"),
+    (MS_LICENSES, "Key Pack Licenses"),
+    (MS_LICENSE_SERVER, "License Server Verification"),
+    (MS_SMARTCARD_LOGON, "Smart Card Logon"),
+    (MS_KERNEL_MODE_CODE_SIGNING, "Kernel Mode Code Signing"),
+];
+
+/// Returns the human-readable name of the given EKU (as Windows displays it), if
+/// it's one of the EKUs in this module.
+pub fn name(oid: &ObjectIdentifier) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(known, _)| known == oid)
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        assert_eq!(name(&SERVER_AUTH), Some("Server Authentication"));
+        assert_eq!(
+            name(&ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.61.1.1")),
+            Some("Kernel Mode Code Signing")
+        );
+        assert_eq!(name(&ObjectIdentifier::new_unwrap("1.2.3.4")), None);
+
+        // No EKU is listed twice.
+        for (i, (oid, _)) in NAMES.iter().enumerate() {
+            assert!(!NAMES[i + 1..].iter().any(|(other, _)| other == oid));
+        }
+    }
+}
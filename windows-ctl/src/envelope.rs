@@ -0,0 +1,760 @@
+//! Access to the PKCS#7 envelope that a [`CertificateTrustList`] is shipped in.
+
+use std::io::{Read, Write};
+use std::ops::Range;
+
+use der::asn1::{AnyRef, ObjectIdentifier, SetOfVec};
+use der::pem::LineEnding;
+use der::{Decode, Encode, Reader, Sequence, SliceReader, Tag, Tagged};
+use pkcs7::certificate_choices::CertificateChoices;
+use pkcs7::cms_version::CmsVersion;
+use pkcs7::encapsulated_content_info::EncapsulatedContentInfo;
+use pkcs7::signed_data_content::SignedDataContent;
+use pkcs7::signer_info::{SignerIdentifier, SignerInfos};
+use pkcs7::{ContentInfo, ContentType};
+use x509_cert::ext::pkix::SubjectKeyIdentifier;
+use x509_cert::Certificate;
+
+use crate::{
+    CertificateTrustList, CertificateTrustListRef, CtlError, CtlHeader, SignerMetadata,
+    MS_CERT_TRUST_LIST_OID,
+};
+
+/// The labels that PEM-armored PKCS#7 messages are found with.
+const PEM_LABELS: &[&str] = &["PKCS7", "CMS", "PKCS #7 SIGNED DATA"];
+
+/// A minimal view of a PKCS#7 `ContentInfo` that leaves the content undecoded,
+/// so that we can recover its position in the original encoding.
+#[derive(Sequence)]
+struct RawContentInfo<'a> {
+    content_type: ObjectIdentifier,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT")]
+    content: AnyRef<'a>,
+}
+
+/// A minimal view of a PKCS#7 `EncapsulatedContentInfo`.
+#[derive(Sequence)]
+struct RawEncapsulatedContentInfo<'a> {
+    e_content_type: ObjectIdentifier,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    e_content: Option<AnyRef<'a>>,
+}
+
+/// The raw DER that a [`CertificateTrustList`] was parsed from.
+///
+/// This retains the original PKCS#7 encoding along with the positions of the
+/// `SignedData` and the encapsulated `certTrustList` within it, so that
+/// callers can archive, hash, or independently verify exactly what was signed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawCtl {
+    der: Vec<u8>,
+    signed_data: Range<usize>,
+    content: Range<usize>,
+    content_header_len: usize,
+}
+
+impl RawCtl {
+    /// Validates the PKCS#7 envelope in the given DER, without decoding the
+    /// [`CertificateTrustList`] it contains.
+    ///
+    /// This is useful for large lists (like the disallowed list), which can then be
+    /// inspected with [`RawCtl::ctl_ref`] rather than decoded into owned types.
+    pub fn new(der: Vec<u8>) -> Result<Self, CtlError> {
+        let Some(content) = decode_signed_data(&der)?.encap_content_info.e_content else {
+            return Err(CtlError::MissingSignedDataContent);
+        };
+
+        let content_header_len = usize::try_from(content.encoded_len()?)? - content.value().len();
+        let content = tlv_range(&der, &content)?;
+        let signed_data = tlv_range(&der, &RawContentInfo::from_der(&der)?.content)?;
+
+        Ok(Self {
+            der,
+            signed_data,
+            content,
+            content_header_len,
+        })
+    }
+
+    /// Parses the given PKCS#7 DER, returning both the inner [`CertificateTrustList`]
+    /// and the raw encoding it came from.
+    pub(crate) fn parse(der: Vec<u8>) -> Result<(CertificateTrustList, Self), CtlError> {
+        let raw = Self::new(der)?;
+        let ctl = <CertificateTrustList as Decode>::from_der(raw.content())?;
+
+        Ok((ctl, raw))
+    }
+
+    /// Decodes the encapsulated `certTrustList` as a [`CertificateTrustListRef`], which
+    /// borrows from this `RawCtl` rather than allocating for each of its entries.
+    pub fn ctl_ref(&self) -> Result<CertificateTrustListRef<'_>, CtlError> {
+        Ok(CertificateTrustListRef::from_der(self.content())?)
+    }
+
+    /// Returns the complete DER encoding of the PKCS#7 `ContentInfo`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Returns the DER encoding of the `SignedData`, including its tag and length.
+    pub fn signed_data(&self) -> &[u8] {
+        &self.der[self.signed_data.clone()]
+    }
+
+    /// Returns the complete PKCS#7 `ContentInfo`, PEM-armored with the `PKCS7` label.
+    ///
+    /// Unlike [`CertificateTrustList::to_pem`], this preserves the original envelope,
+    /// including its signatures.
+    pub fn to_pem(&self, line_ending: LineEnding) -> Result<String, CtlError> {
+        Ok(
+            der::pem::encode_string(PEM_LABELS[0], line_ending, &self.der)
+                .map_err(der::Error::from)?,
+        )
+    }
+
+    /// Returns the byte range of the `SignedData` within [`RawCtl::as_bytes`].
+    pub fn signed_data_range(&self) -> Range<usize> {
+        self.signed_data.clone()
+    }
+
+    /// Returns the DER encoding of the encapsulated `certTrustList` (the `eContent`),
+    /// including its tag and length.
+    pub fn content(&self) -> &[u8] {
+        &self.der[self.content.clone()]
+    }
+
+    /// Returns the byte range of the encapsulated `certTrustList` within [`RawCtl::as_bytes`].
+    pub fn content_range(&self) -> Range<usize> {
+        self.content.clone()
+    }
+
+    /// Returns the content octets of the encapsulated `certTrustList`: its DER encoding
+    /// without the tag and length.
+    ///
+    /// These are the bytes that a `SignerInfo`'s `messageDigest` attribute is computed
+    /// over, per [RFC 2315, section 9.3](https://www.rfc-editor.org/rfc/rfc2315#section-9.3).
+    pub fn content_octets(&self) -> &[u8] {
+        &self.der[self.content_octets_range()]
+    }
+
+    /// Returns the byte range of the [content octets](RawCtl::content_octets) within
+    /// [`RawCtl::as_bytes`].
+    pub fn content_octets_range(&self) -> Range<usize> {
+        self.content.start + self.content_header_len..self.content.end
+    }
+}
+
+/// A [`CertificateTrustList`] together with the PKCS#7 envelope it was shipped in.
+///
+/// Unlike [`CertificateTrustList::from_der`], which discards everything but the
+/// inner list, this retains the outer `ContentInfo` and `SignedData` so that the
+/// signers can be inspected (or re-verified) and the message can be written back
+/// out unchanged without re-reading the source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CtlMessage {
+    raw: RawCtl,
+    ctl: CertificateTrustList,
+}
+
+impl CtlMessage {
+    /// Load a `CtlMessage` from the given source, which is expected to be a DER-encoded
+    /// PKCS#7 stream.
+    pub fn from_der<R: Read>(source: R) -> Result<Self, CtlError> {
+        let (ctl, raw) = CertificateTrustList::from_der_with_raw(source)?;
+        Ok(Self { raw, ctl })
+    }
+
+    /// Load a `CtlMessage` from a PEM-armored PKCS#7 message. See
+    /// [`CertificateTrustList::from_pem`].
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> Result<Self, CtlError> {
+        RawCtl::new(decode_pem(pem.as_ref())?)?.try_into()
+    }
+
+    /// Returns the inner [`CertificateTrustList`].
+    pub fn ctl(&self) -> &CertificateTrustList {
+        &self.ctl
+    }
+
+    /// Consumes this message, returning the inner [`CertificateTrustList`].
+    pub fn into_ctl(self) -> CertificateTrustList {
+        self.ctl
+    }
+
+    /// Returns the raw DER this message was parsed from.
+    pub fn raw(&self) -> &RawCtl {
+        &self.raw
+    }
+
+    /// Returns the outer PKCS#7 `ContentInfo`.
+    pub fn content_info(&self) -> ContentInfo<'_> {
+        // NOTE: The PKCS#7 types borrow from their encoding, so we re-decode them
+        // on demand. This can't fail, since we've already decoded them once in `parse`.
+        ContentInfo::from_der(self.raw.as_bytes()).expect("ContentInfo validated during parsing")
+    }
+
+    /// Returns the `SignedData` that encapsulates the [`CertificateTrustList`].
+    pub fn signed_data(&self) -> SignedDataContent<'_> {
+        SignedDataContent::from_der(self.raw.signed_data())
+            .expect("SignedData validated during parsing")
+    }
+
+    /// Returns the `SignerInfo`s attached to this message's `SignedData`.
+    pub fn signer_infos(&self) -> SignerInfos<'_> {
+        self.signed_data().signer_infos
+    }
+
+    /// Returns every certificate embedded in this message's `SignedData`, in the
+    /// order they're encoded.
+    ///
+    /// Microsoft's lists embed the signer's certificate along with its chain, short of
+    /// the root, so this is enough to build (or pin) the signing chain without
+    /// retrieving anything. Other certificate formats (such as attribute
+    /// certificates) are skipped.
+    pub fn certificates(&self) -> Vec<Certificate> {
+        embedded_certificates(&self.signed_data())
+    }
+
+    /// Returns the certificates embedded in this message's `SignedData` that
+    /// belong to one of its signers.
+    pub fn signer_certificates(&self) -> Vec<Certificate> {
+        let signer_infos = self.signer_infos();
+        self.certificates()
+            .into_iter()
+            .filter(|cert| signer_infos.iter().any(|info| identifies(&info.sid, cert)))
+            .collect()
+    }
+
+    /// Returns the metadata of each of this message's signers: who they are, which
+    /// algorithms they signed with, and which attributes they signed.
+    pub fn signers(&self) -> Vec<SignerMetadata> {
+        signers(self.raw.as_bytes(), &self.certificates())
+            .expect("SignerInfos validated during parsing")
+    }
+
+    /// Writes this message back out, byte-for-byte identical to its source.
+    pub fn write_to<W: Write>(&self, mut sink: W) -> Result<(), CtlError> {
+        sink.write_all(self.raw.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl CertificateTrustList {
+    /// Decodes just the header of the CTL in the given DER-encoded PKCS#7 message:
+    /// its version, usage, identifier, sequence number, and update times.
+    ///
+    /// Neither the envelope's certificates and signers nor the list's subjects are
+    /// decoded (or validated), so this is a cheap way to check a large list's freshness
+    /// before committing to a full parse.
+    pub fn peek_header(der: &[u8]) -> Result<CtlHeader<'_>, CtlError> {
+        let content_info = RawContentInfo::from_der(der)?;
+        match ContentType::try_from(content_info.content_type)? {
+            ContentType::SignedData => {}
+            other => return Err(CtlError::ContentType(other)),
+        }
+
+        // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo, ... }
+        content_info.content.tag().assert_eq(Tag::Sequence)?;
+        let mut reader = SliceReader::new(content_info.content.value())?;
+        let _version: AnyRef<'_> = reader.decode()?;
+        let _digest_algorithms: AnyRef<'_> = reader.decode()?;
+        let encap: RawEncapsulatedContentInfo<'_> = reader.decode()?;
+
+        if encap.e_content_type != MS_CERT_TRUST_LIST_OID {
+            return Err(CtlError::Content(encap.e_content_type));
+        }
+        let Some(content) = encap.e_content else {
+            return Err(CtlError::MissingSignedDataContent);
+        };
+
+        CtlHeader::decode_prefix(content)
+    }
+
+    /// Load a `CertificateTrustList` from a PEM-armored PKCS#7 message, as produced by
+    /// e.g. `openssl pkcs7 -outform PEM`.
+    ///
+    /// The armor's label must be `PKCS7` or `CMS` (or the older `PKCS #7 SIGNED DATA`).
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> Result<Self, CtlError> {
+        let (ctl, _) = RawCtl::parse(decode_pem(pem.as_ref())?)?;
+        Ok(ctl)
+    }
+
+    /// Returns this list as a PEM-armored PKCS#7 message, labelled `PKCS7`.
+    ///
+    /// The list is encapsulated in a `SignedData` without any signers or certificates,
+    /// much like the degenerate bundles that `openssl crl2pkcs7` produces, so this is
+    /// suited to exchanging a rebuilt or filtered list with PKCS#7 tooling (and to
+    /// reading it back with [`CertificateTrustList::from_pem`]), but Windows won't
+    /// accept it. Lists can be signed with `sign::sign`, when the `sign` feature is
+    /// enabled, and a parsed list's original envelope is available via
+    /// [`RawCtl::to_pem`].
+    pub fn to_pem(&self, line_ending: LineEnding) -> Result<String, CtlError> {
+        let content = self.to_der()?;
+        let signed_data = SignedDataContent {
+            version: CmsVersion::V1,
+            digest_algorithms: SetOfVec::new(),
+            encap_content_info: EncapsulatedContentInfo {
+                e_content_type: MS_CERT_TRUST_LIST_OID,
+                e_content: Some(AnyRef::try_from(content.as_slice())?),
+            },
+            certificates: None,
+            crls: None,
+            signer_infos: SetOfVec::new(),
+        };
+
+        let der = ContentInfo::SignedData(signed_data).to_der()?;
+        Ok(der::pem::encode_string(PEM_LABELS[0], line_ending, &der).map_err(der::Error::from)?)
+    }
+
+    /// Load a `CertificateTrustList` from a detached PKCS#7 signature, i.e. one whose
+    /// `SignedData` has no encapsulated content, along with the DER-encoded
+    /// `certTrustList` that it was computed over.
+    ///
+    /// With the `digest` feature, each signer's `messageDigest` must match
+    /// `content_der` (see `SignerMetadata::check_message_digest`). Signers without
+    /// signed attributes sign the list directly, so there's no digest to check. The
+    /// signatures themselves aren't checked: see `Verifier::verify_detached` (with the
+    /// `verify` feature).
+    pub fn from_detached(signature_der: &[u8], content_der: &[u8]) -> Result<Self, CtlError> {
+        let (signers, _) = detached_signers(signature_der)?;
+        let ctl = <Self as Decode>::from_der(content_der)?;
+
+        #[cfg(feature = "digest")]
+        {
+            let content = AnyRef::from_der(content_der)?;
+            for signer in signers.iter().filter(|s| !s.signed_attributes.is_empty()) {
+                signer.check_message_digest(content.value())?;
+            }
+        }
+        #[cfg(not(feature = "digest"))]
+        let _ = signers;
+
+        Ok(ctl)
+    }
+}
+
+/// Decodes the list in a validated envelope, e.g. one from `RawCtl::from_cab`.
+impl TryFrom<RawCtl> for CtlMessage {
+    type Error = CtlError;
+
+    fn try_from(raw: RawCtl) -> Result<Self, Self::Error> {
+        let ctl = <CertificateTrustList as Decode>::from_der(raw.content())?;
+        Ok(Self { raw, ctl })
+    }
+}
+
+/// Decodes the list encapsulated in an already-parsed PKCS#7 message, for callers
+/// that parse CMS themselves.
+impl TryFrom<ContentInfo<'_>> for CertificateTrustList {
+    type Error = CtlError;
+
+    fn try_from(content_info: ContentInfo<'_>) -> Result<Self, Self::Error> {
+        signed_data(content_info)?.try_into()
+    }
+}
+
+/// Decodes the list encapsulated in an already-parsed `SignedData`.
+///
+/// As with [`CertificateTrustList::from_der`], the signatures aren't checked.
+impl TryFrom<SignedDataContent<'_>> for CertificateTrustList {
+    type Error = CtlError;
+
+    fn try_from(signed_data: SignedDataContent<'_>) -> Result<Self, Self::Error> {
+        let Some(content) = ctl_content(&signed_data)?.e_content else {
+            return Err(CtlError::MissingSignedDataContent);
+        };
+
+        Ok(content.decode_as()?)
+    }
+}
+
+/// Returns the DER of a PEM-armored PKCS#7 message, checking its label.
+fn decode_pem(pem: &[u8]) -> Result<Vec<u8>, CtlError> {
+    let (label, der) = der::pem::decode_vec(pem).map_err(der::Error::from)?;
+    if !PEM_LABELS.contains(&label) {
+        return Err(CtlError::PemLabel(label.into()));
+    }
+    Ok(der)
+}
+
+/// Decodes the `SignedData` in the given PKCS#7 DER, checking that it claims to
+/// encapsulate a `certTrustList`.
+fn decode_signed_data(der: &[u8]) -> Result<SignedDataContent<'_>, CtlError> {
+    let signed_data = signed_data(ContentInfo::from_der(der)?)?;
+    ctl_content(&signed_data)?;
+
+    Ok(signed_data)
+}
+
+/// Returns the signers of the detached signature in the given PKCS#7 DER, along with
+/// the certificates embedded in it, checking that it has no content of its own.
+pub(crate) fn detached_signers(
+    der: &[u8],
+) -> Result<(Vec<SignerMetadata>, Vec<Certificate>), CtlError> {
+    let signed_data = decode_signed_data(der)?;
+    if signed_data.encap_content_info.e_content.is_some() {
+        return Err(CtlError::UnexpectedSignedDataContent);
+    }
+
+    let certificates = embedded_certificates(&signed_data);
+    let signers = signers(der, &certificates)?;
+    Ok((signers, certificates))
+}
+
+/// Returns the signers of the `SignedData` in the given PKCS#7 DER, in the order
+/// they're encoded, looking up their certificates among `certificates`.
+///
+/// Each `SignerInfo` is decoded from its own encoding (rather than taken from a
+/// decoded `SignedData`) so that its signed attributes are kept as they were encoded:
+/// see [`SignerMetadata::from_der`].
+pub(crate) fn signers(
+    der: &[u8],
+    certificates: &[Certificate],
+) -> Result<Vec<SignerMetadata>, CtlError> {
+    // The signerInfos are the SignedData's last field.
+    let mut fields = SliceReader::new(RawContentInfo::from_der(der)?.content.value())?;
+    let mut signer_infos = AnyRef::decode(&mut fields)?;
+    while !fields.is_finished() {
+        signer_infos = AnyRef::decode(&mut fields)?;
+    }
+    signer_infos.tag().assert_eq(Tag::Set)?;
+
+    let mut reader = SliceReader::new(signer_infos.value())?;
+    let mut signers = vec![];
+    while !reader.is_finished() {
+        signers.push(SignerMetadata::from_der(reader.tlv_bytes()?, certificates)?);
+    }
+    Ok(signers)
+}
+
+/// Returns the `SignedData` in the given `ContentInfo`, if that's what it holds.
+fn signed_data(body: ContentInfo<'_>) -> Result<SignedDataContent<'_>, CtlError> {
+    match body {
+        ContentInfo::SignedData(signed_data) => Ok(signed_data),
+        _ => Err(CtlError::ContentType(body.content_type())),
+    }
+}
+
+/// Returns the X.509 certificates embedded in `signed_data`, skipping other formats.
+pub(crate) fn embedded_certificates(signed_data: &SignedDataContent<'_>) -> Vec<Certificate> {
+    signed_data
+        .certificates
+        .iter()
+        .flat_map(|certs| certs.iter())
+        .filter_map(|choice| match choice {
+            CertificateChoices::Certificate(cert) => Some(cert.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the encapsulated content of `signed_data`, checking that it claims to be
+/// a `certTrustList`.
+fn ctl_content<'a>(
+    signed_data: &SignedDataContent<'a>,
+) -> Result<EncapsulatedContentInfo<'a>, CtlError> {
+    // Our actual SignedData content should be a MS-specific `certTrustList`.
+    let encap = signed_data.encap_content_info;
+    if encap.e_content_type != MS_CERT_TRUST_LIST_OID {
+        return Err(CtlError::Content(encap.e_content_type));
+    }
+
+    Ok(encap)
+}
+
+/// Returns the DER encoding (including tag and length) of the `certTrustList`
+/// encapsulated in the given PKCS#7 DER, without copying it.
+pub(crate) fn encapsulated_content(der: &[u8]) -> Result<&[u8], CtlError> {
+    let Some(content) = decode_signed_data(der)?.encap_content_info.e_content else {
+        return Err(CtlError::MissingSignedDataContent);
+    };
+
+    Ok(&der[tlv_range(der, &content)?])
+}
+
+/// Returns whether `sid` identifies `cert`.
+pub(crate) fn identifies(sid: &SignerIdentifier, cert: &Certificate) -> bool {
+    let tbs_cert = &cert.tbs_certificate;
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(id) => {
+            id.name == tbs_cert.issuer && id.serial_number == tbs_cert.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(ski) => tbs_cert
+            .get::<SubjectKeyIdentifier>()
+            .ok()
+            .flatten()
+            .is_some_and(|(_, ext)| ext == *ski),
+    }
+}
+
+/// Computes the range that `any` (including its header) occupies within `der`.
+///
+/// `any` must have been decoded from `der`.
+fn tlv_range(der: &[u8], any: &AnyRef<'_>) -> Result<Range<usize>, CtlError> {
+    let value_start = any.value().as_ptr() as usize - der.as_ptr() as usize;
+    let tlv_len = usize::try_from(any.encoded_len()?)?;
+    let header_len = tlv_len - any.value().len();
+
+    Ok(value_start - header_len..value_start + any.value().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, SequenceNumber};
+
+    #[test]
+    fn test_raw_ctl() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        let (parsed, raw) = RawCtl::parse(der.clone()).unwrap();
+        assert_eq!(parsed, ctl);
+        assert_eq!(raw.as_bytes(), der);
+        assert_eq!(raw.content(), ctl.to_der().unwrap());
+        assert_eq!(
+            <CertificateTrustList as Decode>::from_der(raw.content()).unwrap(),
+            parsed
+        );
+
+        // The SignedData is the tail of the ContentInfo.
+        assert_eq!(raw.signed_data_range().end, der.len());
+        assert!(raw.signed_data_range().contains(&raw.content_range().start));
+
+        // The content octets are the content, less its tag and length.
+        assert!(raw.content().ends_with(raw.content_octets()));
+        assert_eq!(raw.content_octets_range().end, raw.content_range().end);
+        assert_eq!(
+            raw.content_octets(),
+            AnyRef::from_der(raw.content()).unwrap().value()
+        );
+    }
+
+    #[test]
+    fn test_ctl_message() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert_eq!(message.ctl(), &ctl);
+        assert_eq!(
+            message.signed_data().encap_content_info.e_content_type,
+            MS_CERT_TRUST_LIST_OID
+        );
+        assert!(message.signer_infos().is_empty());
+
+        let mut out = vec![];
+        message.write_to(&mut out).unwrap();
+        assert_eq!(out, der);
+    }
+
+    #[test]
+    fn test_peek_header() {
+        let mut ctl = testing::sample_ctl();
+        ctl.sequence_number = Some(SequenceNumber::new(&[0x01, 0x02]).unwrap());
+        let der = testing::sign_ctl(&ctl);
+
+        let header = CertificateTrustList::peek_header(&der).unwrap();
+        assert_eq!(header.version, ctl.version);
+        assert_eq!(
+            header
+                .subject_usage()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            ctl.subject_usage.0
+        );
+        assert_eq!(header.list_identifier, None);
+        assert_eq!(
+            SequenceNumber::try_from(header.sequence_number.unwrap()).unwrap(),
+            ctl.sequence_number.unwrap()
+        );
+        assert_eq!(header.this_update, ctl.this_update);
+        assert_eq!(header.next_update, None);
+
+        // The subjects aren't decoded, so a malformed one doesn't get in the way.
+        let mut corrupted = der.clone();
+        let id = corrupted.windows(20).position(|w| w == [0x22; 20]).unwrap();
+        corrupted[id - 2] = 0x05;
+        assert!(CtlMessage::from_der(std::io::Cursor::new(&corrupted)).is_err());
+        assert_eq!(
+            CertificateTrustList::peek_header(&corrupted).unwrap(),
+            header
+        );
+
+        assert!(matches!(
+            CertificateTrustList::peek_header(&testing::sign_ctl_detached()),
+            Err(CtlError::MissingSignedDataContent)
+        ));
+    }
+
+    #[test]
+    fn test_from_detached() {
+        let ctl = testing::sample_ctl();
+        let content = ctl.to_der().unwrap();
+        let signature = testing::sign_ctl_detached();
+
+        assert_eq!(
+            CertificateTrustList::from_detached(&signature, &content).unwrap(),
+            ctl
+        );
+
+        // Detached signatures don't have any content to decode on their own...
+        assert!(matches!(
+            RawCtl::new(signature),
+            Err(CtlError::MissingSignedDataContent)
+        ));
+
+        // ...and attached ones aren't detached.
+        assert!(matches!(
+            CertificateTrustList::from_detached(&testing::sign_ctl(&ctl), &content),
+            Err(CtlError::UnexpectedSignedDataContent)
+        ));
+    }
+
+    #[cfg(all(feature = "digest", feature = "sign"))]
+    #[test]
+    fn test_from_detached_digest() {
+        use der::DecodePem;
+
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(&ctl, testing::TestSigner::new(), &signer, &[]).unwrap();
+        let (signature, mut content) = testing::detach(&der);
+        assert_eq!(
+            CertificateTrustList::from_detached(&signature, &content).unwrap(),
+            ctl
+        );
+
+        // Flip a bit in the first subject's identifier, which the digest covers.
+        let offset = content.windows(20).position(|w| w == [0x11; 20]).unwrap();
+        content[offset] ^= 1;
+        assert!(matches!(
+            CertificateTrustList::from_detached(&signature, &content),
+            Err(CtlError::SignedAttributes(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_pem() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        for label in PEM_LABELS {
+            let pem = der::pem::encode_string(label, LineEnding::LF, &der).unwrap();
+            assert_eq!(CertificateTrustList::from_pem(&pem).unwrap(), ctl);
+        }
+
+        let pem = der::pem::encode_string("CERTIFICATE", LineEnding::CRLF, &der).unwrap();
+        assert!(matches!(
+            CertificateTrustList::from_pem(pem),
+            Err(CtlError::PemLabel(label)) if label == "CERTIFICATE"
+        ));
+        assert!(matches!(
+            CertificateTrustList::from_pem(&der),
+            Err(CtlError::Der(_))
+        ));
+    }
+
+    #[test]
+    fn test_ctl_message_sources() {
+        use der::DecodePem;
+
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = testing::sign_ctl_with(&ctl, Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+
+        let pem = RawCtl::new(der.clone())
+            .unwrap()
+            .to_pem(LineEnding::LF)
+            .unwrap();
+        for other in [
+            CtlMessage::from_der(&der[..]).unwrap(),
+            CtlMessage::from_pem(pem).unwrap(),
+            CtlMessage::try_from(RawCtl::new(der.clone()).unwrap()).unwrap(),
+        ] {
+            assert_eq!(other, message);
+            assert_eq!(other.signer_certificates(), std::slice::from_ref(&signer));
+        }
+
+        assert!(matches!(
+            CtlMessage::from_pem(
+                der::pem::encode_string("CERTIFICATE", LineEnding::LF, &der).unwrap()
+            ),
+            Err(CtlError::PemLabel(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_from_content_info() {
+        let ctl = testing::sample_ctl();
+        let der = testing::sign_ctl(&ctl);
+
+        let content_info = ContentInfo::from_der(&der).unwrap();
+        assert_eq!(CertificateTrustList::try_from(content_info).unwrap(), ctl);
+        assert_eq!(
+            CertificateTrustList::try_from(decode_signed_data(&der).unwrap()).unwrap(),
+            ctl
+        );
+
+        let detached = testing::sign_ctl_detached();
+        assert!(matches!(
+            CertificateTrustList::try_from(ContentInfo::from_der(&detached).unwrap()),
+            Err(CtlError::MissingSignedDataContent)
+        ));
+
+        let data = ContentInfo::new_data(b"not a list");
+        assert!(matches!(
+            CertificateTrustList::try_from(data),
+            Err(CtlError::ContentType(ContentType::Data))
+        ));
+    }
+
+    #[test]
+    fn test_to_pem() {
+        use der::DecodePem;
+
+        let ctl = testing::sample_ctl();
+
+        let pem = ctl.to_pem(LineEnding::LF).unwrap();
+        assert!(pem.starts_with("-----BEGIN PKCS7-----\n"));
+        assert_eq!(CertificateTrustList::from_pem(&pem).unwrap(), ctl);
+
+        // An unsigned envelope is identical to the one that tests sign lists into.
+        let (_, der) = der::pem::decode_vec(pem.as_bytes()).unwrap();
+        assert_eq!(der, testing::sign_ctl(&ctl));
+
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(message.signer_infos().is_empty());
+
+        // A raw list's envelope is armored as is.
+        let der = testing::sign_ctl_with(
+            &ctl,
+            Some(&Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap()),
+        );
+        let raw = RawCtl::new(der.clone()).unwrap();
+        let pem = raw.to_pem(LineEnding::CRLF).unwrap();
+        assert!(pem.contains("\r\n"));
+        assert_eq!(
+            der::pem::decode_vec(pem.as_bytes()).unwrap(),
+            ("PKCS7", der)
+        );
+    }
+
+    #[test]
+    fn test_signer_certificates() {
+        use der::DecodePem;
+
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+
+        let der = testing::sign_ctl_with(&ctl, Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert_eq!(message.signer_certificates(), std::slice::from_ref(&signer));
+        assert_eq!(message.certificates(), [signer]);
+
+        let der = testing::sign_ctl(&ctl);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(message.signer_certificates().is_empty());
+        assert!(message.certificates().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+//! Classifying CTLs by what they're for.
+
+use crate::{CertificateTrustList, ListUsage, DISALLOWED_CERT_LIST_NAME};
+
+/// The kind of a [`CertificateTrustList`], as returned by [`CertificateTrustList::kind`].
+///
+/// Trust lists and distrust lists share a format, but mean opposite things: the
+/// subjects of an [`AuthRoot`](CtlKind::AuthRoot) list are trusted, while those of a
+/// [`Disallowed`](CtlKind::Disallowed) list are explicitly distrusted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CtlKind {
+    /// The trusted root list (`authroot.stl`).
+    AuthRoot,
+    /// The disallowed certificate list (`disallowedcert.stl`).
+    Disallowed,
+    /// The certificate pinning rules list (`pinrules.stl`).
+    PinRules,
+    /// A list that isn't recognized.
+    Unknown,
+}
+
+impl CertificateTrustList {
+    /// Returns this list's [`CtlKind`], as determined by its
+    /// [`subject_usage`](CertificateTrustList::subject_usage) and
+    /// [`name`](CertificateTrustList::name).
+    pub fn kind(&self) -> CtlKind {
+        let has = |usage| self.usages().any(|u| u == usage);
+        let name = self.name().ok().flatten();
+
+        if has(ListUsage::DisallowedList) || name.as_deref() == Some(DISALLOWED_CERT_LIST_NAME) {
+            CtlKind::Disallowed
+        } else if has(ListUsage::PinRulesCtl) {
+            CtlKind::PinRules
+        } else if has(ListUsage::RootListSigner) {
+            CtlKind::AuthRoot
+        } else {
+            CtlKind::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::asn1::{ObjectIdentifier, OctetString};
+    use x509_cert::ext::pkix::ExtendedKeyUsage;
+
+    use super::*;
+    use crate::{testing, usage};
+
+    #[test]
+    fn test_kind() {
+        let mut ctl = testing::sample_ctl();
+        assert_eq!(ctl.kind(), CtlKind::AuthRoot);
+
+        ctl.subject_usage = ExtendedKeyUsage(vec![usage::DISALLOWED_LIST]);
+        assert_eq!(ctl.kind(), CtlKind::Disallowed);
+
+        ctl.subject_usage = ExtendedKeyUsage(vec![usage::PIN_RULES_CTL]);
+        assert_eq!(ctl.kind(), CtlKind::PinRules);
+
+        ctl.subject_usage = ExtendedKeyUsage(vec![ObjectIdentifier::new_unwrap("1.2.3.4")]);
+        assert_eq!(ctl.kind(), CtlKind::Unknown);
+
+        // The disallowed list is also recognized by name.
+        let name = DISALLOWED_CERT_LIST_NAME
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        ctl.list_identifier = Some(OctetString::new(name).unwrap());
+        assert_eq!(ctl.kind(), CtlKind::Disallowed);
+    }
+}
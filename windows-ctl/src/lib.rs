@@ -8,32 +8,115 @@
 #![allow(clippy::redundant_field_names)]
 #![forbid(unsafe_code)]
 
-use std::io::{Read, Seek};
+use std::io::Read;
 
-use der::asn1::{Any, ObjectIdentifier, OctetString, OctetStringRef, Uint};
-use der::{Decode, Enumerated, Sequence};
+use der::asn1::{Any, ObjectIdentifier, OctetString, OctetStringRef};
+use der::{Decode, Encode, Enumerated, Sequence};
 use itertools::Itertools;
-use pkcs7::{ContentInfo, ContentType};
-#[cfg(feature = "serde")]
-use serde::ser::SerializeStruct;
-#[cfg(feature = "serde")]
-use serde::{ser, Serialize};
+use pkcs7::ContentType;
 use spki::AlgorithmIdentifier;
 use thiserror::Error;
 use x509_cert::attr::Attributes;
+use x509_cert::ext::pkix::certpolicy::PolicyInformation;
 use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::ext::Extensions;
 use x509_cert::time::Time;
 
-/// The object identifier for [`CertificateTrustList`].
-pub const MS_CERT_TRUST_LIST_OID: ObjectIdentifier =
-    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.1");
+#[macro_use]
+mod diag;
+
+mod attributes;
+mod borrowed;
+mod builder;
+#[cfg(feature = "cab")]
+mod cabinet;
+mod consistency;
+mod countersignature;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod datetime;
+#[cfg(feature = "serde")]
+mod de;
+mod decoded;
+pub mod eku;
+mod envelope;
+mod kind;
+pub mod oids;
+pub mod prelude;
+mod purpose;
+#[cfg(feature = "schema")]
+mod schema;
+mod sequence_number;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "sign")]
+pub mod sign;
+mod signer_info;
+#[cfg(feature = "signers")]
+pub mod signers;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "cab")]
+mod stream;
+mod subject_id;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "serde")]
+mod timestamp;
+mod trust_bits;
+pub mod usage;
+mod utf16;
+#[cfg(feature = "verify")]
+pub mod verify;
+mod warnings;
+
+pub use attributes::{ChainPolicies, SubjectAttribute};
+pub use borrowed::{CertificateTrustListRef, CtlHeader, TrustedSubjectRef};
+pub use builder::CtlBuilder;
+#[cfg(feature = "cab")]
+pub use cabinet::{cabinet_members, CabinetMember};
+pub use consistency::Inconsistency;
+pub use countersignature::{
+    Timestamp, TimestampKind, COUNTERSIGNATURE_OID, MS_TIMESTAMP_TOKEN_OID, TIMESTAMP_TOKEN_OID,
+};
+pub use decoded::DecodedTrustedSubject;
+pub use envelope::{CtlMessage, RawCtl};
+pub use kind::CtlKind;
+#[doc(no_inline)]
+pub use oids::{
+    MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+    MS_CERT_PROP_ID_FRIENDLY_NAME_OID, MS_CERT_PROP_ID_KEY_IDENTIFIER_OID,
+    MS_CERT_PROP_ID_METAEKUS_OID, MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+    MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+    MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID, MS_CERT_PROP_ID_SHA256_HASH_OID,
+    MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID, MS_CERT_TRUST_LIST_OID,
+};
+pub use purpose::Purpose;
+pub use sequence_number::SequenceNumber;
+#[cfg(feature = "serde")]
+pub use ser::{BinaryEncoding, EkuFormat, SerializeOptions, TimeFormat, WithOptions};
+pub use signer_info::{
+    algorithm_name, SignerMetadata, CONTENT_TYPE_OID, MESSAGE_DIGEST_OID, SIGNING_TIME_OID,
+};
+pub use subject_id::{IdentifierKind, SubjectAlgorithm, SubjectId};
+pub use trust_bits::TrustBits;
+pub use usage::ListUsage;
+pub use warnings::CtlWarning;
+
+/// The [name](CertificateTrustList::name) of the disallowed list (`disallowedcert.stl`)
+/// distributed by Windows' AutoUpdate.
+pub const DISALLOWED_CERT_LIST_NAME: &str = "DisallowedCert_AutoUpdate_1";
 
-/// The OID for an attribute containing `ExtendedKeyUsage` identifiers.
-pub const MS_CERT_PROP_ID_METAEKUS_OID: ObjectIdentifier =
-    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.9");
+/// The SHA-1 algorithm identifier, which Microsoft's CTLs use as their `subjectAlgorithm`.
+const SHA1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+
+/// The SHA-256 algorithm identifier, which some CTLs use as their `subjectAlgorithm` instead.
+const SHA256_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
 
 /// Possible errors while parsing a certificate trust list.
+///
+/// New variants may be added in minor releases.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CtlError {
     /// I/O errors.
     #[error("I/O error")]
@@ -58,6 +141,139 @@ pub enum CtlError {
     /// Valid PKCS#7 that claims to have a `CertificateTrustList`, but not present.
     #[error("missing SignedData inner content")]
     MissingSignedDataContent,
+
+    /// A PKCS#7 signature that was expected to be detached, but has inner content.
+    #[error("unexpected SignedData inner content in detached signature")]
+    UnexpectedSignedDataContent,
+
+    /// PEM input whose label isn't one that PKCS#7 is armored with.
+    #[error("bad PEM label: expected PKCS7 or CMS, got {0:?}")]
+    PemLabel(String),
+
+    /// A subject attribute whose value couldn't be decoded.
+    #[error("malformed {oid} attribute: {reason}")]
+    Attribute {
+        /// The attribute's OID.
+        oid: ObjectIdentifier,
+        /// Why the value couldn't be decoded.
+        reason: &'static str,
+    },
+
+    /// A list identifier that couldn't be decoded as a name.
+    #[error("malformed list identifier: {0}")]
+    ListIdentifier(&'static str),
+
+    /// A list that couldn't be built, e.g. by [`CtlBuilder`].
+    #[error("invalid CTL: {0}")]
+    InvalidList(&'static str),
+
+    /// A subject identifier that couldn't be parsed.
+    #[error("invalid subject identifier: {0:?}")]
+    InvalidSubjectId(String),
+
+    /// A purpose that couldn't be parsed.
+    #[error("invalid purpose: {0:?} (expected a name like server-auth, or an OID)")]
+    InvalidPurpose(String),
+
+    /// A cabinet that contains no CTLs.
+    #[cfg(feature = "cab")]
+    #[error("no CTL (.stl) member found in cabinet")]
+    MissingCabinetCtl,
+
+    /// A cabinet that contains more than one CTL, when only one was expected.
+    #[cfg(feature = "cab")]
+    #[error("expected one CTL in cabinet, found several: {0:?}")]
+    AmbiguousCabinet(Vec<String>),
+
+    /// A certificate source that failed to retrieve a certificate.
+    #[cfg(feature = "store")]
+    #[error("failed to retrieve certificate for {0}")]
+    Fetch(
+        SubjectId,
+        #[source] Box<dyn std::error::Error + Send + Sync>,
+    ),
+
+    /// A certificate that rustls wouldn't accept as a trust anchor.
+    #[cfg(feature = "rustls")]
+    #[error("invalid trust anchor for {0}")]
+    TrustAnchor(SubjectId, #[source] rustls::Error),
+
+    /// A retrieved certificate that doesn't match the CTL entry it was retrieved for.
+    #[cfg(feature = "store")]
+    #[error("retrieved certificate doesn't match {subject}: expected {algorithm} {expected}, got {actual}")]
+    FingerprintMismatch {
+        /// The entry's identifier.
+        subject: SubjectId,
+        /// The name of the mismatched hash algorithm.
+        algorithm: &'static str,
+        /// The hash listed in the CTL, as hex.
+        expected: String,
+        /// The retrieved certificate's hash, as hex.
+        actual: String,
+    },
+
+    /// A signer that failed to sign a CTL.
+    #[cfg(feature = "sign")]
+    #[error("failed to sign CTL")]
+    Sign(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// A CTL with no signers, and so nothing to verify.
+    #[error("CTL is unsigned")]
+    Unsigned,
+
+    /// A signer whose certificate is neither embedded in the CTL nor supplied.
+    #[error("signer's certificate not found")]
+    MissingSignerCertificate,
+
+    /// A signer whose signed attributes don't describe what it signed: the CTL, or for
+    /// a timestamp's signer, the timestamp.
+    #[error("bad signed attributes: {0}")]
+    SignedAttributes(&'static str),
+
+    /// A signer whose certificate doesn't assert the EKU that verification requires.
+    #[error("signer's certificate doesn't assert the {0} EKU")]
+    SignerUsage(ObjectIdentifier),
+
+    /// A signer using a digest algorithm that verification's policy disallows.
+    #[error("digest algorithm {0} is disallowed by policy")]
+    DisallowedDigest(ObjectIdentifier),
+
+    /// A certificate whose RSA key is shorter than verification's policy requires.
+    #[error("{bits}-bit RSA key is shorter than the required {minimum} bits")]
+    WeakKey {
+        /// The key's size, in bits.
+        bits: usize,
+        /// The smallest size that the policy allows.
+        minimum: usize,
+    },
+
+    /// A timestamp whose imprint isn't the digest of the signature it's on.
+    #[error("timestamp doesn't cover the signature")]
+    TimestampMismatch,
+
+    /// A digest or signature algorithm that verification doesn't support.
+    #[error("unsupported {kind} algorithm: {oid}")]
+    UnsupportedAlgorithm {
+        /// Whether the algorithm is a `digest` or `signature` algorithm.
+        kind: &'static str,
+        /// The algorithm's OID.
+        oid: ObjectIdentifier,
+    },
+
+    /// A certificate that can't be used as a trust anchor for verification.
+    #[cfg(feature = "verify")]
+    #[error("invalid trust anchor")]
+    InvalidTrustAnchor(#[source] webpki::Error),
+
+    /// A signer's signature that doesn't verify under its certificate's key.
+    #[cfg(feature = "verify")]
+    #[error("bad CTL signature")]
+    BadSignature(#[source] webpki::Error),
+
+    /// A signer whose certificate doesn't chain to a trust anchor.
+    #[cfg(feature = "verify")]
+    #[error("CTL signer isn't trusted")]
+    UntrustedSigner(#[source] webpki::Error),
 }
 
 /// ```asn1
@@ -72,7 +288,99 @@ pub type SubjectIdentifier = OctetString;
 /// ```asn1
 /// MetaEku ::= SEQUENCE OF OBJECT IDENTIFIER
 /// ```
-pub type MetaEku = Vec<ObjectIdentifier>;
+///
+/// Although it's encoded as a sequence, a `MetaEku` is used as a set of purposes,
+/// and so offers set-style queries like [`MetaEku::contains`] and [`MetaEku::intersects`].
+/// It can be collected from an iterator of OIDs, including a fallible one like
+/// [`TrustedSubject::extended_key_usages`]:
+///
+/// ```
+/// # use windows_ctl::{MetaEku, TrustedSubject};
+/// # fn example(subject: &TrustedSubject) -> Result<(), der::Error> {
+/// let ekus = subject.extended_key_usages().collect::<Result<MetaEku, _>>()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct MetaEku(pub Vec<ObjectIdentifier>);
+
+impl MetaEku {
+    /// Returns whether `eku` is one of these EKUs.
+    pub fn contains(&self, eku: &ObjectIdentifier) -> bool {
+        self.0.contains(eku)
+    }
+
+    /// Returns whether any EKU is in both `self` and `other`.
+    pub fn intersects(&self, other: &MetaEku) -> bool {
+        self.iter().any(|eku| other.contains(eku))
+    }
+
+    /// Returns an iterator over the EKUs.
+    pub fn iter(&self) -> impl Iterator<Item = &ObjectIdentifier> {
+        self.0.iter()
+    }
+
+    /// Returns the number of EKUs.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether there are no EKUs.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> Decode<'a> for MetaEku {
+    fn decode<R: der::Reader<'a>>(reader: &mut R) -> der::Result<Self> {
+        Ok(Self(Vec::decode(reader)?))
+    }
+}
+
+impl Encode for MetaEku {
+    fn encoded_len(&self) -> der::Result<der::Length> {
+        self.0.encoded_len()
+    }
+
+    fn encode(&self, writer: &mut impl der::Writer) -> der::Result<()> {
+        self.0.encode(writer)
+    }
+}
+
+impl From<Vec<ObjectIdentifier>> for MetaEku {
+    fn from(value: Vec<ObjectIdentifier>) -> Self {
+        Self(value)
+    }
+}
+
+impl FromIterator<ObjectIdentifier> for MetaEku {
+    fn from_iter<T: IntoIterator<Item = ObjectIdentifier>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for MetaEku {
+    type Item = ObjectIdentifier;
+    type IntoIter = std::vec::IntoIter<ObjectIdentifier>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MetaEku {
+    type Item = &'a ObjectIdentifier;
+    type IntoIter = std::slice::Iter<'a, ObjectIdentifier>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A single certificate policy granted to a subject by Microsoft's root program.
+///
+/// These are encoded the same way as the entries of an X.509 `certificatePolicies` extension.
+pub type CertificatePolicy = PolicyInformation;
 
 /// Represents a single entry in the certificate trust list.
 ///
@@ -92,15 +400,54 @@ pub struct TrustedSubject {
 }
 
 impl TrustedSubject {
-    /// Returns the certificate's ID, as bytes.
-    pub fn cert_id(&self) -> &[u8] {
-        self.identifier.as_bytes()
+    /// Returns the certificate's ID (usually its thumbprint).
+    pub fn cert_id(&self) -> SubjectId {
+        SubjectId::from(self.identifier.as_bytes())
+    }
+
+    /// Returns whether this subject's identifier is `id`, without allocating.
+    pub fn has_id(&self, id: impl AsRef<[u8]>) -> bool {
+        self.identifier.as_bytes() == id.as_ref()
+    }
+
+    /// Returns whether this subject's identifier is equal to the given hex string,
+    /// without allocating. See [`SubjectId::eq_hex`].
+    pub fn has_hex_id(&self, hex: &str) -> bool {
+        subject_id::eq_hex(self.identifier.as_bytes(), hex)
     }
 
     /// Returns an iterator over all Extended Key Usages (EKUs) listed
     /// in this `TrustedSubject`.
     pub fn extended_key_usages(
         &self,
+    ) -> impl Iterator<Item = Result<ObjectIdentifier, der::Error>> + '_ {
+        self.meta_ekus(MS_CERT_PROP_ID_METAEKUS_OID)
+    }
+
+    /// Returns an iterator over the Extended Key Usages (EKUs) that this `TrustedSubject`
+    /// is distrusted for.
+    ///
+    /// Entries on the disallowed list without this attribute are distrusted for all EKUs.
+    pub fn disallowed_ekus(
+        &self,
+    ) -> impl Iterator<Item = Result<ObjectIdentifier, der::Error>> + '_ {
+        self.meta_ekus(MS_CERT_PROP_ID_DISALLOWED_EKUS_OID)
+    }
+
+    /// Returns an iterator over the Extended Key Usages (EKUs) that this `TrustedSubject`'s
+    /// [not-before](TrustedSubject::not_before) distrust is scoped to.
+    ///
+    /// Subjects without this attribute have their not-before distrust applied to all EKUs.
+    pub fn not_before_ekus(
+        &self,
+    ) -> impl Iterator<Item = Result<ObjectIdentifier, der::Error>> + '_ {
+        self.meta_ekus(MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID)
+    }
+
+    /// Returns an iterator over the EKUs in every MetaEKU-valued attribute identified by `oid`.
+    fn meta_ekus(
+        &self,
+        oid: ObjectIdentifier,
     ) -> impl Iterator<Item = Result<ObjectIdentifier, der::Error>> + '_ {
         // Option<Attributes>
         //   -> Iterator<Attribute>
@@ -112,7 +459,7 @@ impl TrustedSubject {
         self.attributes
             .iter()
             .flat_map(|attrs| attrs.iter())
-            .filter(|attr| attr.oid == MS_CERT_PROP_ID_METAEKUS_OID)
+            .filter(move |attr| attr.oid == oid)
             .flat_map(|attr| attr.values.iter())
             .flat_map(|value| {
                 value
@@ -121,26 +468,14 @@ impl TrustedSubject {
             })
             .flatten_ok()
     }
-}
 
-#[cfg(feature = "serde")]
-impl Serialize for TrustedSubject {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let eku_oids = self
-            .extended_key_usages()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| ser::Error::custom(format!("EKU collection failed: {e}")))?
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<_>>();
-
-        let mut s = serializer.serialize_struct("TrustedSubject", 2)?;
-        s.serialize_field("identifier", &hex::encode(self.identifier.as_bytes()))?;
-        s.serialize_field("ekus", &eku_oids)?;
-        s.end()
+    /// Returns the DER encoding of this `TrustedSubject`.
+    ///
+    /// Since CTLs are parsed as strict DER, this is byte-for-byte identical to the
+    /// entry's encoding within the list it came from, making it suitable for hashing
+    /// or comparing individual entries across CTL versions.
+    pub fn to_der(&self) -> Result<Vec<u8>, CtlError> {
+        Ok(Encode::to_der(self)?)
     }
 }
 
@@ -207,8 +542,8 @@ pub struct CertificateTrustList {
     /// See [MS-CAESO](https://yossarian.net/junk/hard_to_find/ms-caeso-v20090709.pdf) page 48.
     pub list_identifier: Option<ListIdentifier>,
 
-    /// Some kind of sequence number; purpose unknown.
-    pub sequence_number: Option<Uint>,
+    /// This list's sequence number, which increases with each release.
+    pub sequence_number: Option<SequenceNumber>,
 
     // NOTE: MS doesn't bother to document `ChoiceOfTime`, but experimentally
     // it's the same thing as an X.509 `Time` (See <https://www.rfc-editor.org/rfc/rfc5280#section-4.1>)
@@ -224,40 +559,101 @@ pub struct CertificateTrustList {
     /// The list of trusted subjects in this CTL.
     pub trusted_subjects: Option<TrustedSubjects>,
 
-    // TODO: this should really be `x509_cert::ext::Extensions`
-    // but that's a borrowed type and this struct is owning.
     /// Any X.509 style extensions.
     #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
-    pub ctl_extensions: Option<Any>,
+    pub ctl_extensions: Option<Extensions>,
 }
 
 impl CertificateTrustList {
+    /// Returns this list's name, i.e. its [`list_identifier`](CertificateTrustList::list_identifier)
+    /// decoded as the UTF-16LE string that Microsoft uses, if it has one.
+    ///
+    /// See [`DISALLOWED_CERT_LIST_NAME`] for the names of known lists.
+    pub fn name(&self) -> Result<Option<String>, CtlError> {
+        self.list_identifier
+            .as_ref()
+            .map(|id| utf16::decode(id.as_bytes()).map_err(CtlError::ListIdentifier))
+            .transpose()
+    }
+
+    /// Returns the algorithm this list's subjects are identified by, or `None` if
+    /// its [`subject_algorithm`](CertificateTrustList::subject_algorithm) isn't one
+    /// that's understood.
+    pub fn identifier_algorithm(&self) -> Option<SubjectAlgorithm> {
+        SubjectAlgorithm::from_oid(&self.subject_algorithm.oid)
+    }
+
+    /// Computes the identifier that this list would give the DER-encoded certificate,
+    /// per its [`identifier_algorithm`](CertificateTrustList::identifier_algorithm).
+    #[cfg(feature = "store")]
+    pub fn identify(&self, der: &[u8]) -> Option<SubjectId> {
+        self.identifier_algorithm()
+            .map(|algorithm| algorithm.identify(der))
+    }
+
+    /// Returns the entry for the DER-encoded certificate, if this list has one.
+    #[cfg(feature = "store")]
+    pub fn find(&self, der: &[u8]) -> Option<&TrustedSubject> {
+        let id = self.identify(der)?;
+        self.trusted_subjects
+            .iter()
+            .flatten()
+            .find(|entry| entry.has_id(&id))
+    }
+
     /// Load a `CertificateTrustList` from the given source, which is expected to be a DER-encoded
     /// PKCS#7 stream.
-    pub fn from_der<R: Read + Seek>(mut source: R) -> Result<Self, CtlError> {
-        // TODO: Micro-optimize: could pre-allocate `der` here using the stream's
-        // size (since we have the `Seek` bound).
+    ///
+    /// Only the list itself is kept: use [`CtlMessage::from_der`] to also keep the
+    /// envelope, with its signers and certificates, for verification or auditing.
+    ///
+    /// The source needn't be seekable, so standard input and network streams can be
+    /// read directly. It's read into memory in full; see
+    /// [`CertificateTrustList::from_der_slice`] for DER that's already in memory.
+    pub fn from_der<R: Read>(source: R) -> Result<Self, CtlError> {
+        let (ctl, _) = Self::from_der_with_raw(source)?;
+        Ok(ctl)
+    }
+
+    /// Like [`CertificateTrustList::from_der`], but additionally returns the [`RawCtl`]
+    /// that the list was parsed from.
+    pub fn from_der_with_raw<R: Read>(mut source: R) -> Result<(Self, RawCtl), CtlError> {
         let mut der = vec![];
         source.read_to_end(&mut der)?;
 
-        let body = ContentInfo::from_der(&der)?;
-        let signed_data = match body {
-            ContentInfo::SignedData(signed_data) => signed_data,
-            _ => return Err(CtlError::ContentType(body.content_type())),
-        };
+        RawCtl::parse(der)
+    }
 
-        // Our actual SignedData content should be a MS-specific `certTrustList`.
-        if signed_data.encap_content_info.e_content_type != MS_CERT_TRUST_LIST_OID {
-            return Err(CtlError::Content(
-                signed_data.encap_content_info.e_content_type,
-            ));
-        }
+    /// Load a `CertificateTrustList` from DER-encoded PKCS#7 that's already in memory,
+    /// e.g. a memory-mapped file or an embedded resource.
+    ///
+    /// This is equivalent to [`CertificateTrustList::from_der`], but decodes the list
+    /// in place rather than copying the DER first.
+    pub fn from_der_slice(der: &[u8]) -> Result<Self, CtlError> {
+        Ok(<Self as Decode>::from_der(envelope::encapsulated_content(
+            der,
+        )?)?)
+    }
 
-        let Some(content) = signed_data.encap_content_info.e_content else {
-            return Err(CtlError::MissingSignedDataContent);
-        };
+    /// Decodes a bare DER-encoded `certTrustList`, i.e. the content of a CTL without
+    /// its PKCS#7 envelope, as found in forensic extractions and some registry caches.
+    ///
+    /// This is the inverse of [`CertificateTrustList::to_der`]. Since there's no
+    /// envelope, there are no signers to inspect.
+    pub fn from_raw_der(der: &[u8]) -> Result<Self, CtlError> {
+        Ok(<Self as Decode>::from_der(der)?)
+    }
 
-        Ok(content.decode_as()?)
+    /// Returns the DER encoding of this list: the `certTrustList` content, without a
+    /// PKCS#7 envelope.
+    ///
+    /// For a parsed list, this is byte-for-byte identical to the content it was parsed
+    /// from (see [`RawCtl::content`]), provided that was canonical DER, as Microsoft's
+    /// lists are. Encodings that DER forbids but the decoder tolerates, such as an
+    /// explicitly encoded default `version`, come out canonicalized. Modified lists
+    /// (e.g. filtered or merged ones) can be re-signed from this encoding.
+    pub fn to_der(&self) -> Result<Vec<u8>, CtlError> {
+        Ok(Encode::to_der(self)?)
     }
 }
 
@@ -273,8 +669,188 @@ mod tests {
         let res = MetaEku::from_der(metaeku).unwrap();
 
         assert_eq!(res.len(), 3);
-        assert_eq!(res[0], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.2"));
-        assert_eq!(res[1], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.4"));
-        assert_eq!(res[2], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1"));
+        assert_eq!(res.0[0], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.2"));
+        assert_eq!(res.0[1], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.4"));
+        assert_eq!(res.0[2], ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1"));
+        assert_eq!(Encode::to_der(&res).unwrap(), metaeku);
+    }
+
+    #[test]
+    fn test_metaeku_set_ops() {
+        let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+        let client_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.2");
+        let code_signing = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+
+        let ekus = [server_auth, client_auth].into_iter().collect::<MetaEku>();
+        assert!(ekus.contains(&server_auth));
+        assert!(!ekus.contains(&code_signing));
+        assert!(ekus.intersects(&MetaEku(vec![code_signing, client_auth])));
+        assert!(!ekus.intersects(&MetaEku(vec![code_signing])));
+        assert!(!ekus.intersects(&MetaEku::default()));
+
+        let subject = &testing::sample_ctl().trusted_subjects.unwrap()[0];
+        let ekus = subject
+            .extended_key_usages()
+            .collect::<Result<MetaEku, _>>()
+            .unwrap();
+        assert_eq!(ekus, MetaEku(vec![server_auth, code_signing]));
+    }
+
+    #[test]
+    fn test_name() {
+        let mut ctl = testing::sample_ctl();
+        assert_eq!(ctl.name().unwrap(), None);
+
+        let name = DISALLOWED_CERT_LIST_NAME
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        ctl.list_identifier = Some(OctetString::new(name).unwrap());
+        assert_eq!(
+            ctl.name().unwrap().as_deref(),
+            Some(DISALLOWED_CERT_LIST_NAME)
+        );
+
+        ctl.list_identifier = Some(OctetString::new([0x41]).unwrap());
+        assert!(matches!(ctl.name(), Err(CtlError::ListIdentifier(_))));
+    }
+
+    #[test]
+    fn test_ctl_extensions() {
+        use x509_cert::ext::Extension;
+
+        let mut ctl = testing::sample_ctl();
+        ctl.ctl_extensions = Some(vec![Extension {
+            extn_id: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.21.1"),
+            critical: false,
+            extn_value: OctetString::new([0x02, 0x01, 0x00]).unwrap(),
+        }]);
+
+        let der = Encode::to_der(&ctl).unwrap();
+        let parsed = <CertificateTrustList as Decode>::from_der(&der).unwrap();
+        assert_eq!(parsed, ctl);
+
+        let extensions = parsed.ctl_extensions.unwrap();
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0].extn_value.as_bytes(), [0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_ctl_to_der_round_trip() {
+        use der::asn1::GeneralizedTime;
+        use der::Length;
+        use std::time::Duration;
+        use x509_cert::ext::Extension;
+
+        let mut full = testing::sample_ctl();
+        full.list_identifier = Some(OctetString::new([0xab, 0xcd]).unwrap());
+        full.sequence_number = Some(SequenceNumber::new(&[0x00, 0x80, 0x01]).unwrap());
+        full.next_update = Some(Time::GeneralTime(
+            GeneralizedTime::from_unix_duration(Duration::from_secs(4_102_444_800)).unwrap(),
+        ));
+        full.ctl_extensions = Some(vec![Extension {
+            extn_id: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.21.1"),
+            critical: false,
+            extn_value: OctetString::new([0x02, 0x01, 0x00]).unwrap(),
+        }]);
+        let mut empty = testing::sample_sha256_ctl();
+        empty.trusted_subjects = None;
+
+        for ctl in [testing::sample_ctl(), full, empty] {
+            // Parsed from a signed list, the list re-encodes to exactly its content.
+            let (parsed, raw) = RawCtl::parse(testing::sign_ctl(&ctl)).unwrap();
+            assert_eq!(parsed, ctl);
+            let der = parsed.to_der().unwrap();
+            assert_eq!(der, raw.content());
+            assert_eq!(
+                <CertificateTrustList as Decode>::from_der(&der).unwrap(),
+                ctl
+            );
+        }
+
+        // A filtered list re-encodes (and decodes) without the filtered entries.
+        let mut filtered = testing::sample_ctl();
+        filtered.trusted_subjects.as_mut().unwrap().remove(0);
+        let der = filtered.to_der().unwrap();
+        let parsed = <CertificateTrustList as Decode>::from_der(&der).unwrap();
+        assert_eq!(parsed.trusted_subjects.unwrap().len(), 1);
+
+        // An explicitly encoded default version is tolerated, but canonicalized away.
+        let der = testing::sample_ctl().to_der().unwrap();
+        let header_len = match der[1] {
+            len @ 0x81.. => 2 + usize::from(len & 0x7f),
+            _ => 2,
+        };
+        let mut contents = vec![0x02, 0x01, 0x00];
+        contents.extend(&der[header_len..]);
+        let mut explicit = vec![0x30];
+        explicit.extend(Length::try_from(contents.len()).unwrap().to_der().unwrap());
+        explicit.extend(contents);
+        let parsed = <CertificateTrustList as Decode>::from_der(&explicit).unwrap();
+        assert_eq!(parsed.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn test_from_der_slice() {
+        let ctl = testing::sample_ctl();
+        let signed = testing::sign_ctl(&ctl);
+        assert_eq!(CertificateTrustList::from_der_slice(&signed).unwrap(), ctl);
+        assert_eq!(
+            CertificateTrustList::from_der_slice(&signed).unwrap(),
+            CertificateTrustList::from_der(std::io::Cursor::new(&signed)).unwrap()
+        );
+
+        assert!(matches!(
+            CertificateTrustList::from_der_slice(&testing::sign_ctl_detached()),
+            Err(CtlError::MissingSignedDataContent)
+        ));
+        assert!(matches!(
+            CertificateTrustList::from_der_slice(&ctl.to_der().unwrap()),
+            Err(CtlError::Der(_))
+        ));
+        assert!(CertificateTrustList::from_der_slice(&signed[..signed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_from_der_unseekable() {
+        let ctl = testing::sample_ctl();
+        let signed = testing::sign_ctl(&ctl);
+
+        // A slice is `Read`, but not `Seek`.
+        assert_eq!(CertificateTrustList::from_der(&signed[..]).unwrap(), ctl);
+        assert!(CertificateTrustList::from_der(&signed[..signed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_from_raw_der() {
+        let ctl = testing::sample_ctl();
+        let signed = testing::sign_ctl(&ctl);
+        let (_, raw) = RawCtl::parse(signed.clone()).unwrap();
+        assert_eq!(
+            CertificateTrustList::from_raw_der(raw.content()).unwrap(),
+            ctl
+        );
+        assert_eq!(
+            CertificateTrustList::from_raw_der(&ctl.to_der().unwrap()).unwrap(),
+            ctl
+        );
+
+        // A full PKCS#7 message isn't a bare list, and neither is a truncated one.
+        assert!(CertificateTrustList::from_raw_der(&signed).is_err());
+        let content = raw.content();
+        assert!(CertificateTrustList::from_raw_der(&content[..content.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_trusted_subject_to_der() {
+        let ctl = testing::sample_ctl();
+        let der = Encode::to_der(&ctl).unwrap();
+
+        for subject in ctl.trusted_subjects.as_ref().unwrap() {
+            let encoded = subject.to_der().unwrap();
+            assert!(der.windows(encoded.len()).any(|w| w == encoded));
+            assert_eq!(&TrustedSubject::from_der(&encoded).unwrap(), subject);
+        }
     }
 }
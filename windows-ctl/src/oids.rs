@@ -0,0 +1,188 @@
+//! Object identifiers for CTLs and the Microsoft properties attached to their subjects.
+//!
+//! Each property OID is `1.3.6.1.4.1.311.10.11.N` (under `szOID_CERT_PROP_ID_PREFIX`), where
+//! `N` is the corresponding Win32 `CERT_*_PROP_ID`.
+
+use der::asn1::ObjectIdentifier;
+
+/// The object identifier for [`CertificateTrustList`](crate::CertificateTrustList).
+pub const MS_CERT_TRUST_LIST_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.1");
+
+/// The OID for an attribute containing the SHA-1 hash of a subject's certificate
+/// (`CERT_SHA1_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_SHA1_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.3");
+
+/// The OID for an attribute containing the MD5 hash of a subject's certificate
+/// (`CERT_MD5_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_MD5_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.4");
+
+/// The OID for an attribute containing `ExtendedKeyUsage` identifiers.
+pub const MS_CERT_PROP_ID_METAEKUS_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.9");
+
+/// The OID for an attribute containing a subject's display name (`CERT_FRIENDLY_NAME_PROP_ID`).
+pub const MS_CERT_PROP_ID_FRIENDLY_NAME_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.11");
+
+/// The OID for an attribute containing the MD5 hash of a subject's signature
+/// (`CERT_SIGNATURE_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_SIGNATURE_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.15");
+
+/// The OID for an attribute containing a subject's key identifier (`CERT_KEY_IDENTIFIER_PROP_ID`).
+pub const MS_CERT_PROP_ID_KEY_IDENTIFIER_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.20");
+
+/// The OID for an attribute containing the MD5 hash of a subject's issuer's public key
+/// (`CERT_ISSUER_PUBLIC_KEY_MD5_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_ISSUER_PUBLIC_KEY_MD5_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.24");
+
+/// The OID for an attribute containing the MD5 hash of a subject's public key
+/// (`CERT_SUBJECT_PUBLIC_KEY_MD5_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_SUBJECT_PUBLIC_KEY_MD5_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.25");
+
+/// The OID for an attribute containing the MD5 hash of a subject's issuer name and serial
+/// number (`CERT_ISSUER_SERIAL_NUMBER_MD5_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_ISSUER_SERIAL_NUMBER_MD5_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.28");
+
+/// The OID for an attribute containing the MD5 hash of a subject's encoded subject name
+/// (`CERT_SUBJECT_NAME_MD5_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.29");
+
+/// The OID for an attribute containing the certificate policies (e.g. EV policies) that
+/// Microsoft's root program grants a subject (`CERT_ROOT_PROGRAM_CERT_POLICIES_PROP_ID`).
+pub const MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.83");
+
+/// The OID for an attribute containing the name constraints that Microsoft's root program
+/// applies to a subject (`CERT_ROOT_PROGRAM_NAME_CONSTRAINTS_PROP_ID`).
+pub const MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.84");
+
+/// The OID for an attribute containing the SHA-256 hash of a subject's certificate
+/// (`CERT_AUTH_ROOT_SHA256_HASH_PROP_ID`).
+pub const MS_CERT_PROP_ID_SHA256_HASH_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.98");
+
+/// The OID for an attribute containing the `FILETIME` at which a subject was distrusted
+/// (`CERT_DISALLOWED_FILETIME_PROP_ID`).
+pub const MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.104");
+
+/// The OID for an attribute containing the chain policies that Microsoft's root program
+/// applies to a subject (`CERT_ROOT_PROGRAM_CHAIN_POLICIES_PROP_ID`).
+pub const MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.105");
+
+/// The OID for an attribute containing the `ExtendedKeyUsage` identifiers that a subject is
+/// distrusted for (`CERT_DISALLOWED_ENHKEY_USAGE_PROP_ID`).
+pub const MS_CERT_PROP_ID_DISALLOWED_EKUS_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.122");
+
+/// The OID for an attribute containing the `FILETIME` after which certificates issued by a
+/// subject are no longer trusted (`CERT_NOT_BEFORE_FILETIME_PROP_ID`).
+pub const MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.126");
+
+/// The OID for an attribute containing the `ExtendedKeyUsage` identifiers that a subject's
+/// not-before `FILETIME` applies to (`CERT_NOT_BEFORE_ENHKEY_USAGE_PROP_ID`).
+pub const MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.127");
+
+/// Every OID in this module, along with its description.
+const DESCRIPTIONS: &[(ObjectIdentifier, &str)] = &[
+    (MS_CERT_TRUST_LIST_OID, "certificate trust list"),
+    (MS_CERT_PROP_ID_SHA1_HASH_OID, "SHA-1 certificate hash"),
+    (MS_CERT_PROP_ID_MD5_HASH_OID, "MD5 certificate hash"),
+    (MS_CERT_PROP_ID_METAEKUS_OID, "extended key usages"),
+    (MS_CERT_PROP_ID_FRIENDLY_NAME_OID, "friendly name"),
+    (MS_CERT_PROP_ID_SIGNATURE_HASH_OID, "signature hash"),
+    (MS_CERT_PROP_ID_KEY_IDENTIFIER_OID, "key identifier"),
+    (
+        MS_CERT_PROP_ID_ISSUER_PUBLIC_KEY_MD5_HASH_OID,
+        "MD5 issuer public key hash",
+    ),
+    (
+        MS_CERT_PROP_ID_SUBJECT_PUBLIC_KEY_MD5_HASH_OID,
+        "MD5 subject public key hash",
+    ),
+    (
+        MS_CERT_PROP_ID_ISSUER_SERIAL_NUMBER_MD5_HASH_OID,
+        "MD5 issuer and serial number hash",
+    ),
+    (
+        MS_CERT_PROP_ID_SUBJECT_NAME_MD5_HASH_OID,
+        "MD5 subject name hash",
+    ),
+    (
+        MS_CERT_PROP_ID_ROOT_PROGRAM_CERT_POLICIES_OID,
+        "root program certificate policies",
+    ),
+    (
+        MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID,
+        "root program name constraints",
+    ),
+    (MS_CERT_PROP_ID_SHA256_HASH_OID, "SHA-256 certificate hash"),
+    (MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID, "disallowed time"),
+    (
+        MS_CERT_PROP_ID_ROOT_PROGRAM_CHAIN_POLICIES_OID,
+        "root program chain policies",
+    ),
+    (
+        MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+        "disallowed extended key usages",
+    ),
+    (MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID, "not-before time"),
+    (
+        MS_CERT_PROP_ID_NOT_BEFORE_EKUS_OID,
+        "not-before extended key usages",
+    ),
+];
+
+/// Returns a short, human-readable description of the given OID, if it's one of
+/// the OIDs in this module.
+pub fn describe(oid: &ObjectIdentifier) -> Option<&'static str> {
+    DESCRIPTIONS
+        .iter()
+        .find(|(known, _)| known == oid)
+        .map(|(_, description)| *description)
+}
+
+/// Returns whether the given OID is one of the OIDs in this module, i.e. whether it's
+/// [described](describe).
+pub fn is_known(oid: &ObjectIdentifier) -> bool {
+    describe(oid).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(&MS_CERT_PROP_ID_FRIENDLY_NAME_OID),
+            Some("friendly name")
+        );
+        assert_eq!(
+            describe(&ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.10.11.999")),
+            None
+        );
+        assert!(is_known(&MS_CERT_PROP_ID_ROOT_PROGRAM_NAME_CONSTRAINTS_OID));
+        assert!(!is_known(&ObjectIdentifier::new_unwrap(
+            "1.3.6.1.4.1.311.10.11.999"
+        )));
+
+        // No OID is listed twice.
+        for (i, (oid, _)) in DESCRIPTIONS.iter().enumerate() {
+            assert!(!DESCRIPTIONS[i + 1..].iter().any(|(other, _)| other == oid));
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! The types and traits that most users of this crate need, for glob importing:
+//!
+//! ```
+//! use windows_ctl::prelude::*;
+//! ```
+//!
+//! This only re-exports items that are public elsewhere, so anything not found here
+//! can still be imported from its own module. Items are only ever added to the
+//! prelude, never removed from it, outside of major releases.
+
+pub use crate::{
+    CertificateTrustList, CtlError, CtlKind, CtlMessage, ListUsage, Purpose, RawCtl,
+    SequenceNumber, SubjectId, TrustedSubject,
+};
+
+#[cfg(feature = "sign")]
+pub use crate::sign::CtlSigner;
+
+#[cfg(feature = "store")]
+pub use crate::store::{
+    CertificateCache, CertificateFetcher, Storage, StoreBuilder, StoreWriter, TrustStore,
+};
+
+#[cfg(feature = "verify")]
+pub use crate::verify::{Policy, Verifier};
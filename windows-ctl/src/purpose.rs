@@ -0,0 +1,252 @@
+//! What a root can be trusted for.
+
+use std::fmt;
+use std::str::FromStr;
+
+use der::asn1::ObjectIdentifier;
+
+use crate::{eku, trust_bits, CtlError, DecodedTrustedSubject, MetaEku, TrustedSubject};
+
+/// A purpose that a root can be trusted (or distrusted) for, i.e. an EKU.
+///
+/// The well-known purposes have their own variants; any other EKU is
+/// [`Purpose::Other`]. Converting from an OID (with [`Purpose::from_oid`] or `From`)
+/// always picks the well-known variant where there is one, so purposes compare
+/// equal exactly when their OIDs do, as long as `Other` isn't constructed with a
+/// well-known OID directly.
+///
+/// Purposes parse from (and display as) short names like `server-auth`, or from
+/// dotted OIDs:
+///
+/// ```
+/// # use windows_ctl::{eku, Purpose};
+/// assert_eq!("server-auth".parse::<Purpose>().unwrap(), Purpose::ServerAuth);
+/// assert_eq!("1.3.6.1.5.5.7.3.3".parse::<Purpose>().unwrap(), Purpose::CodeSigning);
+/// assert_eq!(Purpose::from(eku::MS_DOCUMENT_SIGNING).to_string(), "document-signing");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Purpose {
+    /// [`eku::SERVER_AUTH`].
+    ServerAuth,
+    /// [`eku::CLIENT_AUTH`].
+    ClientAuth,
+    /// [`eku::CODE_SIGNING`].
+    CodeSigning,
+    /// [`eku::EMAIL_PROTECTION`].
+    EmailProtection,
+    /// [`eku::TIME_STAMPING`].
+    TimeStamping,
+    /// [`eku::OCSP_SIGNING`].
+    OcspSigning,
+    /// [`eku::MS_DOCUMENT_SIGNING`].
+    DocumentSigning,
+    /// [`eku::MS_KERNEL_MODE_CODE_SIGNING`].
+    KernelModeCodeSigning,
+    /// Any other EKU.
+    Other(ObjectIdentifier),
+}
+
+/// Every well-known purpose, along with its OID and short name.
+const PURPOSES: &[(Purpose, ObjectIdentifier, &str)] = &[
+    (Purpose::ServerAuth, eku::SERVER_AUTH, "server-auth"),
+    (Purpose::ClientAuth, eku::CLIENT_AUTH, "client-auth"),
+    (Purpose::CodeSigning, eku::CODE_SIGNING, "code-signing"),
+    (
+        Purpose::EmailProtection,
+        eku::EMAIL_PROTECTION,
+        "email-protection",
+    ),
+    (Purpose::TimeStamping, eku::TIME_STAMPING, "time-stamping"),
+    (Purpose::OcspSigning, eku::OCSP_SIGNING, "ocsp-signing"),
+    (
+        Purpose::DocumentSigning,
+        eku::MS_DOCUMENT_SIGNING,
+        "document-signing",
+    ),
+    (
+        Purpose::KernelModeCodeSigning,
+        eku::MS_KERNEL_MODE_CODE_SIGNING,
+        "kernel-mode-code-signing",
+    ),
+];
+
+impl Purpose {
+    /// Returns the purpose for `oid`.
+    pub fn from_oid(oid: ObjectIdentifier) -> Self {
+        PURPOSES
+            .iter()
+            .find(|(_, known, _)| *known == oid)
+            .map_or(Self::Other(oid), |(purpose, _, _)| *purpose)
+    }
+
+    /// Returns this purpose's EKU.
+    pub fn oid(&self) -> ObjectIdentifier {
+        match self {
+            Self::Other(oid) => *oid,
+            known => {
+                PURPOSES
+                    .iter()
+                    .find(|(purpose, _, _)| purpose == known)
+                    .expect("every purpose is listed")
+                    .1
+            }
+        }
+    }
+
+    /// Returns this purpose's human-readable name (as Windows displays it), if its
+    /// EKU has one. See [`eku::name`].
+    pub fn name(&self) -> Option<&'static str> {
+        eku::name(&self.oid())
+    }
+}
+
+impl From<ObjectIdentifier> for Purpose {
+    fn from(oid: ObjectIdentifier) -> Self {
+        Self::from_oid(oid)
+    }
+}
+
+impl From<Purpose> for ObjectIdentifier {
+    fn from(purpose: Purpose) -> Self {
+        purpose.oid()
+    }
+}
+
+impl FromStr for Purpose {
+    type Err = CtlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((purpose, _, _)) = PURPOSES.iter().find(|(_, _, name)| *name == s) {
+            return Ok(*purpose);
+        }
+
+        ObjectIdentifier::new(s)
+            .map(Self::from_oid)
+            .map_err(|_| CtlError::InvalidPurpose(s.into()))
+    }
+}
+
+impl fmt::Display for Purpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match PURPOSES.iter().find(|(purpose, _, _)| purpose == self) {
+            Some((_, _, name)) => f.write_str(name),
+            None => write!(f, "{}", self.oid()),
+        }
+    }
+}
+
+impl TrustedSubject {
+    /// Returns whether this subject is trusted for `purpose`: that is, whether its
+    /// MetaEKUs include the purpose's EKU, and it hasn't been distrusted for it.
+    ///
+    /// See [`TrustedSubject::trust_bits`] for how distrust is determined.
+    pub fn is_trusted_for(&self, purpose: Purpose) -> Result<bool, CtlError> {
+        let eku = purpose.oid();
+        let ekus = self.extended_key_usages().collect::<Result<MetaEku, _>>()?;
+        Ok(ekus.contains(&eku)
+            && !trust_bits::distrusted(
+                &eku,
+                &self.disallowed_ekus().collect::<Result<_, _>>()?,
+                self.disallowed_at()?.is_some(),
+            ))
+    }
+}
+
+impl DecodedTrustedSubject<'_> {
+    /// See [`TrustedSubject::is_trusted_for`].
+    pub fn is_trusted_for(&self, purpose: Purpose) -> bool {
+        let eku = purpose.oid();
+        self.extended_key_usages().contains(&eku)
+            && !trust_bits::distrusted(
+                &eku,
+                self.disallowed_ekus().unwrap_or(&MetaEku::default()),
+                self.disallowed_at().is_some(),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::Encode;
+
+    use super::*;
+    use crate::{
+        testing, MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+        MS_CERT_PROP_ID_METAEKUS_OID,
+    };
+
+    #[test]
+    fn test_purpose() {
+        for (purpose, oid, name) in PURPOSES {
+            assert_eq!(Purpose::from_oid(*oid), *purpose);
+            assert_eq!(purpose.oid(), *oid);
+            assert_eq!(name.parse::<Purpose>().unwrap(), *purpose);
+            assert_eq!(oid.to_string().parse::<Purpose>().unwrap(), *purpose);
+            assert_eq!(purpose.to_string(), *name);
+        }
+
+        let other = ObjectIdentifier::new_unwrap("1.2.3.4");
+        assert_eq!(Purpose::from(other), Purpose::Other(other));
+        assert_eq!(ObjectIdentifier::from(Purpose::Other(other)), other);
+        assert_eq!(Purpose::Other(other).to_string(), "1.2.3.4");
+        assert_eq!(Purpose::Other(other).name(), None);
+        assert_eq!(
+            Purpose::from(eku::MS_EFS).name(),
+            Some("Encrypting File System")
+        );
+
+        for invalid in ["", "server", "Server-Auth", "1"] {
+            assert!(matches!(
+                invalid.parse::<Purpose>(),
+                Err(CtlError::InvalidPurpose(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_trusted_for() {
+        let ekus = |oids: &[_]| MetaEku(oids.to_vec()).to_der().unwrap();
+        let meta = testing::attribute(
+            MS_CERT_PROP_ID_METAEKUS_OID,
+            &ekus(&[eku::SERVER_AUTH, eku::CODE_SIGNING]),
+        );
+        let disallowed = testing::attribute(
+            MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+            &132_223_104_000_000_000u64.to_le_bytes(),
+        );
+
+        for (attributes, expected) in [
+            (vec![meta.clone()], [true, true, false]),
+            (
+                vec![meta.clone(), disallowed.clone()],
+                [false, false, false],
+            ),
+            (
+                vec![
+                    meta,
+                    disallowed,
+                    testing::attribute(
+                        MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+                        &ekus(&[eku::SERVER_AUTH]),
+                    ),
+                ],
+                [false, true, false],
+            ),
+        ] {
+            let subject = testing::subject(&[0x11; 20], attributes);
+            let decoded = subject.decoded().unwrap();
+            for (purpose, expected) in [
+                Purpose::ServerAuth,
+                Purpose::CodeSigning,
+                Purpose::ClientAuth,
+            ]
+            .into_iter()
+            .zip(expected)
+            {
+                assert_eq!(subject.is_trusted_for(purpose).unwrap(), expected);
+                assert_eq!(decoded.is_trusted_for(purpose), expected);
+            }
+        }
+    }
+}
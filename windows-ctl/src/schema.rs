@@ -0,0 +1,118 @@
+//! JSON Schemas for the serialized forms of CTLs and their entries.
+//!
+//! The schemas are derived from the same types that [`CertificateTrustList`]s and
+//! [`TrustedSubject`]s are serialized through, so they can't drift apart. Use
+//! `schemars::schema_for!` to generate them, or compose them into other schemas.
+
+use std::borrow::Cow;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use crate::ser::{CtlForm, SubjectForm, Timestamp};
+use crate::{CertificateTrustList, TrustedSubject};
+
+/// The schema describes the list's serialized form under any
+/// [`SerializeOptions`](crate::SerializeOptions).
+impl JsonSchema for CertificateTrustList {
+    fn schema_name() -> Cow<'static, str> {
+        CtlForm::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        CtlForm::json_schema(generator)
+    }
+}
+
+/// The schema describes the entry's serialized form under any
+/// [`SerializeOptions`](crate::SerializeOptions).
+impl JsonSchema for TrustedSubject {
+    fn schema_name() -> Cow<'static, str> {
+        SubjectForm::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        SubjectForm::json_schema(generator)
+    }
+}
+
+impl JsonSchema for Timestamp {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "An RFC 3339 timestamp (or Unix seconds).",
+            "type": ["string", "integer"],
+            "format": "date-time",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::schema_for;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{testing, SerializeOptions, TimeFormat, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID};
+
+    /// Checks that `value` has exactly the properties that `schema` describes, and
+    /// at least those it requires.
+    fn check_properties(value: &Value, schema: &Value) {
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in &keys {
+            assert!(properties.contains_key(*key), "undescribed property: {key}");
+        }
+        for required in schema["required"].as_array().unwrap() {
+            assert!(keys.contains(&&required.as_str().unwrap().to_string()));
+        }
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects.as_mut().unwrap()[1] = testing::subject(
+            &[0x22; 20],
+            vec![testing::attribute(
+                MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+                &132_223_104_000_000_000u64.to_le_bytes(),
+            )],
+        );
+        let ctl_schema = schema_for!(CertificateTrustList).to_value();
+        let subject_schema = schema_for!(TrustedSubject).to_value();
+        assert_eq!(ctl_schema["title"], "CertificateTrustList");
+        assert_eq!(
+            ctl_schema["properties"]["entries"]["items"]["$ref"],
+            "#/$defs/TrustedSubject"
+        );
+        assert_eq!(
+            ctl_schema["$defs"]["TrustedSubject"]["properties"],
+            subject_schema["properties"]
+        );
+        let attribute_schema = &subject_schema["$defs"]["RawAttribute"];
+
+        for options in [
+            SerializeOptions::default(),
+            SerializeOptions {
+                raw_attributes: true,
+                time_format: TimeFormat::UnixSeconds,
+                ..Default::default()
+            },
+        ] {
+            let json = serde_json::to_value(ctl.serialize_with(options)).unwrap();
+            check_properties(&json, &ctl_schema);
+            for entry in json["entries"].as_array().unwrap() {
+                check_properties(entry, &subject_schema);
+                for attr in entry["attributes"].as_array().into_iter().flatten() {
+                    check_properties(attr, attribute_schema);
+                }
+            }
+        }
+    }
+}
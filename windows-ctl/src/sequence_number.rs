@@ -0,0 +1,153 @@
+//! CTL sequence numbers.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use der::asn1::{Uint, UintRef};
+use der::{DecodeValue, EncodeValue, FixedTag, Header, Length, Reader, Tag, Writer};
+
+/// A [`CertificateTrustList`](crate::CertificateTrustList)'s sequence number: an
+/// arbitrarily large unsigned integer that increases with each release of a list.
+///
+/// Sequence numbers are ordered numerically, so that two releases of the same list
+/// can be compared for recency the way Windows' AutoUpdate does:
+///
+/// ```
+/// # use windows_ctl::SequenceNumber;
+/// let older = SequenceNumber::new(&[0xff]).unwrap();
+/// let newer = SequenceNumber::new(&[0x01, 0x00]).unwrap();
+/// assert!(newer > older);
+/// ```
+///
+/// With the `serde` feature, it's (de)serialized as a hex string (see [`to_hex`](Self::to_hex)).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SequenceNumber(Uint);
+
+impl SequenceNumber {
+    /// Creates a sequence number from its big-endian bytes.
+    pub fn new(bytes: &[u8]) -> Result<Self, der::Error> {
+        Ok(Self(Uint::new(bytes)?))
+    }
+
+    /// Returns the big-endian bytes of this sequence number, without leading zeros.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    /// Returns this sequence number as a lowercase hex string, as Windows displays it.
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns this sequence number as a `u128`, if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        let bytes = self.as_bytes();
+        (bytes.len() <= 16).then(|| bytes.iter().fold(0, |n, b| (n << 8) | u128::from(*b)))
+    }
+}
+
+impl Ord for SequenceNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Without leading zeros, a longer number is always a larger one.
+        self.as_bytes()
+            .len()
+            .cmp(&other.as_bytes().len())
+            .then_with(|| self.as_bytes().cmp(other.as_bytes()))
+    }
+}
+
+impl PartialOrd for SequenceNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for SequenceNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl From<Uint> for SequenceNumber {
+    fn from(value: Uint) -> Self {
+        Self(value)
+    }
+}
+
+impl TryFrom<UintRef<'_>> for SequenceNumber {
+    type Error = der::Error;
+
+    fn try_from(value: UintRef<'_>) -> Result<Self, Self::Error> {
+        Self::new(value.as_bytes())
+    }
+}
+
+impl FixedTag for SequenceNumber {
+    const TAG: Tag = Tag::Integer;
+}
+
+impl<'a> DecodeValue<'a> for SequenceNumber {
+    fn decode_value<R: Reader<'a>>(reader: &mut R, header: Header) -> der::Result<Self> {
+        Ok(Self(Uint::decode_value(reader, header)?))
+    }
+}
+
+impl EncodeValue for SequenceNumber {
+    fn value_len(&self) -> der::Result<Length> {
+        self.0.value_len()
+    }
+
+    fn encode_value(&self, writer: &mut impl Writer) -> der::Result<()> {
+        self.0.encode_value(writer)
+    }
+}
+
+impl fmt::Display for SequenceNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes()
+            .iter()
+            .try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SequenceNumber {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SequenceNumber {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
+        Self::new(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::{Decode, Encode};
+
+    use super::*;
+
+    #[test]
+    fn test_sequence_number() {
+        let n = SequenceNumber::new(&[0x00, 0x01, 0x02]).unwrap();
+        assert_eq!(n.as_bytes(), [0x01, 0x02]);
+        assert_eq!(n.to_hex(), "0102");
+        assert_eq!(n.to_u128(), Some(0x0102));
+        assert_eq!(SequenceNumber::new(&[0xff; 17]).unwrap().to_u128(), None);
+
+        let der = n.to_der().unwrap();
+        assert_eq!(der, [0x02, 0x02, 0x01, 0x02]);
+        assert_eq!(SequenceNumber::from_der(&der).unwrap(), n);
+
+        let mut sorted = [[0x01, 0x00].as_slice(), &[0xff], &[0x02], &[0x00, 0x03]]
+            .map(|bytes| SequenceNumber::new(bytes).unwrap());
+        sorted.sort();
+        assert_eq!(sorted.map(|n| n.to_hex()), ["02", "03", "ff", "0100"]);
+    }
+}
@@ -0,0 +1,397 @@
+//! [`Serialize`] implementations for CTLs and their entries, and the
+//! [`SerializeOptions`] that control their shape.
+
+use std::time::SystemTime;
+
+use base64::Engine;
+use der::Encode;
+use serde::ser;
+use serde::{Serialize, Serializer};
+
+use crate::{eku, timestamp, CertificateTrustList, SequenceNumber, TrustedSubject};
+
+/// Options controlling how [`CertificateTrustList`]s and [`TrustedSubject`]s are
+/// serialized, via their `serialize_with` methods.
+///
+/// Their [`Serialize`] impls use the default options, which are also the only ones
+/// whose output can be deserialized again.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SerializeOptions {
+    /// Whether to include each entry's attributes, undecoded, as `attributes`.
+    pub raw_attributes: bool,
+    /// How identifiers and other binary values are encoded.
+    pub binary_encoding: BinaryEncoding,
+    /// How EKUs are represented.
+    pub eku_format: EkuFormat,
+    /// How times are represented.
+    pub time_format: TimeFormat,
+}
+
+/// How binary values are encoded as strings.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BinaryEncoding {
+    /// Lowercase hex, as identifiers are usually displayed.
+    #[default]
+    Hex,
+    /// Standard, padded base64.
+    Base64,
+}
+
+impl BinaryEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Hex => hex::encode(bytes),
+            Self::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+}
+
+/// How EKUs are represented.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EkuFormat {
+    /// Dotted OID strings.
+    #[default]
+    Oid,
+    /// Friendly names (see [`eku::name`]), falling back to dotted OID strings for
+    /// unrecognized EKUs.
+    Name,
+}
+
+/// How times (the list's update times, and entries' `FILETIME` attributes) are
+/// represented.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TimeFormat {
+    /// RFC 3339 UTC timestamps, e.g. `2020-01-01T00:00:00Z`, with fractional seconds
+    /// only when there are any.
+    #[default]
+    Rfc3339,
+    /// Whole seconds since the Unix epoch (negative before 1970), rounded down.
+    UnixSeconds,
+}
+
+/// A time to be serialized in a particular [`TimeFormat`].
+pub(crate) struct Timestamp(SystemTime, TimeFormat);
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.1 {
+            TimeFormat::Rfc3339 => serializer.serialize_str(
+                &timestamp::format(self.0)
+                    .ok_or_else(|| ser::Error::custom("time out of RFC 3339's range"))?,
+            ),
+            TimeFormat::UnixSeconds => serializer.serialize_i64(timestamp::unix_seconds(self.0).0),
+        }
+    }
+}
+
+/// A [`CertificateTrustList`] or [`TrustedSubject`] to be serialized with particular
+/// [`SerializeOptions`].
+#[derive(Clone, Copy, Debug)]
+pub struct WithOptions<'a, T> {
+    value: &'a T,
+    options: SerializeOptions,
+}
+
+impl TrustedSubject {
+    /// Returns a serializable view of this entry, shaped by `options`.
+    pub fn serialize_with(&self, options: SerializeOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl CertificateTrustList {
+    /// Returns a serializable view of this list, shaped by `options` (which also
+    /// apply to its entries).
+    pub fn serialize_with(&self, options: SerializeOptions) -> WithOptions<'_, Self> {
+        WithOptions {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl Serialize for TrustedSubject {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_with(SerializeOptions::default())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for WithOptions<'_, TrustedSubject> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (subject, options) = (self.value, self.options);
+
+        let ekus = subject
+            .extended_key_usages()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ser::Error::custom(format!("EKU collection failed: {e}")))?
+            .iter()
+            .map(|oid| match options.eku_format {
+                EkuFormat::Name => eku::name(oid).map_or_else(|| oid.to_string(), Into::into),
+                EkuFormat::Oid => oid.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let friendly_name = subject
+            .friendly_name()
+            .map_err(|e| ser::Error::custom(format!("friendly name decoding failed: {e}")))?;
+
+        let attribute_error = |e| ser::Error::custom(format!("attribute decoding failed: {e}"));
+        let disallowed_at = subject.disallowed_at().map_err(attribute_error)?;
+        let not_before = subject.not_before().map_err(attribute_error)?;
+
+        let attributes = options
+            .raw_attributes
+            .then(|| {
+                subject
+                    .attributes
+                    .iter()
+                    .flat_map(|attrs| attrs.iter())
+                    .map(|attr| {
+                        let values = attr
+                            .values
+                            .iter()
+                            .map(|value| Ok(options.binary_encoding.encode(&value.to_der()?)))
+                            .collect::<Result<Vec<_>, der::Error>>()?;
+                        Ok(RawAttribute {
+                            oid: attr.oid.to_string(),
+                            values,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, der::Error>>()
+            })
+            .transpose()
+            .map_err(ser::Error::custom)?;
+
+        SubjectForm {
+            identifier: options
+                .binary_encoding
+                .encode(subject.identifier.as_bytes()),
+            friendly_name,
+            ekus,
+            disallowed_at: disallowed_at.map(|time| Timestamp(time, options.time_format)),
+            not_before: not_before.map(|time| Timestamp(time, options.time_format)),
+            attributes,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The serialized form of a trusted subject, i.e. an entry in a CTL.
+#[derive(Serialize)]
+#[serde(rename = "TrustedSubject")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub(crate) struct SubjectForm {
+    /// The entry's identifier (usually a certificate fingerprint), as hex (or base64).
+    identifier: String,
+    /// The entry's friendly name, if it has one.
+    #[cfg_attr(feature = "schema", schemars(required))]
+    friendly_name: Option<String>,
+    /// The EKUs the entry is trusted for, as OIDs (or names).
+    ekus: Vec<String>,
+    /// When the entry was distrusted, if it has been.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disallowed_at: Option<Timestamp>,
+    /// When certificates issued under the entry stop being trusted, if they do.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not_before: Option<Timestamp>,
+    /// The entry's undecoded attributes, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attributes: Option<Vec<RawAttribute>>,
+}
+
+/// An undecoded attribute, with its values' DER encodings.
+#[derive(Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+struct RawAttribute {
+    /// The attribute's OID.
+    oid: String,
+    /// Each value's DER, as hex (or base64).
+    values: Vec<String>,
+}
+
+/// Serializes the list's fields, with its entries serialized as [`TrustedSubject`]s are.
+///
+/// The list identifier is serialized as a hex string, the sequence number as a hex
+/// string (as Windows displays it), OIDs as strings, and times as RFC 3339 strings.
+impl Serialize for CertificateTrustList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_with(SerializeOptions::default())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for WithOptions<'_, CertificateTrustList> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (ctl, options) = (self.value, self.options);
+
+        let time =
+            |time: &x509_cert::time::Time| Timestamp(time.to_system_time(), options.time_format);
+
+        CtlForm {
+            version: ctl.version as u8 + 1,
+            usage: ctl
+                .subject_usage
+                .0
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            list_identifier: ctl
+                .list_identifier
+                .as_ref()
+                .map(|id| options.binary_encoding.encode(id.as_bytes())),
+            sequence_number: ctl.sequence_number.as_ref(),
+            this_update: time(&ctl.this_update),
+            next_update: ctl.next_update.as_ref().map(time),
+            subject_algorithm: ctl.subject_algorithm.oid.to_string(),
+            entries: Entries(ctl.trusted_subjects.as_deref().unwrap_or_default(), options),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The serialized form of a certificate trust list.
+#[derive(Serialize)]
+#[serde(rename = "CertificateTrustList")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub(crate) struct CtlForm<'a> {
+    /// The list's version.
+    #[cfg_attr(feature = "schema", schemars(range(min = 1, max = 1)))]
+    version: u8,
+    /// The list's usage OIDs, i.e. what the list is for.
+    usage: Vec<String>,
+    /// The list's identifier, as hex (or base64).
+    #[cfg_attr(feature = "schema", schemars(required))]
+    list_identifier: Option<String>,
+    /// The list's sequence number, as hex.
+    #[cfg_attr(feature = "schema", schemars(required, with = "Option<String>"))]
+    sequence_number: Option<&'a SequenceNumber>,
+    /// When the list was issued.
+    this_update: Timestamp,
+    /// When the next list is expected to be issued.
+    #[cfg_attr(feature = "schema", schemars(required))]
+    next_update: Option<Timestamp>,
+    /// The OID of the digest algorithm that identifies entries.
+    subject_algorithm: String,
+    /// The list's entries.
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<SubjectForm>"))]
+    entries: Entries<'a>,
+}
+
+/// Serializes a list's entries with the given options, without collecting them first.
+struct Entries<'a>(&'a [TrustedSubject], SerializeOptions);
+
+impl Serialize for Entries<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.iter().map(|entry| entry.serialize_with(self.1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::asn1::OctetString;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{testing, SequenceNumber, MS_CERT_PROP_ID_METAEKUS_OID, SHA1_OID};
+
+    #[test]
+    fn test_serialize_ctl() {
+        let mut ctl = testing::sample_ctl();
+        ctl.sequence_number = Some(SequenceNumber::new(&[0x01, 0x02]).unwrap());
+        ctl.list_identifier = Some(OctetString::new([0xab, 0xcd]).unwrap());
+
+        let json = serde_json::to_value(&ctl).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["usage"], json!(["1.3.6.1.4.1.311.10.3.9"]));
+        assert_eq!(json["list_identifier"], "abcd");
+        assert_eq!(json["sequence_number"], "0102");
+        assert_eq!(json["this_update"], "2023-11-14T22:13:20Z");
+        assert_eq!(json["next_update"], serde_json::Value::Null);
+        assert_eq!(json["subject_algorithm"], SHA1_OID.to_string());
+
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["identifier"], "11".repeat(20));
+        assert_eq!(entries[0].get("attributes"), None);
+
+        ctl.trusted_subjects = None;
+        let json = serde_json::to_value(&ctl).unwrap();
+        assert_eq!(json["entries"], json!([]));
+    }
+
+    #[test]
+    fn test_serialize_options() {
+        let mut ctl = testing::sample_ctl();
+        ctl.list_identifier = Some(OctetString::new([0xab, 0xcd]).unwrap());
+        let options = SerializeOptions {
+            raw_attributes: true,
+            binary_encoding: BinaryEncoding::Base64,
+            eku_format: EkuFormat::Name,
+            time_format: TimeFormat::UnixSeconds,
+        };
+
+        let json = serde_json::to_value(ctl.serialize_with(options)).unwrap();
+        assert_eq!(json["list_identifier"], "q80=");
+        assert_eq!(json["this_update"], 1_700_000_000);
+
+        let entry = &json["entries"][0];
+        assert_eq!(entry["identifier"], "ERERERERERERERERERERERERERE=");
+        assert_eq!(
+            entry["ekus"],
+            json!(["Server Authentication", "Code Signing"])
+        );
+
+        let der = ctl.trusted_subjects.as_ref().unwrap()[0]
+            .attributes
+            .as_ref()
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .values
+            .get(0)
+            .unwrap()
+            .to_der()
+            .unwrap();
+        assert_eq!(
+            entry["attributes"],
+            json!([{
+                "oid": MS_CERT_PROP_ID_METAEKUS_OID.to_string(),
+                "values": [options.binary_encoding.encode(&der)],
+            }])
+        );
+        assert_eq!(json["entries"][1]["attributes"], json!([]));
+
+        // Entries serialize the same way on their own as within their list.
+        let subject = &ctl.trusted_subjects.as_ref().unwrap()[0];
+        assert_eq!(
+            serde_json::to_value(subject.serialize_with(options)).unwrap(),
+            *entry
+        );
+        assert_eq!(
+            serde_json::to_value(subject).unwrap(),
+            serde_json::to_value(&ctl).unwrap()["entries"][0]
+        );
+    }
+}
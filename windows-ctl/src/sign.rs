@@ -0,0 +1,188 @@
+//! Signing CTLs into the PKCS#7 `SignedData` that Windows consumes (`.stl` files).
+//!
+//! Lists are signed the way Microsoft signs its own: the `certTrustList` is
+//! encapsulated directly (rather than wrapped in an `OCTET STRING`), and its signer
+//! authenticates `contentType` and `messageDigest` attributes, the latter being the
+//! SHA-256 hash of the list's content octets (its DER, without the outer tag and
+//! length), per [RFC 2315, section 9.3].
+//!
+//! [RFC 2315, section 9.3]: https://www.rfc-editor.org/rfc/rfc2315#section-9.3
+
+use std::error::Error;
+
+use der::asn1::{Any, AnyRef, ObjectIdentifier, OctetStringRef, SetOfVec};
+use der::referenced::OwnedToRef;
+use der::{Decode, Encode, Tag};
+use pkcs7::certificate_choices::CertificateChoices;
+use pkcs7::cms_version::CmsVersion;
+use pkcs7::encapsulated_content_info::EncapsulatedContentInfo;
+use pkcs7::signed_data_content::SignedDataContent;
+use pkcs7::signer_info::{IssuerAndSerialNumber, SignerIdentifier, SignerInfo};
+use pkcs7::ContentInfo;
+use sha2::{Digest, Sha256};
+use spki::{AlgorithmIdentifierOwned, AlgorithmIdentifierRef};
+use x509_cert::attr::Attribute;
+use x509_cert::Certificate;
+
+use crate::signer_info::{CONTENT_TYPE_OID, MESSAGE_DIGEST_OID};
+use crate::{CertificateTrustList, CtlError, MS_CERT_TRUST_LIST_OID, SHA256_OID};
+
+/// A private key that CTLs can be signed with.
+///
+/// RustCrypto signing keys (such as `p256::ecdsa::SigningKey` or
+/// `rsa::pkcs1v15::SigningKey<Sha256>`) can be used by wrapping them in a type that
+/// returns their `DynSignatureAlgorithmIdentifier` and the encoding of their
+/// `Signer::try_sign` output (DER, for ECDSA).
+pub trait CtlSigner {
+    /// Returns the signature algorithm, e.g. `ecdsa-with-SHA256`, as it's recorded
+    /// in the `SignerInfo`.
+    fn signature_algorithm(&self) -> Result<AlgorithmIdentifierOwned, CtlError>;
+
+    /// Signs `message` (hashing it as the signature algorithm requires), returning
+    /// the encoded signature.
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+}
+
+impl<T: CtlSigner + ?Sized> CtlSigner for &T {
+    fn signature_algorithm(&self) -> Result<AlgorithmIdentifierOwned, CtlError> {
+        (**self).signature_algorithm()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        (**self).sign(message)
+    }
+}
+
+/// Signs `ctl` with `signer`, whose certificate is `cert`, returning the DER of the
+/// resulting PKCS#7 `ContentInfo` (i.e. the contents of an `.stl` file).
+///
+/// `chain` holds any further certificates to embed alongside `cert`, such as
+/// intermediates up to (but not necessarily including) the root that Windows is
+/// expected to trust. For Windows to accept the list, `cert` should have the
+/// [`MS_TRUST_LIST_SIGNING`](crate::eku::MS_TRUST_LIST_SIGNING) EKU, and for root
+/// lists, [`MS_ROOT_LIST_SIGNER`](crate::eku::MS_ROOT_LIST_SIGNER) too.
+pub fn sign(
+    ctl: &CertificateTrustList,
+    signer: impl CtlSigner,
+    cert: &Certificate,
+    chain: &[Certificate],
+) -> Result<Vec<u8>, CtlError> {
+    let content = ctl.to_der()?;
+    let digest = Sha256::digest(AnyRef::from_der(&content)?.value());
+
+    let signed_attributes = SetOfVec::try_from(vec![
+        attribute(CONTENT_TYPE_OID, Any::encode_from(&MS_CERT_TRUST_LIST_OID)?)?,
+        attribute(
+            MESSAGE_DIGEST_OID,
+            Any::new(Tag::OctetString, digest.as_slice())?,
+        )?,
+    ])?;
+    let signature = signer
+        .sign(&signed_attributes.to_der()?)
+        .map_err(CtlError::Sign)?;
+    let signature_algorithm = signer.signature_algorithm()?;
+
+    let sha256 = AlgorithmIdentifierRef {
+        oid: SHA256_OID,
+        parameters: Some(AnyRef::NULL),
+    };
+    let signer_info = SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            name: cert.tbs_certificate.issuer.clone(),
+            serial_number: cert.tbs_certificate.serial_number.clone(),
+        }),
+        digest_algorithm: sha256,
+        signed_attributes: Some(signed_attributes),
+        signature_algorithm: signature_algorithm.owned_to_ref(),
+        signature: OctetStringRef::new(&signature)?,
+        unsigned_attributes: None,
+    };
+
+    let mut certificates = vec![cert.clone()];
+    for cert in chain {
+        if !certificates.contains(cert) {
+            certificates.push(cert.clone());
+        }
+    }
+
+    let signed_data = SignedDataContent {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::try_from(vec![sha256])?,
+        encap_content_info: EncapsulatedContentInfo {
+            e_content_type: MS_CERT_TRUST_LIST_OID,
+            e_content: Some(AnyRef::try_from(content.as_slice())?),
+        },
+        certificates: Some(SetOfVec::try_from(
+            certificates
+                .into_iter()
+                .map(CertificateChoices::Certificate)
+                .collect::<Vec<_>>(),
+        )?),
+        crls: None,
+        signer_infos: SetOfVec::try_from(vec![signer_info])?,
+    };
+
+    Ok(ContentInfo::SignedData(signed_data).to_der()?)
+}
+
+/// Builds a single-valued attribute.
+fn attribute(oid: ObjectIdentifier, value: Any) -> Result<Attribute, CtlError> {
+    Ok(Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![value])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use der::DecodePem;
+    use ring::signature::{self, KeyPair};
+
+    use super::*;
+    use crate::{testing, CtlMessage};
+
+    #[test]
+    fn test_sign() {
+        let cert = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let root = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let signer = testing::TestSigner::new();
+        let ctl = testing::sample_ctl();
+
+        let der = sign(&ctl, &signer, &cert, &[root.clone(), cert.clone()]).unwrap();
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert_eq!(message.ctl(), &ctl);
+        assert_eq!(message.signer_certificates(), [cert]);
+        assert_eq!(message.signed_data().certificates.unwrap().len(), 2);
+        assert!(message.certificates().contains(&root));
+
+        // The signature covers the signed attributes, whose digest covers the list.
+        let signer_infos = message.signer_infos();
+        let signer_info = signer_infos.get(0).unwrap();
+        let attributes = signer_info.signed_attributes.as_ref().unwrap();
+        let digest = attributes
+            .iter()
+            .find(|attr| attr.oid == MESSAGE_DIGEST_OID)
+            .unwrap()
+            .values
+            .get(0)
+            .unwrap()
+            .decode_as::<OctetStringRef>()
+            .unwrap();
+        let content = AnyRef::from_der(message.raw().content()).unwrap();
+        assert_eq!(
+            digest.as_bytes(),
+            Sha256::digest(content.value()).as_slice()
+        );
+
+        signature::UnparsedPublicKey::new(
+            &signature::ECDSA_P256_SHA256_ASN1,
+            signer.key_pair().public_key().as_ref(),
+        )
+        .verify(
+            &attributes.to_der().unwrap(),
+            signer_info.signature.as_bytes(),
+        )
+        .unwrap();
+    }
+}
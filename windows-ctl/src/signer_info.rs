@@ -0,0 +1,419 @@
+//! Metadata about who signed a [`CtlMessage`], and how.
+
+use std::time::SystemTime;
+
+use der::asn1::{Any, AnyRef, ObjectIdentifier, SetOfVec};
+use der::referenced::RefToOwned;
+use der::{Decode, Encode, Reader, SliceReader, Tag, TagNumber};
+use pkcs7::signer_info::{SignerIdentifier, SignerInfo};
+use spki::AlgorithmIdentifierOwned;
+use x509_cert::attr::Attribute;
+use x509_cert::time::Time;
+use x509_cert::Certificate;
+
+#[cfg(feature = "digest")]
+use der::asn1::OctetStringRef;
+
+use crate::{CtlError, SHA1_OID, SHA256_OID};
+#[cfg(feature = "digest")]
+use crate::{CtlMessage, MS_CERT_TRUST_LIST_OID};
+
+/// The PKCS#9 `contentType` attribute.
+pub const CONTENT_TYPE_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+
+/// The PKCS#9 `messageDigest` attribute.
+pub const MESSAGE_DIGEST_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+
+/// The PKCS#9 `signingTime` attribute.
+pub const SIGNING_TIME_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.5");
+
+/// The tag that a `SignerInfo`'s signed attributes are encoded with.
+const SIGNED_ATTRIBUTES_TAG: Tag = Tag::ContextSpecific {
+    constructed: true,
+    number: TagNumber::N0,
+};
+
+/// The MD5 digest algorithm.
+pub(crate) const MD5_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.2.5");
+
+/// The SHA-384 digest algorithm.
+pub(crate) const SHA384_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+
+/// The SHA-512 digest algorithm.
+pub(crate) const SHA512_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+/// RSA keys, which PKCS#7 also uses as a signature algorithm, leaving the hash to the
+/// digest algorithm.
+pub(crate) const RSA_ENCRYPTION_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// RSA PKCS#1 v1.5 signatures with MD5.
+pub(crate) const MD5_WITH_RSA_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.4");
+
+/// RSA PKCS#1 v1.5 signatures with SHA-1.
+pub(crate) const SHA1_WITH_RSA_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.5");
+
+/// RSASSA-PSS signatures, whose hash is given by their parameters.
+pub(crate) const RSASSA_PSS_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.10");
+
+/// RSA PKCS#1 v1.5 signatures with SHA-256.
+pub(crate) const SHA256_WITH_RSA_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// RSA PKCS#1 v1.5 signatures with SHA-384.
+pub(crate) const SHA384_WITH_RSA_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+
+/// RSA PKCS#1 v1.5 signatures with SHA-512.
+pub(crate) const SHA512_WITH_RSA_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+
+/// Elliptic curve keys, which PKCS#7 also uses as a signature algorithm (ECDSA),
+/// leaving the hash to the digest algorithm.
+pub(crate) const EC_PUBLIC_KEY_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+
+/// ECDSA signatures with SHA-1.
+pub(crate) const ECDSA_WITH_SHA1_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.1");
+
+/// ECDSA signatures with SHA-256.
+pub(crate) const ECDSA_WITH_SHA256_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+
+/// ECDSA signatures with SHA-384.
+pub(crate) const ECDSA_WITH_SHA384_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+
+/// ECDSA signatures with SHA-512.
+pub(crate) const ECDSA_WITH_SHA512_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.4");
+
+/// Digest and signature algorithms that CTLs are commonly signed with, along with
+/// their names.
+const ALGORITHMS: &[(ObjectIdentifier, &str)] = &[
+    (MD5_OID, "MD5"),
+    (SHA1_OID, "SHA-1"),
+    (SHA256_OID, "SHA-256"),
+    (SHA384_OID, "SHA-384"),
+    (SHA512_OID, "SHA-512"),
+    (RSA_ENCRYPTION_OID, "RSA"),
+    (MD5_WITH_RSA_OID, "MD5 with RSA"),
+    (SHA1_WITH_RSA_OID, "SHA-1 with RSA"),
+    (RSASSA_PSS_OID, "RSASSA-PSS"),
+    (SHA256_WITH_RSA_OID, "SHA-256 with RSA"),
+    (SHA384_WITH_RSA_OID, "SHA-384 with RSA"),
+    (SHA512_WITH_RSA_OID, "SHA-512 with RSA"),
+    (EC_PUBLIC_KEY_OID, "ECDSA"),
+    (ECDSA_WITH_SHA1_OID, "ECDSA with SHA-1"),
+    (ECDSA_WITH_SHA256_OID, "ECDSA with SHA-256"),
+    (ECDSA_WITH_SHA384_OID, "ECDSA with SHA-384"),
+    (ECDSA_WITH_SHA512_OID, "ECDSA with SHA-512"),
+];
+
+/// Returns the name of the given digest or signature algorithm (e.g. `SHA-256 with
+/// RSA`), if it's one that CTLs are commonly signed with.
+pub fn algorithm_name(oid: &ObjectIdentifier) -> Option<&'static str> {
+    ALGORITHMS
+        .iter()
+        .find(|(known, _)| known == oid)
+        .map(|(_, name)| *name)
+}
+
+/// One of a [`CtlMessage`](crate::CtlMessage)'s signers, as described by its
+/// `SignerInfo`: who signed, with which algorithms, and which attributes the
+/// signature covers.
+///
+/// Nothing here has been verified: this only reports what the message claims.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SignerMetadata {
+    /// Identifies the signer's certificate, by issuer and serial number or by subject
+    /// key identifier.
+    pub sid: SignerIdentifier,
+    /// The algorithm that the content (and signed attributes) were digested with.
+    pub digest_algorithm: AlgorithmIdentifierOwned,
+    /// The algorithm that the digest was signed with.
+    pub signature_algorithm: AlgorithmIdentifierOwned,
+    /// The signature itself, over the signed attributes (or over the content, if
+    /// there are none).
+    pub signature: Vec<u8>,
+    /// The attributes covered by the signature, such as `contentType`,
+    /// `messageDigest`, and `signingTime`.
+    pub signed_attributes: Vec<Attribute>,
+    /// The attributes that aren't covered by the signature, such as countersignatures.
+    pub unsigned_attributes: Vec<Attribute>,
+    /// The signer's certificate, if it's embedded in the message.
+    pub certificate: Option<Certificate>,
+    /// The signed attributes as the signature covers them, if there are any: as they
+    /// were encoded, but tagged as a `SET OF` rather than `[0] IMPLICIT`.
+    pub(crate) signed_attributes_der: Option<Vec<u8>>,
+}
+
+impl SignerMetadata {
+    /// Collects the metadata in the given DER-encoded `SignerInfo`, looking up the
+    /// signer's certificate among `certificates`.
+    ///
+    /// Decoding sorts the signed attributes (they're a `SET OF`), but they're signed
+    /// in the order they were encoded in, so their encoding is kept as well.
+    pub(crate) fn from_der(der: &[u8], certificates: &[Certificate]) -> Result<Self, CtlError> {
+        let info = SignerInfo::from_der(der)?;
+
+        // SignerInfo ::= SEQUENCE { version, sid, digestAlgorithm,
+        //     signedAttrs [0] IMPLICIT SignedAttributes OPTIONAL, ... }
+        let mut fields = SliceReader::new(AnyRef::from_der(der)?.value())?;
+        for _ in 0..3 {
+            fields.tlv_bytes()?;
+        }
+        let signed_attributes_der = match fields.peek_tag()? {
+            SIGNED_ATTRIBUTES_TAG => {
+                let mut attrs = fields.tlv_bytes()?.to_vec();
+                attrs[0] = Tag::Set.into();
+                Some(attrs)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            sid: info.sid.clone(),
+            digest_algorithm: info.digest_algorithm.ref_to_owned(),
+            signature_algorithm: info.signature_algorithm.ref_to_owned(),
+            signature: info.signature.as_bytes().to_vec(),
+            signed_attributes: attributes(info.signed_attributes.as_ref()),
+            unsigned_attributes: attributes(info.unsigned_attributes.as_ref()),
+            certificate: certificates
+                .iter()
+                .find(|cert| crate::envelope::identifies(&info.sid, cert))
+                .cloned(),
+            signed_attributes_der,
+        })
+    }
+
+    /// Returns the name of the digest algorithm, if it's a well-known one.
+    pub fn digest_algorithm_name(&self) -> Option<&'static str> {
+        algorithm_name(&self.digest_algorithm.oid)
+    }
+
+    /// Returns the name of the signature algorithm, if it's a well-known one.
+    pub fn signature_algorithm_name(&self) -> Option<&'static str> {
+        algorithm_name(&self.signature_algorithm.oid)
+    }
+
+    /// Returns the signed attribute with the given OID, if any.
+    pub fn signed_attribute(&self, oid: &ObjectIdentifier) -> Option<&Attribute> {
+        self.signed_attributes.iter().find(|attr| attr.oid == *oid)
+    }
+
+    /// Returns the time the signer claims to have signed at (its `signingTime`
+    /// attribute), if it says.
+    pub fn signing_time(&self) -> Result<Option<SystemTime>, CtlError> {
+        let Some(attr) = self.signed_attribute(&SIGNING_TIME_OID) else {
+            return Ok(None);
+        };
+        let malformed = |reason| CtlError::Attribute {
+            oid: SIGNING_TIME_OID,
+            reason,
+        };
+
+        if attr.values.len() != 1 {
+            return Err(malformed("expected exactly one value"));
+        }
+        let value = attr.values.get(0).expect("checked length");
+        let time = Time::from_der(&value.to_der()?)
+            .map_err(|_| malformed("expected a UTCTime or GeneralizedTime"))?;
+        Ok(Some(time.to_system_time()))
+    }
+
+    /// Returns the value of the signed attribute with the given OID, which must
+    /// appear exactly once, with exactly one value.
+    pub(crate) fn signed_attribute_value(&self, oid: ObjectIdentifier) -> Result<&Any, CtlError> {
+        let mut matching = self.signed_attributes.iter().filter(|attr| attr.oid == oid);
+        match (matching.next(), matching.next()) {
+            (Some(attr), None) if attr.values.len() == 1 => {
+                Ok(attr.values.get(0).expect("checked length"))
+            }
+            (None, _) => Err(CtlError::SignedAttributes("missing attribute")),
+            _ => Err(CtlError::SignedAttributes("attribute must have one value")),
+        }
+    }
+}
+
+#[cfg(feature = "digest")]
+impl SignerMetadata {
+    /// Checks that this signer's signed attributes describe the list whose content
+    /// octets (see [`RawCtl::content_octets`](crate::RawCtl::content_octets)) are
+    /// `content`: their `contentType` must be a CTL's, and their `messageDigest` must
+    /// be the digest of `content`, per
+    /// [RFC 2315, section 9.3](https://www.rfc-editor.org/rfc/rfc2315#section-9.3).
+    ///
+    /// This needs no certificates or public-key cryptography, but only shows that the
+    /// list is the one the signer claims to have signed: without also verifying the
+    /// signature over the attributes (see the `verify` feature), anyone could have
+    /// written them. Signers without signed attributes sign the list directly, so
+    /// they fail this check.
+    pub fn check_message_digest(&self, content: &[u8]) -> Result<(), CtlError> {
+        self.check_signed_attributes(content, Some(MS_CERT_TRUST_LIST_OID))
+    }
+
+    /// Checks that this signer's `messageDigest` is the digest of `content`, and, if
+    /// a `content_type` is given, that its `contentType` is that.
+    pub(crate) fn check_signed_attributes(
+        &self,
+        content: &[u8],
+        content_type: Option<ObjectIdentifier>,
+    ) -> Result<(), CtlError> {
+        if let Some(expected) = content_type {
+            let content_type = self
+                .signed_attribute_value(CONTENT_TYPE_OID)?
+                .decode_as::<ObjectIdentifier>()
+                .map_err(|_| CtlError::SignedAttributes("malformed content type"))?;
+            if content_type != expected {
+                return Err(CtlError::SignedAttributes("unexpected content type"));
+            }
+        }
+
+        let digest = self
+            .signed_attribute_value(MESSAGE_DIGEST_OID)?
+            .decode_as::<OctetStringRef<'_>>()
+            .map_err(|_| CtlError::SignedAttributes("malformed message digest"))?;
+        if digest.as_bytes() != self::digest(&self.digest_algorithm.oid, content)? {
+            return Err(CtlError::SignedAttributes(
+                "message digest doesn't match content",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "digest")]
+impl CtlMessage {
+    /// Checks that every signer's `messageDigest` matches this message's list, as
+    /// [`SignerMetadata::check_message_digest`] does, failing if there are no signers.
+    ///
+    /// As with that check, this doesn't verify any signatures.
+    pub fn check_message_digests(&self) -> Result<(), CtlError> {
+        let signers = self.signers();
+        if signers.is_empty() {
+            return Err(CtlError::Unsigned);
+        }
+        for signer in &signers {
+            signer.check_message_digest(self.raw().content_octets())?;
+        }
+        Ok(())
+    }
+}
+
+/// Digests `data` with the given digest algorithm.
+#[cfg(feature = "digest")]
+pub(crate) fn digest(algorithm: &ObjectIdentifier, data: &[u8]) -> Result<Vec<u8>, CtlError> {
+    use sha1::Sha1;
+    use sha2::{Digest, Sha256, Sha384, Sha512};
+
+    Ok(match *algorithm {
+        SHA1_OID => Sha1::digest(data).to_vec(),
+        SHA256_OID => Sha256::digest(data).to_vec(),
+        SHA384_OID => Sha384::digest(data).to_vec(),
+        SHA512_OID => Sha512::digest(data).to_vec(),
+        oid => {
+            return Err(CtlError::UnsupportedAlgorithm {
+                kind: "digest",
+                oid,
+            })
+        }
+    })
+}
+
+fn attributes(attributes: Option<&SetOfVec<Attribute>>) -> Vec<Attribute> {
+    attributes.map_or_else(Vec::new, |attrs| attrs.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use der::DecodePem;
+
+    use super::*;
+    use crate::{testing, CtlMessage};
+
+    #[test]
+    fn test_signers() {
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+
+        let der = testing::sign_ctl_with(&ctl, Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let signers = message.signers();
+        assert_eq!(signers.len(), 1);
+
+        let metadata = &signers[0];
+        assert!(matches!(
+            &metadata.sid,
+            SignerIdentifier::IssuerAndSerialNumber(id)
+                if id.serial_number == signer.tbs_certificate.serial_number
+        ));
+        assert_eq!(metadata.digest_algorithm.oid, SHA1_OID);
+        assert_eq!(metadata.digest_algorithm_name(), Some("SHA-1"));
+        assert_eq!(metadata.certificate.as_ref(), Some(&signer));
+        assert_eq!(metadata.signature, [0; 32]);
+        assert!(metadata.unsigned_attributes.is_empty());
+        assert_eq!(
+            metadata.signing_time().unwrap(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800))
+        );
+
+        let der = testing::sign_ctl(&ctl);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(message.signers().is_empty());
+    }
+
+    #[cfg(all(feature = "digest", feature = "sign"))]
+    #[test]
+    fn test_check_message_digest() {
+        let ctl = testing::sample_ctl();
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(&ctl, testing::TestSigner::new(), &signer, &[]).unwrap();
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        message.check_message_digests().unwrap();
+
+        let metadata = &message.signers()[0];
+        let mut content = message.raw().content_octets().to_vec();
+        *content.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            metadata.check_message_digest(&content),
+            Err(CtlError::SignedAttributes(_))
+        ));
+
+        // Only a signingTime is signed.
+        let der = testing::sign_ctl_with(&ctl, Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(matches!(
+            message.check_message_digests(),
+            Err(CtlError::SignedAttributes("missing attribute"))
+        ));
+
+        let der = testing::sign_ctl(&ctl);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(matches!(
+            message.check_message_digests(),
+            Err(CtlError::Unsigned)
+        ));
+    }
+
+    #[test]
+    fn test_algorithm_name() {
+        for (oid, name) in ALGORITHMS {
+            assert_eq!(algorithm_name(oid), Some(*name));
+        }
+        assert_eq!(
+            algorithm_name(&ObjectIdentifier::new_unwrap("1.2.3.4")),
+            None
+        );
+    }
+}
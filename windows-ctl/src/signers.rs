@@ -0,0 +1,134 @@
+//! Identification of the certificates that CTLs are signed with.
+//!
+//! Microsoft has rotated its CTL signing certificates over the years, so knowing which
+//! signer produced a list helps to date (and authenticate) archived copies of it.
+//! Registries are populated by the caller, e.g. with [`KnownSigner::from_certificate`]
+//! on the signer certificates embedded in lists they've obtained from Microsoft.
+
+use std::time::SystemTime;
+
+use der::Encode;
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+use crate::{CtlError, CtlMessage};
+
+/// A certificate that's known to have been used to sign CTLs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KnownSigner {
+    /// A short, human-readable name for this signer.
+    pub name: String,
+    /// The signing certificate's subject, in RFC 4514 form.
+    pub subject: String,
+    /// The SHA-256 hash of the signing certificate's DER-encoded `SubjectPublicKeyInfo`.
+    pub spki_sha256: [u8; 32],
+    /// The start of the signing certificate's validity period.
+    pub not_before: SystemTime,
+    /// The end of the signing certificate's validity period.
+    pub not_after: SystemTime,
+}
+
+impl KnownSigner {
+    /// Describes the given signing certificate.
+    pub fn from_certificate(name: impl Into<String>, cert: &Certificate) -> Result<Self, CtlError> {
+        let tbs_cert = &cert.tbs_certificate;
+        Ok(Self {
+            name: name.into(),
+            subject: tbs_cert.subject.to_string(),
+            spki_sha256: spki_sha256(cert)?,
+            not_before: tbs_cert.validity.not_before.to_system_time(),
+            not_after: tbs_cert.validity.not_after.to_system_time(),
+        })
+    }
+
+    /// Returns whether this signer's certificate was valid at `time`.
+    pub fn valid_at(&self, time: SystemTime) -> bool {
+        self.not_before <= time && time <= self.not_after
+    }
+}
+
+/// Returns the SHA-256 hash of the certificate's `SubjectPublicKeyInfo`.
+fn spki_sha256(cert: &Certificate) -> Result<[u8; 32], CtlError> {
+    let spki = cert.tbs_certificate.subject_public_key_info.to_der()?;
+    Ok(Sha256::digest(spki).into())
+}
+
+/// A collection of [`KnownSigner`]s.
+#[derive(Clone, Debug, Default)]
+pub struct SignerRegistry {
+    signers: Vec<KnownSigner>,
+}
+
+impl SignerRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a signer to this registry.
+    pub fn add(&mut self, signer: KnownSigner) {
+        self.signers.push(signer);
+    }
+
+    /// Returns an iterator over the signers in this registry.
+    pub fn iter(&self) -> impl Iterator<Item = &KnownSigner> {
+        self.signers.iter()
+    }
+
+    /// Returns the known signer whose key matches the given certificate's, if any.
+    pub fn lookup(&self, cert: &Certificate) -> Result<Option<&KnownSigner>, CtlError> {
+        let hash = spki_sha256(cert)?;
+        Ok(self.signers.iter().find(|s| s.spki_sha256 == hash))
+    }
+
+    /// Returns the known signers of the given message, as identified by the signer
+    /// certificates embedded in it.
+    ///
+    /// This doesn't verify the message's signatures.
+    pub fn identify(&self, message: &CtlMessage) -> Result<Vec<&KnownSigner>, CtlError> {
+        let mut signers = vec![];
+        for cert in message.signer_certificates() {
+            if let Some(signer) = self.lookup(&cert)? {
+                signers.push(signer);
+            }
+        }
+        Ok(signers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use der::DecodePem;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_identify() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let signer = KnownSigner::from_certificate("ISRG", &cert).unwrap();
+        assert_eq!(
+            signer.subject,
+            "CN=ISRG Root X1,O=Internet Security Research Group,C=US"
+        );
+        // 2020-01-01T00:00:00Z, and 2040-01-01T00:00:00Z.
+        assert!(signer.valid_at(SystemTime::UNIX_EPOCH + Duration::from_secs(1_577_836_800)));
+        assert!(!signer.valid_at(SystemTime::UNIX_EPOCH + Duration::from_secs(2_208_988_800)));
+
+        let ctl = testing::sample_ctl();
+        let message =
+            CtlMessage::from_der(Cursor::new(testing::sign_ctl_with(&ctl, Some(&cert)))).unwrap();
+
+        let mut registry = SignerRegistry::new();
+        assert!(registry.identify(&message).unwrap().is_empty());
+
+        registry.add(signer.clone());
+        assert_eq!(registry.identify(&message).unwrap(), [&signer]);
+
+        let unsigned = CtlMessage::from_der(Cursor::new(testing::sign_ctl(&ctl))).unwrap();
+        assert!(registry.identify(&unsigned).unwrap().is_empty());
+    }
+}
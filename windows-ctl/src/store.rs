@@ -0,0 +1,425 @@
+//! Writers for the trust store formats that CTL-listed certificates can be exported to.
+//!
+//! Each format implements [`StoreWriter`], so that applications (and third-party
+//! formats) can be driven by the same retrieval loop. [`TrustStore`] implements that
+//! loop's retrieval side: resolving entries into verified certificates. [`StoreBuilder`]
+//! implements the loop itself.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use der::asn1::{Any, SetOfVec};
+use der::{Decode, DecodePem, Encode, EncodePem, Tag, Tagged};
+use pkcs7::certificate_choices::CertificateChoices;
+use pkcs7::cms_version::CmsVersion;
+use pkcs7::encapsulated_content_info::EncapsulatedContentInfo;
+use pkcs7::signed_data_content::SignedDataContent;
+use pkcs7::ContentInfo;
+use sha1::{Digest, Sha1};
+use x509_cert::attr::AttributeTypeAndValue;
+use x509_cert::der::pem::LineEnding;
+use x509_cert::name::Name;
+use x509_cert::Certificate;
+
+use crate::{eku, CtlError, TrustedSubject};
+
+mod audit;
+mod builder;
+mod materialize;
+#[cfg(feature = "rustls")]
+mod root_store;
+#[cfg(feature = "serde")]
+mod snapshot;
+mod storage;
+
+pub use audit::StoreAudit;
+pub use builder::{StoreBuilder, StoreProgress, StoreReport};
+pub use materialize::{
+    CertificateCache, CertificateFetcher, DirCache, EnrichedRoot, NoCache, TrustStore,
+};
+#[cfg(feature = "rustls")]
+pub use root_store::RootCertStoreWriter;
+#[cfg(feature = "serde")]
+pub use snapshot::TrustSnapshot;
+pub use storage::{DirStorage, MemoryStorage, Storage, StorageCache};
+
+/// A sink for certificates retrieved on behalf of CTL entries.
+pub trait StoreWriter {
+    /// Adds `cert`, which was retrieved for `entry`, to the store.
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError>;
+
+    /// Completes the store. Formats that can't be written incrementally do
+    /// their actual writing here.
+    fn finish(&mut self) -> Result<(), CtlError> {
+        Ok(())
+    }
+}
+
+/// Creates a new file at `path`, refusing to overwrite an existing one.
+fn create_new(path: &Path) -> Result<File, CtlError> {
+    Ok(File::options().write(true).create_new(true).open(path)?)
+}
+
+/// Writes certificates as a concatenated PEM bundle.
+pub struct PemWriter<W: Write> {
+    sink: W,
+    annotate: bool,
+}
+
+impl<W: Write> PemWriter<W> {
+    /// Creates a writer that emits bare PEM blocks, e.g. for a `ca-bundle.pem`.
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink,
+            annotate: false,
+        }
+    }
+
+    /// Creates a writer that precedes each PEM block with a human-readable summary
+    /// of the certificate (serial, issuer, subject, and validity) and the purposes
+    /// its entry is trusted for.
+    pub fn annotated(sink: W) -> Self {
+        Self {
+            sink,
+            annotate: true,
+        }
+    }
+}
+
+impl<W: Write> StoreWriter for PemWriter<W> {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        let pem = cert.to_pem(LineEnding::LF)?;
+
+        if self.annotate {
+            let tbs_cert = &cert.tbs_certificate;
+            writeln!(self.sink, "Serial: {}", tbs_cert.serial_number)?;
+            writeln!(self.sink, "Issuer: {}", tbs_cert.issuer)?;
+            writeln!(self.sink, "Subject: {}", tbs_cert.subject)?;
+            writeln!(self.sink, "Not Before: {}", tbs_cert.validity.not_before)?;
+            writeln!(self.sink, "Not After: {}", tbs_cert.validity.not_after)?;
+
+            let purposes = entry
+                .extended_key_usages()
+                .map(|eku| {
+                    eku.map(|eku| eku::name(&eku).map_or_else(|| eku.to_string(), Into::into))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if !purposes.is_empty() {
+                writeln!(self.sink, "Purposes: {}", purposes.join(", "))?;
+            }
+
+            writeln!(self.sink, "{pem}")?;
+        } else {
+            write!(self.sink, "{pem}")?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), CtlError> {
+        Ok(self.sink.flush()?)
+    }
+}
+
+/// Writes each certificate as a DER file named after its CTL identifier
+/// (`<id>.crt`), mirroring the layout of Microsoft's CDN.
+pub struct DerDirWriter {
+    dir: PathBuf,
+}
+
+impl DerDirWriter {
+    /// Creates a writer that populates `dir`, which must already exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl StoreWriter for DerDirWriter {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        let id = entry.cert_id();
+        let mut file = create_new(&self.dir.join(format!("{id}.crt")))?;
+        file.write_all(&cert.to_der()?)?;
+        Ok(())
+    }
+}
+
+/// Writes certificates into an OpenSSL "hashed" directory, as produced by
+/// `c_rehash` or `openssl rehash` and consumed via `SSL_CERT_DIR`.
+pub struct HashedDirWriter {
+    dir: PathBuf,
+    hash_counts: HashMap<u32, usize>,
+}
+
+impl HashedDirWriter {
+    /// Creates a writer that populates `dir`, which must already exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            hash_counts: HashMap::new(),
+        }
+    }
+}
+
+impl StoreWriter for HashedDirWriter {
+    fn write(&mut self, _entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        let hash = openssl_subject_hash(&cert.tbs_certificate.subject)?;
+
+        // c_rehash disambiguates colliding subject hashes with an incrementing suffix.
+        let count = self.hash_counts.entry(hash).or_default();
+        let mut file = create_new(&self.dir.join(format!("{hash:08x}.{count}")))?;
+        *count += 1;
+
+        file.write_all(cert.to_pem(LineEnding::LF)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Writes certificates as a "certs-only" PKCS#7 bundle (i.e. a `.p7b` file).
+pub struct Pkcs7Writer<W: Write> {
+    sink: W,
+    certs: Vec<Certificate>,
+}
+
+impl<W: Write> Pkcs7Writer<W> {
+    /// Creates a writer that emits a DER-encoded PKCS#7 bundle into `sink`.
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink,
+            certs: vec![],
+        }
+    }
+}
+
+impl<W: Write> StoreWriter for Pkcs7Writer<W> {
+    fn write(&mut self, _entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        if !self.certs.contains(cert) {
+            self.certs.push(cert.clone());
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), CtlError> {
+        let certificates = SetOfVec::try_from(
+            self.certs
+                .iter()
+                .cloned()
+                .map(CertificateChoices::Certificate)
+                .collect::<Vec<_>>(),
+        )?;
+
+        let signed_data = SignedDataContent {
+            version: CmsVersion::V1,
+            digest_algorithms: SetOfVec::new(),
+            encap_content_info: EncapsulatedContentInfo {
+                e_content_type: pkcs7::PKCS_7_DATA_OID,
+                e_content: None,
+            },
+            certificates: Some(certificates),
+            crls: None,
+            signer_infos: SetOfVec::new(),
+        };
+
+        self.sink
+            .write_all(&ContentInfo::SignedData(signed_data).to_der()?)?;
+        Ok(self.sink.flush()?)
+    }
+}
+
+/// Reads the certificates in `contents`, which may be in any of the formats that this
+/// module writes: a (possibly annotated) PEM bundle, a DER certificate, or a certs-only
+/// PKCS#7 bundle.
+pub fn read_certificates(contents: &[u8]) -> Result<Vec<Certificate>, CtlError> {
+    const BEGIN: &[u8] = b"-----BEGIN CERTIFICATE-----";
+    const END: &[u8] = b"-----END CERTIFICATE-----";
+
+    if contents.windows(BEGIN.len()).any(|w| w == BEGIN) {
+        // Skip over any annotations between the PEM blocks.
+        let mut certs = vec![];
+        let mut rest = contents;
+        while let Some(start) = rest.windows(BEGIN.len()).position(|w| w == BEGIN) {
+            let Some(len) = rest[start..].windows(END.len()).position(|w| w == END) else {
+                return Err(der::Error::from(der::ErrorKind::Failed).into());
+            };
+            let end = start + len + END.len();
+            certs.push(Certificate::from_pem(&rest[start..end])?);
+            rest = &rest[end..];
+        }
+        return Ok(certs);
+    }
+
+    match ContentInfo::from_der(contents) {
+        Ok(ContentInfo::SignedData(signed_data)) => Ok(signed_data
+            .certificates
+            .iter()
+            .flat_map(|certs| certs.iter())
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(cert) => Some(cert.clone()),
+                _ => None,
+            })
+            .collect()),
+        _ => Ok(vec![Certificate::from_der(contents)?]),
+    }
+}
+
+/// Computes OpenSSL's `X509_NAME_hash` for the given name.
+///
+/// This is the hash that `c_rehash` (and `openssl rehash`) use to name
+/// entries in a hashed certificate directory: the first four bytes (little-endian)
+/// of the SHA-1 digest of the name's canonical encoding.
+pub fn openssl_subject_hash(name: &Name) -> Result<u32, CtlError> {
+    // OpenSSL's canonical encoding is the concatenation of each RDN's SET,
+    // *without* the outer SEQUENCE header.
+    let mut canon = vec![];
+    for rdn in name.0.iter() {
+        let atvs = rdn
+            .0
+            .iter()
+            .map(canonicalize_atv)
+            .collect::<Result<Vec<_>, _>>()?;
+        SetOfVec::try_from(atvs)?.encode_to_vec(&mut canon)?;
+    }
+
+    let digest = Sha1::digest(&canon);
+    Ok(u32::from_le_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+/// Canonicalizes a single attribute value the same way OpenSSL's `asn1_string_canon` does:
+/// string types are converted to UTF-8, trimmed, have internal whitespace collapsed,
+/// and are ASCII-lowercased. Everything else is passed through unchanged.
+fn canonicalize_atv(atv: &AttributeTypeAndValue) -> Result<AttributeTypeAndValue, CtlError> {
+    let value = match atv.value.tag() {
+        Tag::Utf8String | Tag::PrintableString | Tag::Ia5String | Tag::VisibleString => {
+            String::from_utf8(atv.value.value().to_vec())
+                .map_err(|_| atv.value.tag().value_error())?
+        }
+        // OpenSSL treats T61String as Latin-1.
+        Tag::TeletexString => atv.value.value().iter().map(|&b| b as char).collect(),
+        Tag::BmpString => {
+            let units = atv
+                .value
+                .value()
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16(&units).map_err(|_| Tag::BmpString.value_error())?
+        }
+        _ => return Ok(atv.clone()),
+    };
+
+    let canonical = value
+        .split(|c: char| c.is_ascii_whitespace() || c == '\x0b')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_lowercase();
+
+    Ok(AttributeTypeAndValue {
+        oid: atv.oid,
+        value: Any::new(Tag::Utf8String, canonical.into_bytes())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use der::{Decode, DecodePem};
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_openssl_subject_hash() {
+        // Expected values computed with `openssl x509 -noout -subject_hash`.
+        let name =
+            Name::from_str("CN=ISRG Root X1,O=Internet Security Research Group,C=US").unwrap();
+        assert_eq!(openssl_subject_hash(&name).unwrap(), 0x4042bcee);
+
+        // Case and whitespace differences don't affect the hash.
+        let name =
+            Name::from_str("CN=isrg  root x1 ,O=INTERNET SECURITY RESEARCH GROUP,C=us").unwrap();
+        assert_eq!(openssl_subject_hash(&name).unwrap(), 0x4042bcee);
+    }
+
+    #[test]
+    fn test_pem_writer() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let entry = testing::subject(&[0x11; 20], vec![]);
+
+        let mut out = vec![];
+        let mut writer = PemWriter::new(&mut out);
+        writer.write(&entry, &cert).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), testing::ISRG_ROOT_X1);
+
+        let mut out = vec![];
+        let mut writer = PemWriter::annotated(&mut out);
+        writer.write(&entry, &cert).unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("Serial: "));
+        assert!(out.contains("Subject: CN=ISRG Root X1"));
+        assert!(!out.contains("Purposes: "));
+        assert!(out.contains(testing::ISRG_ROOT_X1));
+
+        let entry = &testing::sample_ctl().trusted_subjects.unwrap()[0];
+        let mut out = vec![];
+        let mut writer = PemWriter::annotated(&mut out);
+        writer.write(entry, &cert).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Purposes: Server Authentication, Code Signing\n"));
+    }
+
+    #[test]
+    fn test_read_certificates() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let entry = &testing::sample_ctl().trusted_subjects.unwrap()[0];
+
+        let mut pem = vec![];
+        let mut writer = PemWriter::annotated(&mut pem);
+        writer.write(entry, &cert).unwrap();
+        writer.write(entry, &cert).unwrap();
+        assert_eq!(
+            read_certificates(&pem).unwrap(),
+            [cert.clone(), cert.clone()]
+        );
+
+        let mut p7b = vec![];
+        let mut writer = Pkcs7Writer::new(&mut p7b);
+        writer.write(entry, &cert).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            read_certificates(&p7b).unwrap(),
+            std::slice::from_ref(&cert)
+        );
+
+        let der = cert.to_der().unwrap();
+        assert_eq!(read_certificates(&der).unwrap(), [cert]);
+
+        assert!(read_certificates(b"garbage").is_err());
+        assert!(read_certificates(b"-----BEGIN CERTIFICATE-----\nAAAA").is_err());
+    }
+
+    #[test]
+    fn test_pkcs7_writer() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let entry = testing::subject(&[0x11; 20], vec![]);
+
+        let mut out = vec![];
+        let mut writer = Pkcs7Writer::new(&mut out);
+        writer.write(&entry, &cert).unwrap();
+        writer.write(&entry, &cert).unwrap();
+        writer.finish().unwrap();
+
+        let ContentInfo::SignedData(signed_data) = ContentInfo::from_der(&out).unwrap() else {
+            panic!("expected SignedData");
+        };
+        let certs = signed_data.certificates.unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs.get(0), Some(&CertificateChoices::Certificate(cert)));
+    }
+}
@@ -0,0 +1,177 @@
+//! Checking an existing set of certificates against a CTL.
+
+use std::collections::HashMap;
+
+use der::Encode;
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+use super::TrustStore;
+use crate::{CtlError, TrustedSubject};
+
+/// The outcome of checking a set of certificates against a CTL's entries,
+/// as returned by [`TrustStore::audit`].
+#[derive(Debug, Default)]
+pub struct StoreAudit<'a> {
+    /// Certificates that match the entry they correspond to.
+    pub matched: Vec<(&'a TrustedSubject, Certificate)>,
+
+    /// Certificates that correspond to an entry (by one of their fingerprints),
+    /// but fail to verify against it.
+    pub mismatched: Vec<(&'a TrustedSubject, Certificate, CtlError)>,
+
+    /// Certificates that don't correspond to any entry.
+    pub extra: Vec<Certificate>,
+
+    /// Entries that no certificate corresponds to.
+    pub missing: Vec<&'a TrustedSubject>,
+}
+
+impl StoreAudit<'_> {
+    /// Returns whether every certificate matched an entry. Missing entries aren't
+    /// considered, since stores are commonly built from a subset of a CTL.
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl<'a> TrustStore<'a> {
+    /// Checks previously retrieved certificates (e.g. the contents of a store
+    /// built from this CTL) against the CTL's entries, without fetching anything.
+    ///
+    /// Certificates are matched to entries by their identifier (as computed per the
+    /// list's [`subject_algorithm`](crate::CertificateTrustList::subject_algorithm)) or their
+    /// SHA-256 fingerprint, and then verified as [`TrustStore::resolve`] would.
+    pub fn audit(&self, certs: impl IntoIterator<Item = Certificate>) -> StoreAudit<'a> {
+        let entries = self
+            .ctl
+            .trusted_subjects
+            .iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let mut by_fingerprint = HashMap::new();
+        for entry in &entries {
+            by_fingerprint.insert(entry.identifier.as_bytes().to_vec(), *entry);
+            if let Ok(Some(sha256)) = entry.sha256_fingerprint() {
+                by_fingerprint.insert(sha256.to_vec(), *entry);
+            }
+        }
+
+        let mut audit = StoreAudit::default();
+        let mut seen = Vec::new();
+        for cert in certs {
+            let der = match cert.to_der() {
+                Ok(der) => der,
+                Err(_) => {
+                    audit.extra.push(cert);
+                    continue;
+                }
+            };
+
+            let entry = self
+                .ctl
+                .identify(&der)
+                .and_then(|id| by_fingerprint.get(id.as_bytes()))
+                .or_else(|| by_fingerprint.get(&Sha256::digest(&der)[..]));
+            let Some(entry) = entry.copied() else {
+                audit.extra.push(cert);
+                continue;
+            };
+
+            seen.push(entry.cert_id());
+            match self.verify(entry, &der) {
+                Ok(_) => audit.matched.push((entry, cert)),
+                Err(e) => audit.mismatched.push((entry, cert, e)),
+            }
+        }
+
+        audit.missing = entries
+            .into_iter()
+            .filter(|entry| !seen.iter().any(|id| entry.has_id(id)))
+            .collect();
+
+        audit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::DecodePem;
+    use sha1::Sha1;
+
+    use super::*;
+    use crate::{testing, MS_CERT_PROP_ID_SHA256_HASH_OID};
+
+    #[test]
+    fn test_audit() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+        let sha1 = Sha1::digest(&der);
+
+        let mut other = cert.clone();
+        other.tbs_certificate.serial_number =
+            x509_cert::serial_number::SerialNumber::new(&[1]).unwrap();
+        let other_sha256 = Sha256::digest(other.to_der().unwrap());
+
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects = Some(vec![
+            // Matches `cert` by SHA-1, but lists the wrong SHA-256.
+            testing::subject(
+                &sha1,
+                vec![testing::attribute(
+                    MS_CERT_PROP_ID_SHA256_HASH_OID,
+                    &[0xaa; 32],
+                )],
+            ),
+            // Matches `other` by SHA-256.
+            testing::subject(
+                &[0x22; 20],
+                vec![testing::attribute(
+                    MS_CERT_PROP_ID_SHA256_HASH_OID,
+                    &other_sha256,
+                )],
+            ),
+            testing::subject(&[0x33; 20], vec![]),
+        ]);
+
+        let store = TrustStore::new(&ctl);
+
+        // The SHA-1 identifier is checked whenever the subject algorithm is SHA-1,
+        // so `other` fails to verify against the entry it was matched to.
+        let audit = store.audit([cert.clone(), other.clone()]);
+        assert!(audit.matched.is_empty());
+        assert_eq!(audit.mismatched.len(), 2);
+        assert!(audit.extra.is_empty());
+        assert_eq!(audit.missing, [&ctl.trusted_subjects.as_ref().unwrap()[2]]);
+        assert!(!audit.is_clean());
+
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects = Some(vec![testing::subject(&sha1, vec![])]);
+        let store = TrustStore::new(&ctl);
+
+        let audit = store.audit([cert.clone(), other.clone()]);
+        assert_eq!(audit.matched.len(), 1);
+        assert_eq!(audit.matched[0].1, cert);
+        assert_eq!(audit.extra, [other]);
+        assert!(audit.missing.is_empty());
+        assert!(!audit.is_clean());
+    }
+
+    #[test]
+    fn test_audit_sha256() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let sha256 = Sha256::digest(cert.to_der().unwrap());
+
+        let mut ctl = testing::sample_sha256_ctl();
+        ctl.trusted_subjects = Some(vec![
+            testing::subject(&sha256, vec![]),
+            testing::subject(&[0x22; 32], vec![]),
+        ]);
+
+        let audit = TrustStore::new(&ctl).audit([cert.clone()]);
+        assert_eq!(audit.matched.len(), 1);
+        assert_eq!(audit.matched[0].1, cert);
+        assert_eq!(audit.missing, [&ctl.trusted_subjects.as_ref().unwrap()[1]]);
+    }
+}
@@ -0,0 +1,372 @@
+//! A reusable pipeline for building trust stores from a CTL.
+
+use x509_cert::Certificate;
+
+use super::{CertificateCache, CertificateFetcher, NoCache, StoreWriter, TrustStore};
+use crate::{CertificateTrustList, CtlError, SubjectId, TrustedSubject};
+
+type Filter<'a> = Box<dyn FnMut(&TrustedSubject) -> Result<bool, CtlError> + 'a>;
+type Verifier<'a> = Box<dyn FnMut(&TrustedSubject, &Certificate) -> Result<(), CtlError> + 'a>;
+type Observer<'a> = Box<dyn FnMut(&StoreProgress<'_>) + 'a>;
+
+/// Builds a trust store from a CTL's entries, in stages:
+///
+/// 1. each entry is checked against every [filter](StoreBuilder::filter), and skipped
+///    unless all of them accept it;
+/// 2. the remaining entries are resolved into verified certificates by [`TrustStore::resolve`],
+///    using the [source](StoreBuilder::new) and [cache](StoreBuilder::cache);
+/// 3. each certificate is checked by every additional [verifier](StoreBuilder::verify);
+/// 4. each certificate is written to every [writer](StoreBuilder::writer), which are
+///    then [finished](StoreWriter::finish).
+///
+/// Any error aborts the build, except for retrieval failures when
+/// [`keep_partial`](StoreBuilder::keep_partial) is set.
+///
+/// ```no_run
+/// # use windows_ctl::{eku, CertificateTrustList, CtlError, TrustedSubject};
+/// # use windows_ctl::store::{CertificateFetcher, PemWriter, StoreBuilder};
+/// # fn example(ctl: &CertificateTrustList, fetcher: impl CertificateFetcher) -> Result<(), CtlError> {
+/// let bundle = std::fs::File::create("ca-bundle.pem")?;
+/// let report = StoreBuilder::new(ctl, fetcher)
+///     .filter(|entry: &TrustedSubject| {
+///         Ok(entry
+///             .extended_key_usages()
+///             .collect::<Result<Vec<_>, _>>()?
+///             .contains(&eku::SERVER_AUTH))
+///     })
+///     .writer(PemWriter::new(bundle))
+///     .build()?;
+/// println!("wrote {} certificates", report.written.len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct StoreBuilder<'a> {
+    ctl: &'a CertificateTrustList,
+    source: Box<dyn CertificateFetcher + 'a>,
+    cache: Box<dyn CertificateCache + 'a>,
+    filters: Vec<Filter<'a>>,
+    verifiers: Vec<Verifier<'a>>,
+    writers: Vec<Box<dyn StoreWriter + 'a>>,
+    observers: Vec<Observer<'a>>,
+    keep_partial: bool,
+}
+
+/// The progress of a [`StoreBuilder`], as reported to its [observers](StoreBuilder::observe)
+/// after each entry is resolved.
+#[derive(Debug)]
+pub struct StoreProgress<'c> {
+    /// The entry that was just resolved.
+    pub entry: &'c TrustedSubject,
+    /// The entry's certificate, or `None` if it has no certificate (or, with
+    /// [`keep_partial`](StoreBuilder::keep_partial), if it couldn't be retrieved).
+    pub certificate: Option<&'c Certificate>,
+    /// The number of entries resolved so far, including this one.
+    pub position: usize,
+    /// The number of entries to resolve, i.e. those that passed every filter.
+    pub total: usize,
+}
+
+/// A summary of a completed [`StoreBuilder::build`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StoreReport {
+    /// The entries whose certificates were written.
+    pub written: Vec<SubjectId>,
+    /// The entries that passed every filter, but have no certificate.
+    pub metadata_only: Vec<SubjectId>,
+    /// The number of entries rejected by a filter.
+    pub filtered: usize,
+    /// The entries whose certificates couldn't be retrieved, along with why. Only
+    /// populated with [`keep_partial`](StoreBuilder::keep_partial).
+    pub failed: Vec<(SubjectId, String)>,
+}
+
+impl<'a> StoreBuilder<'a> {
+    /// Creates a pipeline for the entries of `ctl`, whose certificates are retrieved
+    /// from `source`, with no other stages.
+    pub fn new(ctl: &'a CertificateTrustList, source: impl CertificateFetcher + 'a) -> Self {
+        Self {
+            ctl,
+            source: Box::new(source),
+            cache: Box::new(NoCache),
+            filters: vec![],
+            verifiers: vec![],
+            writers: vec![],
+            observers: vec![],
+            keep_partial: false,
+        }
+    }
+
+    /// Sets the cache that's consulted before (and populated after) the source.
+    /// Defaults to [`NoCache`].
+    pub fn cache(mut self, cache: impl CertificateCache + 'a) -> Self {
+        self.cache = Box::new(cache);
+        self
+    }
+
+    /// Adds a filter, which returns whether an entry belongs in the store.
+    pub fn filter(
+        mut self,
+        filter: impl FnMut(&TrustedSubject) -> Result<bool, CtlError> + 'a,
+    ) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Adds a verifier, which is run on each certificate after the CTL's own
+    /// fingerprint checks.
+    pub fn verify(
+        mut self,
+        verifier: impl FnMut(&TrustedSubject, &Certificate) -> Result<(), CtlError> + 'a,
+    ) -> Self {
+        self.verifiers.push(Box::new(verifier));
+        self
+    }
+
+    /// Adds a writer, which every certificate is written to.
+    pub fn writer(mut self, writer: impl StoreWriter + 'a) -> Self {
+        self.writers.push(Box::new(writer));
+        self
+    }
+
+    /// Adds an observer, which is told about each entry as it's resolved
+    /// (e.g. to report progress).
+    pub fn observe(mut self, observer: impl FnMut(&StoreProgress<'_>) + 'a) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Sets whether entries whose certificates can't be retrieved (i.e. whose source
+    /// fails with [`CtlError::Fetch`]) are skipped and recorded in
+    /// [`StoreReport::failed`], rather than aborting the build. Defaults to `false`.
+    ///
+    /// Other errors, including certificates that don't match their entries, still
+    /// abort the build.
+    pub fn keep_partial(mut self, keep_partial: bool) -> Self {
+        self.keep_partial = keep_partial;
+        self
+    }
+
+    /// Runs the pipeline.
+    pub fn build(mut self) -> Result<StoreReport, CtlError> {
+        let store = TrustStore::new(self.ctl);
+        let mut report = StoreReport::default();
+
+        let mut entries = vec![];
+        'entries: for entry in self.ctl.trusted_subjects.iter().flatten() {
+            for filter in &mut self.filters {
+                if !filter(entry)? {
+                    report.filtered += 1;
+                    continue 'entries;
+                }
+            }
+            entries.push(entry);
+        }
+
+        for (idx, entry) in entries.iter().enumerate() {
+            let id = entry.cert_id();
+            let cert = match store.resolve(entry, &mut *self.source, &mut *self.cache) {
+                Ok(Some(cert)) => {
+                    for verifier in &mut self.verifiers {
+                        verifier(entry, &cert)?;
+                    }
+                    for writer in &mut self.writers {
+                        writer.write(entry, &cert)?;
+                    }
+                    report.written.push(id);
+                    Some(cert)
+                }
+                Ok(None) => {
+                    report.metadata_only.push(id);
+                    None
+                }
+                Err(CtlError::Fetch(_, e)) if self.keep_partial => {
+                    warn!("skipping {id}: {e}");
+                    report.failed.push((id, e.to_string()));
+                    None
+                }
+                Err(e) => return Err(e),
+            };
+
+            let progress = StoreProgress {
+                entry,
+                certificate: cert.as_ref(),
+                position: idx + 1,
+                total: entries.len(),
+            };
+            for observer in &mut self.observers {
+                observer(&progress);
+            }
+        }
+
+        for writer in &mut self.writers {
+            writer.finish()?;
+        }
+
+        debug!(
+            "built store: {} written, {} metadata-only, {} failed, {} filtered",
+            report.written.len(),
+            report.metadata_only.len(),
+            report.failed.len(),
+            report.filtered
+        );
+        Ok(report)
+    }
+}
+
+impl<T: CertificateFetcher + ?Sized> CertificateFetcher for &mut T {
+    fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+        (**self).fetch(entry)
+    }
+}
+
+impl<T: CertificateCache + ?Sized> CertificateCache for &mut T {
+    fn get(&mut self, id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError> {
+        (**self).get(id)
+    }
+
+    fn put(&mut self, id: &SubjectId, der: &[u8]) -> Result<(), CtlError> {
+        (**self).put(id, der)
+    }
+}
+
+impl<T: StoreWriter + ?Sized> StoreWriter for &mut T {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        (**self).write(entry, cert)
+    }
+
+    fn finish(&mut self) -> Result<(), CtlError> {
+        (**self).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use der::{DecodePem, Encode};
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::testing;
+
+    /// Serves the same certificate for every entry.
+    struct OneCert(Vec<u8>);
+
+    impl CertificateFetcher for OneCert {
+        fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+            Ok(entry.has_id(Sha1::digest(&self.0)).then(|| self.0.clone()))
+        }
+    }
+
+    /// Fails to retrieve one entry's certificate, and defers to the inner fetcher otherwise.
+    struct Flaky(OneCert, SubjectId);
+
+    impl CertificateFetcher for Flaky {
+        fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+            if entry.has_id(&self.1) {
+                return Err(CtlError::Fetch(entry.cert_id(), "connection reset".into()));
+            }
+            self.0.fetch(entry)
+        }
+    }
+
+    /// Records what it's given.
+    #[derive(Default)]
+    struct Recorder(Vec<SubjectId>, bool);
+
+    impl StoreWriter for Recorder {
+        fn write(&mut self, entry: &TrustedSubject, _cert: &Certificate) -> Result<(), CtlError> {
+            self.0.push(entry.cert_id());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), CtlError> {
+            self.1 = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_store_builder() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+        let id = SubjectId::from(Sha1::digest(&der).to_vec());
+
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects = Some(vec![
+            testing::subject(id.as_bytes(), vec![]),
+            testing::subject(&[0x22; 20], vec![]),
+            testing::subject(&[0x33; 20], vec![]),
+        ]);
+
+        let mut recorder = Recorder::default();
+        let verified = RefCell::new(vec![]);
+        let mut progress = vec![];
+        let report = StoreBuilder::new(&ctl, OneCert(der.clone()))
+            .filter(|entry| Ok(!entry.has_id([0x33; 20])))
+            .verify(|entry, _| {
+                verified.borrow_mut().push(entry.cert_id());
+                Ok(())
+            })
+            .writer(&mut recorder)
+            .observe(|p| progress.push((p.position, p.total, p.certificate.is_some())))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            report,
+            StoreReport {
+                written: vec![id.clone()],
+                metadata_only: vec![SubjectId::from(vec![0x22; 20])],
+                filtered: 1,
+                failed: vec![],
+            }
+        );
+        assert_eq!(recorder.0, std::slice::from_ref(&id));
+        assert!(recorder.1);
+        assert_eq!(verified.into_inner(), [id]);
+        assert_eq!(progress, [(1, 2, true), (2, 2, false)]);
+
+        // A failing verifier aborts the build.
+        let result = StoreBuilder::new(&ctl, OneCert(der))
+            .verify(|_, _| Err(CtlError::MissingSignedData))
+            .build();
+        assert!(matches!(result, Err(CtlError::MissingSignedData)));
+    }
+
+    #[test]
+    fn test_store_builder_keep_partial() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+        let id = SubjectId::from(Sha1::digest(&der).to_vec());
+        let broken = SubjectId::from(vec![0x22; 20]);
+
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects = Some(vec![
+            testing::subject(broken.as_bytes(), vec![]),
+            testing::subject(id.as_bytes(), vec![]),
+        ]);
+
+        let mut source = Flaky(OneCert(der), broken.clone());
+
+        // By default, a retrieval failure aborts the build.
+        let result = StoreBuilder::new(&ctl, &mut source).build();
+        assert!(matches!(result, Err(CtlError::Fetch(..))));
+
+        let mut recorder = Recorder::default();
+        let mut progress = vec![];
+        let report = StoreBuilder::new(&ctl, &mut source)
+            .keep_partial(true)
+            .writer(&mut recorder)
+            .observe(|p| progress.push((p.position, p.certificate.is_some())))
+            .build()
+            .unwrap();
+
+        assert_eq!(report.written, std::slice::from_ref(&id));
+        assert_eq!(report.failed, [(broken, "connection reset".into())]);
+        assert!(report.metadata_only.is_empty());
+        assert_eq!(recorder.0, [id]);
+        assert!(recorder.1);
+        assert_eq!(progress, [(1, false), (2, true)]);
+    }
+}
@@ -0,0 +1,330 @@
+//! Resolving CTL entries into verified certificates.
+
+use std::path::PathBuf;
+
+use der::{Decode, DecodePem, Encode};
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+use super::{DirStorage, StorageCache};
+use crate::{CertificateTrustList, CtlError, SubjectId, TrustedSubject};
+
+/// A source of certificates for CTL entries, e.g. Microsoft's CDN.
+pub trait CertificateFetcher {
+    /// Retrieves the (DER or PEM encoded) certificate for `entry`, or `None` if there
+    /// isn't one (as is the case for some "metadata-only" entries).
+    ///
+    /// Errors should be reported as [`CtlError::Fetch`].
+    fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError>;
+}
+
+/// A cache of previously retrieved (and verified) certificates.
+pub trait CertificateCache {
+    /// Returns the DER of the cached certificate for `id`, if any.
+    fn get(&mut self, id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError>;
+
+    /// Caches the DER-encoded certificate for `id`.
+    fn put(&mut self, id: &SubjectId, der: &[u8]) -> Result<(), CtlError>;
+}
+
+/// A [`CertificateCache`] that caches nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoCache;
+
+impl CertificateCache for NoCache {
+    fn get(&mut self, _id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError> {
+        Ok(None)
+    }
+
+    fn put(&mut self, _id: &SubjectId, _der: &[u8]) -> Result<(), CtlError> {
+        Ok(())
+    }
+}
+
+/// A [`CertificateCache`] backed by a directory of DER certificates, named `<id>.crt`
+/// (the same layout as a [`DerDirWriter`](super::DerDirWriter)'s output).
+///
+/// This is a [`StorageCache`] over a [`DirStorage`]; see [`Storage`](super::Storage)
+/// for caching elsewhere.
+#[derive(Clone, Debug)]
+pub struct DirCache {
+    cache: StorageCache<DirStorage>,
+}
+
+impl DirCache {
+    /// Creates a cache in `dir`, creating the directory if necessary.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, CtlError> {
+        Ok(Self {
+            cache: StorageCache::new(DirStorage::new(dir)?),
+        })
+    }
+}
+
+impl CertificateCache for DirCache {
+    fn get(&mut self, id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError> {
+        self.cache.get(id)
+    }
+
+    fn put(&mut self, id: &SubjectId, der: &[u8]) -> Result<(), CtlError> {
+        self.cache.put(id, der)
+    }
+}
+
+/// A CTL entry, together with the result of resolving its certificate.
+#[derive(Debug)]
+pub struct EnrichedRoot {
+    /// The CTL entry.
+    pub entry: TrustedSubject,
+
+    /// The entry's verified certificate, `None` if the entry has no certificate,
+    /// or the reason it couldn't be resolved.
+    pub certificate: Result<Option<Certificate>, CtlError>,
+}
+
+/// Resolves a CTL's entries into the certificates they refer to.
+///
+/// Certificates are verified against the entry's identifier (when the CTL's
+/// `subjectAlgorithm` is a [`SubjectAlgorithm`](crate::SubjectAlgorithm)) and its
+/// SHA-256 fingerprint (when listed) before being cached or returned.
+#[derive(Clone, Copy, Debug)]
+pub struct TrustStore<'a> {
+    pub(super) ctl: &'a CertificateTrustList,
+}
+
+impl<'a> TrustStore<'a> {
+    /// Creates a `TrustStore` for the entries of `ctl`.
+    pub fn new(ctl: &'a CertificateTrustList) -> Self {
+        Self { ctl }
+    }
+
+    /// Resolves every entry in the CTL.
+    ///
+    /// A failure to resolve one entry doesn't prevent the others from being resolved;
+    /// each entry's outcome is reported in its [`EnrichedRoot`].
+    pub fn materialize(
+        &self,
+        fetcher: &mut (impl CertificateFetcher + ?Sized),
+        cache: &mut (impl CertificateCache + ?Sized),
+    ) -> Vec<EnrichedRoot> {
+        self.ctl
+            .trusted_subjects
+            .iter()
+            .flatten()
+            .map(|entry| EnrichedRoot {
+                entry: entry.clone(),
+                certificate: self.resolve(entry, fetcher, cache),
+            })
+            .collect()
+    }
+
+    /// Resolves a single entry, consulting `cache` before `fetcher`.
+    ///
+    /// Cached certificates that no longer verify are re-fetched.
+    pub fn resolve(
+        &self,
+        entry: &TrustedSubject,
+        fetcher: &mut (impl CertificateFetcher + ?Sized),
+        cache: &mut (impl CertificateCache + ?Sized),
+    ) -> Result<Option<Certificate>, CtlError> {
+        let id = entry.cert_id();
+
+        if let Some(der) = cache.get(&id)? {
+            match self.verify(entry, &der) {
+                Ok(cert) => {
+                    debug!("{id}: using cached certificate");
+                    return Ok(Some(cert));
+                }
+                Err(e) => warn!("{id}: cached certificate doesn't verify, re-fetching: {e}"),
+            }
+        }
+
+        let Some(contents) = fetcher.fetch(entry)? else {
+            debug!("{id}: no certificate available");
+            return Ok(None);
+        };
+
+        let cert = if contents.starts_with(b"-----BEGIN") {
+            Certificate::from_pem(&contents)?
+        } else {
+            Certificate::from_der(&contents)?
+        };
+        let der = cert.to_der()?;
+        if let Err(e) = self.verify(entry, &der) {
+            warn!("{id}: fetched certificate doesn't verify: {e}");
+            return Err(e);
+        }
+        debug!("{id}: fetched and verified certificate");
+
+        cache.put(&id, &der)?;
+        Ok(Some(cert))
+    }
+
+    /// Checks that the DER-encoded certificate is the one `entry` refers to.
+    pub(super) fn verify(
+        &self,
+        entry: &TrustedSubject,
+        der: &[u8],
+    ) -> Result<Certificate, CtlError> {
+        let mismatch = |algorithm, expected: &[u8], actual: &[u8]| CtlError::FingerprintMismatch {
+            subject: entry.cert_id(),
+            algorithm,
+            expected: hex::encode(expected),
+            actual: hex::encode(actual),
+        };
+
+        if let Some(algorithm) = self.ctl.identifier_algorithm() {
+            let actual = algorithm.identify(der);
+            if !entry.has_id(&actual) {
+                return Err(mismatch(
+                    algorithm.name(),
+                    entry.identifier.as_bytes(),
+                    actual.as_bytes(),
+                ));
+            }
+        }
+
+        if let Some(expected) = entry.sha256_fingerprint()? {
+            let actual = Sha256::digest(der);
+            if actual[..] != expected {
+                return Err(mismatch("SHA-256", &expected, &actual));
+            }
+        }
+
+        Ok(Certificate::from_der(der)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use der::asn1::ObjectIdentifier;
+    use sha1::Sha1;
+
+    use super::*;
+    use crate::{testing, MS_CERT_PROP_ID_SHA256_HASH_OID};
+
+    /// Serves certificates from memory, counting requests.
+    struct MapFetcher {
+        certs: HashMap<SubjectId, Vec<u8>>,
+        requests: usize,
+    }
+
+    impl CertificateFetcher for MapFetcher {
+        fn fetch(&mut self, entry: &TrustedSubject) -> Result<Option<Vec<u8>>, CtlError> {
+            self.requests += 1;
+            Ok(self.certs.get(&entry.cert_id()).cloned())
+        }
+    }
+
+    #[derive(Default)]
+    struct MapCache(HashMap<SubjectId, Vec<u8>>);
+
+    impl CertificateCache for MapCache {
+        fn get(&mut self, id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError> {
+            Ok(self.0.get(id).cloned())
+        }
+
+        fn put(&mut self, id: &SubjectId, der: &[u8]) -> Result<(), CtlError> {
+            self.0.insert(id.clone(), der.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_materialize() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+        let sha1 = Sha1::digest(&der).to_vec();
+        let sha256 = Sha256::digest(&der);
+
+        let mut ctl = testing::sample_ctl();
+        ctl.trusted_subjects = Some(vec![
+            // A valid entry, with a matching SHA-256 fingerprint.
+            testing::subject(
+                &sha1,
+                vec![testing::attribute(MS_CERT_PROP_ID_SHA256_HASH_OID, &sha256)],
+            ),
+            // An entry whose certificate isn't available.
+            testing::subject(&[0x22; 20], vec![]),
+            // An entry that's served the wrong certificate.
+            testing::subject(&[0x33; 20], vec![]),
+        ]);
+
+        let mut fetcher = MapFetcher {
+            certs: HashMap::from([
+                (SubjectId::from(sha1.clone()), testing::ISRG_ROOT_X1.into()),
+                (SubjectId::from(vec![0x33; 20]), der.clone()),
+            ]),
+            requests: 0,
+        };
+        let mut cache = MapCache::default();
+
+        let store = TrustStore::new(&ctl);
+        let roots = store.materialize(&mut fetcher, &mut cache);
+        assert_eq!(roots.len(), 3);
+        assert_eq!(roots[0].certificate.as_ref().unwrap(), &Some(cert.clone()));
+        assert!(matches!(roots[1].certificate, Ok(None)));
+        assert!(matches!(
+            roots[2].certificate,
+            Err(CtlError::FingerprintMismatch {
+                algorithm: "SHA-1",
+                ..
+            })
+        ));
+
+        // Only the verified certificate was cached, and it's served from the cache next time.
+        assert_eq!(cache.0.len(), 1);
+        assert_eq!(fetcher.requests, 3);
+        let entry = &ctl.trusted_subjects.as_ref().unwrap()[0];
+        assert_eq!(
+            store.resolve(entry, &mut fetcher, &mut cache).unwrap(),
+            Some(cert)
+        );
+        assert_eq!(fetcher.requests, 3);
+
+        // Lists identified by an unknown algorithm don't have their identifiers checked.
+        ctl.subject_algorithm.oid = ObjectIdentifier::new_unwrap("1.2.840.113549.2.5");
+        let roots = TrustStore::new(&ctl).materialize(&mut fetcher, &mut NoCache);
+        assert!(matches!(roots[2].certificate, Ok(Some(_))));
+    }
+
+    #[test]
+    fn test_materialize_sha256() {
+        let der = Certificate::from_pem(testing::ISRG_ROOT_X1)
+            .unwrap()
+            .to_der()
+            .unwrap();
+        let sha1 = Sha1::digest(&der).to_vec();
+        let sha256 = Sha256::digest(&der).to_vec();
+
+        let mut ctl = testing::sample_sha256_ctl();
+        ctl.trusted_subjects = Some(vec![
+            testing::subject(&sha256, vec![]),
+            // A SHA-1 identifier doesn't match under a SHA-256 list.
+            testing::subject(&sha1, vec![]),
+        ]);
+        assert_eq!(ctl.identify(&der), Some(SubjectId::from(sha256.clone())));
+        assert_eq!(
+            ctl.find(&der).unwrap().cert_id(),
+            SubjectId::from(sha256.clone())
+        );
+
+        let mut fetcher = MapFetcher {
+            certs: HashMap::from([
+                (SubjectId::from(sha256), der.clone()),
+                (SubjectId::from(sha1), der),
+            ]),
+            requests: 0,
+        };
+        let roots = TrustStore::new(&ctl).materialize(&mut fetcher, &mut NoCache);
+        assert!(matches!(roots[0].certificate, Ok(Some(_))));
+        assert!(matches!(
+            roots[1].certificate,
+            Err(CtlError::FingerprintMismatch {
+                algorithm: "SHA-256",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+//! Integration with rustls, for building a [`RootCertStore`] from a CTL.
+
+use der::Encode;
+use rustls::pki_types::CertificateDer;
+use rustls::RootCertStore;
+use x509_cert::Certificate;
+
+use super::StoreWriter;
+use crate::{CtlError, TrustedSubject};
+
+/// Adds certificates to a rustls [`RootCertStore`], as trust anchors.
+///
+/// Note that trust anchors don't carry any of the CTL's per-entry restrictions
+/// (EKUs, distrust dates, etc.), so those should be applied as
+/// [filters](super::StoreBuilder::filter) beforehand.
+pub struct RootCertStoreWriter<'a> {
+    roots: &'a mut RootCertStore,
+}
+
+impl<'a> RootCertStoreWriter<'a> {
+    /// Creates a writer that adds certificates to `roots`.
+    pub fn new(roots: &'a mut RootCertStore) -> Self {
+        Self { roots }
+    }
+}
+
+impl StoreWriter for RootCertStoreWriter<'_> {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        self.roots
+            .add(CertificateDer::from(cert.to_der()?))
+            .map_err(|e| CtlError::TrustAnchor(entry.cert_id(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::DecodePem;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_root_cert_store_writer() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let entry = testing::subject(&[0x11; 20], vec![]);
+
+        let mut roots = RootCertStore::empty();
+        let mut writer = RootCertStoreWriter::new(&mut roots);
+        writer.write(&entry, &cert).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(roots.len(), 1);
+    }
+}
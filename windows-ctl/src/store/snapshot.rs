@@ -0,0 +1,242 @@
+//! Self-contained snapshots of the lists' trust decisions, for distribution.
+
+use std::collections::BTreeMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use der::{Decode, Encode};
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use x509_cert::Certificate;
+
+use super::StoreWriter;
+use crate::{CertificateTrustList, CtlError, SubjectId, TrustedSubject};
+
+/// A versioned snapshot of a root list, the certificates its entries refer to, and
+/// (optionally) the disallowed list.
+///
+/// This lets one service do the fetching and verifying, and distribute the result to
+/// any number of consumers, which only need to deserialize and query it. A snapshot
+/// is filled in as a [`StoreWriter`], e.g. by a [`StoreBuilder`](super::StoreBuilder)
+/// over its root list.
+///
+/// Snapshots serialize compactly: each list as its base64 DER, and each certificate
+/// as its base64 DER, keyed by its entry's identifier. They don't carry the lists'
+/// signatures, so consumers trust whoever distributes them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrustSnapshot {
+    roots: CertificateTrustList,
+    certificates: BTreeMap<SubjectId, Certificate>,
+    disallowed: Option<CertificateTrustList>,
+}
+
+impl TrustSnapshot {
+    /// The version of the serialized form. Snapshots of other versions fail to
+    /// deserialize.
+    pub const VERSION: u32 = 1;
+
+    /// Creates a snapshot of `roots` (and `disallowed`), without any certificates yet.
+    pub fn new(roots: CertificateTrustList, disallowed: Option<CertificateTrustList>) -> Self {
+        Self {
+            roots,
+            certificates: BTreeMap::new(),
+            disallowed,
+        }
+    }
+
+    /// Returns the root list.
+    pub fn roots_list(&self) -> &CertificateTrustList {
+        &self.roots
+    }
+
+    /// Returns the disallowed list, if the snapshot has one.
+    pub fn disallowed_list(&self) -> Option<&CertificateTrustList> {
+        self.disallowed.as_ref()
+    }
+
+    /// Returns each of the root list's entries, with its certificate (if the
+    /// snapshot has it).
+    pub fn roots(&self) -> impl Iterator<Item = (&TrustedSubject, Option<&Certificate>)> {
+        self.roots
+            .trusted_subjects
+            .iter()
+            .flatten()
+            .map(|entry| (entry, self.certificates.get(&entry.cert_id())))
+    }
+
+    /// Returns the certificate for the root list entry identified by `id`, if the
+    /// snapshot has it.
+    pub fn certificate(&self, id: &SubjectId) -> Option<&Certificate> {
+        self.certificates.get(id)
+    }
+
+    /// Returns the root list's entry for the DER-encoded certificate, if it's listed.
+    ///
+    /// See [`CertificateTrustList::find`].
+    pub fn find_root(&self, der: &[u8]) -> Option<&TrustedSubject> {
+        self.roots.find(der)
+    }
+
+    /// Returns the disallowed list's entry for the DER-encoded certificate, if the
+    /// snapshot has a disallowed list and the certificate is listed in it.
+    ///
+    /// See [`CertificateTrustList::find`].
+    pub fn find_disallowed(&self, der: &[u8]) -> Option<&TrustedSubject> {
+        self.disallowed.as_ref()?.find(der)
+    }
+}
+
+/// Adds each certificate to the snapshot. The certificates are expected to have been
+/// verified against their entries, as a [`StoreBuilder`](super::StoreBuilder) does.
+impl StoreWriter for TrustSnapshot {
+    fn write(&mut self, entry: &TrustedSubject, cert: &Certificate) -> Result<(), CtlError> {
+        self.certificates.insert(entry.cert_id(), cert.clone());
+        Ok(())
+    }
+}
+
+/// The serialized form of a [`TrustSnapshot`].
+#[derive(Serialize, Deserialize)]
+struct SnapshotRepr {
+    version: u32,
+    roots: String,
+    certificates: BTreeMap<SubjectId, String>,
+    disallowed: Option<String>,
+}
+
+impl Serialize for TrustSnapshot {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let encode = |der: Result<Vec<u8>, der::Error>| {
+            der.map(|der| STANDARD.encode(der))
+                .map_err(S::Error::custom)
+        };
+
+        SnapshotRepr {
+            version: Self::VERSION,
+            roots: encode(Encode::to_der(&self.roots))?,
+            certificates: self
+                .certificates
+                .iter()
+                .map(|(id, cert)| Ok((id.clone(), encode(cert.to_der())?)))
+                .collect::<Result<_, S::Error>>()?,
+            disallowed: self
+                .disallowed
+                .as_ref()
+                .map(|list| encode(Encode::to_der(list)))
+                .transpose()?,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes a snapshot, checking that each certificate belongs to a root list
+/// entry (and matches its identifier, when the list's identifier algorithm is known).
+impl<'de> Deserialize<'de> for TrustSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SnapshotRepr::deserialize(deserializer)?;
+        if repr.version != Self::VERSION {
+            return Err(D::Error::custom(format!(
+                "unsupported snapshot version: {}",
+                repr.version
+            )));
+        }
+
+        let decode = |b64: &str| STANDARD.decode(b64).map_err(D::Error::custom);
+        let list = |b64: &str| {
+            <CertificateTrustList as Decode>::from_der(&decode(b64)?).map_err(D::Error::custom)
+        };
+
+        let roots = list(&repr.roots)?;
+        let certificates = repr
+            .certificates
+            .into_iter()
+            .map(|(id, b64)| {
+                let der = decode(&b64)?;
+                if !roots
+                    .trusted_subjects
+                    .iter()
+                    .flatten()
+                    .any(|e| e.has_id(&id))
+                    || roots.identify(&der).is_some_and(|actual| actual != id)
+                {
+                    return Err(D::Error::custom(format!(
+                        "certificate doesn't match a root list entry: {id}"
+                    )));
+                }
+                Ok((id, Certificate::from_der(&der).map_err(D::Error::custom)?))
+            })
+            .collect::<Result<_, _>>()?;
+        let disallowed = repr.disallowed.as_deref().map(list).transpose()?;
+
+        Ok(Self {
+            roots,
+            certificates,
+            disallowed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::DecodePem;
+    use serde_json::json;
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_trust_snapshot() {
+        let cert = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = cert.to_der().unwrap();
+        let sha1 = Sha1::digest(&der).to_vec();
+
+        let mut roots = testing::sample_ctl();
+        roots
+            .trusted_subjects
+            .as_mut()
+            .unwrap()
+            .push(testing::subject(&sha1, vec![]));
+        let mut disallowed = testing::sample_ctl();
+        disallowed.trusted_subjects = Some(vec![testing::subject(&sha1, vec![])]);
+
+        let mut snapshot = TrustSnapshot::new(roots.clone(), Some(disallowed));
+        let entry = roots.find(&der).unwrap();
+        snapshot.write(entry, &cert).unwrap();
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["version"], TrustSnapshot::VERSION);
+        assert_eq!(
+            json["certificates"],
+            json!({ hex::encode(&sha1): STANDARD.encode(&der) })
+        );
+
+        let parsed: TrustSnapshot = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed, snapshot);
+        assert_eq!(parsed.find_root(&der), Some(entry));
+        assert!(parsed.find_disallowed(&der).is_some());
+        assert_eq!(parsed.certificate(&entry.cert_id()), Some(&cert));
+        assert_eq!(
+            parsed
+                .roots()
+                .map(|(_, cert)| cert.is_some())
+                .collect::<Vec<_>>(),
+            [false, false, true]
+        );
+
+        // Certificates have to belong to (and match) an entry.
+        for id in [hex::encode([0x11; 20]), hex::encode([0x33; 20])] {
+            let mut bad = json.clone();
+            bad["certificates"] = json!({ id: STANDARD.encode(&der) });
+            assert!(serde_json::from_value::<TrustSnapshot>(bad).is_err());
+        }
+
+        let mut bad = json;
+        bad["version"] = json!(2);
+        assert!(serde_json::from_value::<TrustSnapshot>(bad).is_err());
+    }
+}
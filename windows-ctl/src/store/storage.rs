@@ -0,0 +1,189 @@
+//! Pluggable persistence for state that outlives a single run, such as cached
+//! certificates.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use super::CertificateCache;
+use crate::{CtlError, SubjectId};
+
+/// A key-value store of blobs.
+///
+/// Keys are plain names (e.g. `<id>.crt`), without any path separators, so that
+/// backends are free to map them onto files, object store keys, or anything else.
+/// [`DirStorage`] and [`MemoryStorage`] are provided; other backends (such as object
+/// stores, for stateless deployments) only need to implement this trait.
+pub trait Storage {
+    /// Returns the blob stored under `key`, if any.
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, CtlError>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn store(&mut self, key: &str, value: &[u8]) -> Result<(), CtlError>;
+}
+
+impl<T: Storage + ?Sized> Storage for &mut T {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, CtlError> {
+        (**self).load(key)
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> Result<(), CtlError> {
+        (**self).store(key, value)
+    }
+}
+
+impl<T: Storage + ?Sized> Storage for Box<T> {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, CtlError> {
+        (**self).load(key)
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> Result<(), CtlError> {
+        (**self).store(key, value)
+    }
+}
+
+/// A [`Storage`] backed by a directory, with one file per key.
+#[derive(Clone, Debug)]
+pub struct DirStorage {
+    dir: PathBuf,
+}
+
+impl DirStorage {
+    /// Creates a storage in `dir`, creating the directory if necessary.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, CtlError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, CtlError> {
+        if key.is_empty() || key == "." || key == ".." || key.contains(['/', '\\']) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid storage key: {key:?}"),
+            )
+            .into());
+        }
+        Ok(self.dir.join(key))
+    }
+}
+
+impl Storage for DirStorage {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, CtlError> {
+        match fs::read(self.path(key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> Result<(), CtlError> {
+        Ok(fs::write(self.path(key)?, value)?)
+    }
+}
+
+/// A [`Storage`] that keeps everything in memory, e.g. for containers without
+/// persistent volumes, or for tests.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of blobs stored.
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Returns whether nothing has been stored.
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&mut self, key: &str) -> Result<Option<Vec<u8>>, CtlError> {
+        Ok(self.blobs.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> Result<(), CtlError> {
+        self.blobs.insert(key.into(), value.to_vec());
+        Ok(())
+    }
+}
+
+/// A [`CertificateCache`] on top of any [`Storage`], keeping each certificate's DER
+/// under `<id>.crt`.
+#[derive(Clone, Debug, Default)]
+pub struct StorageCache<S: Storage> {
+    storage: S,
+}
+
+impl<S: Storage> StorageCache<S> {
+    /// Creates a cache on top of `storage`.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: Storage> CertificateCache for StorageCache<S> {
+    fn get(&mut self, id: &SubjectId) -> Result<Option<Vec<u8>>, CtlError> {
+        self.storage.load(&format!("{id}.crt"))
+    }
+
+    fn put(&mut self, id: &SubjectId, der: &[u8]) -> Result<(), CtlError> {
+        self.storage.store(&format!("{id}.crt"), der)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage() {
+        let dir = std::env::temp_dir().join(format!("windows-ctl-storage-{}", std::process::id()));
+        let backends: [Box<dyn Storage>; 2] = [
+            Box::new(DirStorage::new(&dir).unwrap()),
+            Box::new(MemoryStorage::new()),
+        ];
+
+        for mut storage in backends {
+            assert_eq!(storage.load("a.crt").unwrap(), None);
+            storage.store("a.crt", b"first").unwrap();
+            storage.store("a.crt", b"second").unwrap();
+            assert_eq!(
+                storage.load("a.crt").unwrap().as_deref(),
+                Some(&b"second"[..])
+            );
+
+            let mut cache = StorageCache::new(&mut storage);
+            let id = SubjectId::from(vec![0x11; 20]);
+            cache.put(&id, b"der").unwrap();
+            assert_eq!(cache.get(&id).unwrap().as_deref(), Some(&b"der"[..]));
+            assert_eq!(
+                storage.load(&format!("{id}.crt")).unwrap().as_deref(),
+                Some(&b"der"[..])
+            );
+        }
+
+        let mut storage = DirStorage::new(&dir).unwrap();
+        for key in ["", "..", "../a.crt", "a/b.crt"] {
+            assert!(storage.load(key).is_err(), "{key:?}");
+            assert!(storage.store(key, b"").is_err(), "{key:?}");
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+//! Decoding CTLs as they're read, rather than reading them into memory first.
+//!
+//! The bulk of a CTL is its trusted subjects, so those are decoded one at a time,
+//! straight from the stream. Everything else (the envelope, the list's other fields,
+//! and the certificates and signers after it) is small, and is kept and decoded as
+//! usual once the stream is exhausted, with the subjects left out.
+
+use std::io::Read;
+
+use der::asn1::ObjectIdentifier;
+use der::{Decode, Encode, ErrorKind, Header, Length, Tag, TagNumber};
+use pkcs7::PKCS_7_SIGNED_DATA_OID;
+
+use crate::{CertificateTrustList, CtlError, TrustedSubject, MS_CERT_TRUST_LIST_OID};
+
+/// The tag of an `[0] EXPLICIT` field, as PKCS#7 wraps its content in.
+const EXPLICIT_0: Tag = Tag::ContextSpecific {
+    constructed: true,
+    number: TagNumber::N0,
+};
+
+/// Decodes the [`CertificateTrustList`] in a DER-encoded PKCS#7 stream, decoding its
+/// trusted subjects as they're read.
+///
+/// This accepts exactly what [`CertificateTrustList::from_der_slice`] does, and fails
+/// the same way: streams that aren't shaped like a CTL are read in full and handed to
+/// it.
+pub(crate) fn decode<R: Read>(source: R) -> Result<CertificateTrustList, CtlError> {
+    let mut stream = Stream {
+        source,
+        kept: vec![],
+        enclosing: vec![],
+    };
+
+    let Some(length) = stream.find_subjects()? else {
+        stream.read_rest()?;
+        return CertificateTrustList::from_der_slice(&stream.kept);
+    };
+    let subjects = stream.subjects(length)?;
+    stream.read_rest()?;
+
+    let mut ctl = CertificateTrustList::from_der_slice(&stream.without_subjects(length)?)?;
+    ctl.trusted_subjects = Some(subjects);
+    Ok(ctl)
+}
+
+/// A DER stream, along with everything that's been read from it except trusted
+/// subjects.
+struct Stream<R> {
+    source: R,
+    /// The encoding read so far, less the trusted subjects.
+    kept: Vec<u8>,
+    /// The offsets into `kept` of the headers that enclose the trusted subjects,
+    /// outermost first, along with the headers themselves.
+    enclosing: Vec<(usize, Header)>,
+}
+
+impl<R: Read> Stream<R> {
+    /// Reads up to the trusted subjects, returning the length of their `SEQUENCE`.
+    ///
+    /// Returns `None` if the stream isn't a CTL with trusted subjects, having read as
+    /// little of it as possible.
+    fn find_subjects(&mut self) -> Result<Option<Length>, CtlError> {
+        // ContentInfo ::= SEQUENCE { contentType, [0] EXPLICIT SignedData }
+        if !self.enter(Tag::Sequence)? || self.oid()? != Some(PKCS_7_SIGNED_DATA_OID) {
+            return Ok(None);
+        }
+        // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo, ... }
+        if !self.enter(EXPLICIT_0)?
+            || !self.enter(Tag::Sequence)?
+            || self.skip()? != Tag::Integer
+            || self.skip()? != Tag::Set
+        {
+            return Ok(None);
+        }
+        // EncapsulatedContentInfo ::= SEQUENCE { eContentType, [0] EXPLICIT eContent }
+        if !self.enter(Tag::Sequence)? || self.oid()? != Some(MS_CERT_TRUST_LIST_OID) {
+            return Ok(None);
+        }
+        if self.remaining()? == 0 || !self.enter(EXPLICIT_0)? || !self.enter(Tag::Sequence)? {
+            return Ok(None);
+        }
+
+        // The trusted subjects are the list's third SEQUENCE, after its subjectUsage
+        // and subjectAlgorithm.
+        let mut sequences = 0;
+        while self.remaining()? > 0 {
+            let start = self.kept.len();
+            let header = self.header()?;
+            if header.tag == Tag::Sequence {
+                sequences += 1;
+                if sequences == 3 {
+                    self.kept.truncate(start);
+                    return Ok(Some(header.length));
+                }
+            }
+            self.read(header.length)?;
+        }
+        Ok(None)
+    }
+
+    /// Decodes trusted subjects from a `SEQUENCE` of the given length, without
+    /// keeping their encodings.
+    fn subjects(&mut self, length: Length) -> Result<Vec<TrustedSubject>, CtlError> {
+        let mut subjects = vec![];
+        let mut remaining = usize::try_from(length)?;
+        while remaining > 0 {
+            let start = self.kept.len();
+            let header = self.header()?;
+            self.read(header.length)?;
+
+            let len = self.kept.len() - start;
+            remaining = remaining
+                .checked_sub(len)
+                .ok_or(der::Error::from(ErrorKind::Length { tag: Tag::Sequence }))?;
+            subjects.push(TrustedSubject::from_der(&self.kept[start..])?);
+            self.kept.truncate(start);
+        }
+        Ok(subjects)
+    }
+
+    /// Returns what's been kept, with the lengths of the headers that enclosed the
+    /// trusted subjects (whose `SEQUENCE` had the given length) corrected for their
+    /// absence.
+    fn without_subjects(&self, length: Length) -> Result<Vec<u8>, CtlError> {
+        // Shortening a header's length can shorten the header itself, so each header
+        // loses what everything inside it did, innermost first.
+        let mut removed = (Header::new(Tag::Sequence, length)?.encoded_len()? + length)?;
+        let mut headers = vec![];
+        for (offset, header) in self.enclosing.iter().rev() {
+            let shortened = Header::new(header.tag, (header.length - removed)?)?;
+            removed = (removed + (header.encoded_len()? - shortened.encoded_len()?)?)?;
+            headers.push((*offset, header.encoded_len()?, shortened));
+        }
+
+        let mut der = vec![];
+        let mut position = 0;
+        for (offset, len, header) in headers.into_iter().rev() {
+            der.extend_from_slice(&self.kept[position..offset]);
+            header.encode_to_vec(&mut der)?;
+            position = offset + usize::try_from(len)?;
+        }
+        der.extend_from_slice(&self.kept[position..]);
+        Ok(der)
+    }
+
+    /// Reads a header, keeping it if it has the expected tag (and if so, treating its
+    /// value as enclosing the trusted subjects). Returns whether it did.
+    fn enter(&mut self, tag: Tag) -> Result<bool, CtlError> {
+        let start = self.kept.len();
+        let header = self.header()?;
+        if header.tag != tag {
+            return Ok(false);
+        }
+        self.enclosing.push((start, header));
+        Ok(true)
+    }
+
+    /// Returns how much of the innermost enclosing value is left to read.
+    fn remaining(&self) -> Result<usize, CtlError> {
+        let Some((offset, header)) = self.enclosing.last() else {
+            return Ok(0);
+        };
+        let end = offset + usize::try_from((header.encoded_len()? + header.length)?)?;
+        Ok(end.saturating_sub(self.kept.len()))
+    }
+
+    /// Reads an element, returning its tag.
+    fn skip(&mut self) -> Result<Tag, CtlError> {
+        let header = self.header()?;
+        self.read(header.length)?;
+        Ok(header.tag)
+    }
+
+    /// Reads an element, returning it if it's an OID.
+    fn oid(&mut self) -> Result<Option<ObjectIdentifier>, CtlError> {
+        let start = self.kept.len();
+        let header = self.header()?;
+        self.read(header.length)?;
+        Ok(ObjectIdentifier::from_der(&self.kept[start..]).ok())
+    }
+
+    /// Reads a header.
+    fn header(&mut self) -> Result<Header, CtlError> {
+        let start = self.kept.len();
+        // The tag, and the first octet of the length, which says how many follow.
+        self.read_exact(2)?;
+        let first = self.kept[start + 1];
+        if first > 0x80 {
+            self.read_exact(usize::from(first & 0x7f))?;
+        }
+        Ok(Header::from_der(&self.kept[start..])?)
+    }
+
+    /// Reads a value of the given length.
+    fn read(&mut self, length: Length) -> Result<(), CtlError> {
+        self.read_exact(usize::try_from(length)?)
+    }
+
+    /// Reads exactly `len` bytes. The buffer only grows as they arrive, so a bogus
+    /// length can't exhaust memory by itself.
+    fn read_exact(&mut self, len: usize) -> Result<(), CtlError> {
+        let start = self.kept.len();
+        (&mut self.source)
+            .take(len as u64)
+            .read_to_end(&mut self.kept)?;
+        if self.kept.len() - start < len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
+
+    /// Reads the rest of the stream.
+    fn read_rest(&mut self) -> Result<(), CtlError> {
+        self.source.read_to_end(&mut self.kept)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_decode() {
+        let ctl = testing::sample_ctl();
+        assert!(ctl.trusted_subjects.as_ref().is_some_and(|s| s.len() > 1));
+        let der = testing::sign_ctl(&ctl);
+        assert_eq!(decode(&der[..]).unwrap(), ctl);
+
+        // Without any trusted subjects, or with an empty list of them.
+        for trusted_subjects in [None, Some(vec![])] {
+            let ctl = CertificateTrustList {
+                trusted_subjects,
+                ..ctl.clone()
+            };
+            assert_eq!(decode(&testing::sign_ctl(&ctl)[..]).unwrap(), ctl);
+        }
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        let der = testing::sign_ctl(&testing::sample_ctl());
+
+        // Truncated anywhere, including partway through the subjects.
+        for len in [0, 1, der.len() / 2, der.len() - 1] {
+            assert!(decode(&der[..len]).is_err());
+        }
+
+        // Trailing data is rejected, as it is when decoding in memory.
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert!(decode(&trailing[..]).is_err());
+        assert!(CertificateTrustList::from_der_slice(&trailing).is_err());
+
+        // Anything that isn't a CTL fails as it would in memory.
+        assert!(matches!(
+            decode(&b"\x30\x03\x02\x01\x00"[..]),
+            Err(CtlError::Der(_))
+        ));
+    }
+}
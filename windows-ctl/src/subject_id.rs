@@ -0,0 +1,372 @@
+//! Normalized subject identifiers.
+
+use std::fmt;
+use std::str::FromStr;
+
+use der::asn1::ObjectIdentifier;
+
+use crate::{CtlError, TrustedSubject, SHA1_OID, SHA256_OID};
+
+/// A [`TrustedSubject`](crate::TrustedSubject)'s identifier, e.g. a SHA-1 certificate thumbprint.
+///
+/// Parsing from a string is lenient about presentation: upper and lowercase hex are
+/// both accepted, as are colon-, dash-, and whitespace-separated forms (such as
+/// `AB:CD:EF...` or `ab cd ef ...`), since that's how certificate viewers tend to
+/// display fingerprints. With the `serde` feature, it's (de)serialized as a hex string.
+#[doc(alias = "Thumbprint")]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SubjectId(Vec<u8>);
+
+impl SubjectId {
+    /// Returns this identifier's bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns whether this identifier is equal to the given hex string, which is
+    /// interpreted as leniently as [`SubjectId::from_str`] does.
+    ///
+    /// Unlike parsing or formatting, this doesn't allocate.
+    pub fn eq_hex(&self, hex: &str) -> bool {
+        eq_hex(&self.0, hex)
+    }
+}
+
+/// Compares `bytes` against a (lenient) hex string, without allocating.
+pub(crate) fn eq_hex(bytes: &[u8], hex: &str) -> bool {
+    let mut nibbles = hex
+        .chars()
+        .filter(|c| !(c.is_whitespace() || *c == ':' || *c == '-'))
+        .map(|c| c.to_digit(16).map(|d| d as u8));
+
+    for byte in bytes {
+        match (nibbles.next(), nibbles.next()) {
+            (Some(Some(hi)), Some(Some(lo))) if (hi << 4) | lo == *byte => {}
+            _ => return false,
+        }
+    }
+
+    nibbles.next().is_none()
+}
+
+impl PartialEq<[u8]> for SubjectId {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&[u8]> for SubjectId {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<SubjectId> for [u8] {
+    fn eq(&self, other: &SubjectId) -> bool {
+        self == other.0
+    }
+}
+
+impl From<&[u8]> for SubjectId {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for SubjectId {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl AsRef<[u8]> for SubjectId {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl FromStr for SubjectId {
+    type Err = CtlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CtlError::InvalidSubjectId(s.into());
+
+        let nibbles = s
+            .chars()
+            .filter(|c| !(c.is_whitespace() || *c == ':' || *c == '-'))
+            .map(|c| c.to_digit(16).map(|d| d as u8).ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if nibbles.is_empty() || nibbles.len() % 2 != 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self(
+            nibbles
+                .chunks_exact(2)
+                .map(|pair| (pair[0] << 4) | pair[1])
+                .collect(),
+        ))
+    }
+}
+
+impl fmt::Display for SubjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SubjectId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SubjectId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A digest algorithm that a CTL can identify its subjects by, per its
+/// [`subject_algorithm`](crate::CertificateTrustList::subject_algorithm).
+///
+/// Microsoft's lists use SHA-1 certificate thumbprints, but SHA-256-identified
+/// lists have also been seen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SubjectAlgorithm {
+    /// SHA-1 certificate thumbprints.
+    Sha1,
+    /// SHA-256 certificate thumbprints.
+    Sha256,
+}
+
+impl SubjectAlgorithm {
+    /// Every supported algorithm.
+    const ALL: [Self; 2] = [Self::Sha1, Self::Sha256];
+
+    /// Returns the algorithm with the given OID, if it's one that's understood.
+    pub fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        match *oid {
+            SHA1_OID => Some(Self::Sha1),
+            SHA256_OID => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Returns this algorithm's OID.
+    pub fn oid(&self) -> ObjectIdentifier {
+        match self {
+            Self::Sha1 => SHA1_OID,
+            Self::Sha256 => SHA256_OID,
+        }
+    }
+
+    /// Returns the length, in bytes, of identifiers computed with this algorithm.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+        }
+    }
+
+    /// Returns this algorithm's conventional name, e.g. `SHA-1`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+        }
+    }
+
+    /// Computes the identifier of the given DER-encoded certificate.
+    #[cfg(feature = "store")]
+    pub fn identify(&self, der: &[u8]) -> SubjectId {
+        use sha1::{Digest, Sha1};
+        use sha2::Sha256;
+
+        match self {
+            Self::Sha1 => SubjectId(Sha1::digest(der).to_vec()),
+            Self::Sha256 => SubjectId(Sha256::digest(der).to_vec()),
+        }
+    }
+}
+
+/// What a [`TrustedSubject`](crate::TrustedSubject)'s identifier appears to be, as
+/// returned by [`TrustedSubject::identifier_kind`].
+///
+/// Most lists key their entries by certificate thumbprint, per their `subjectAlgorithm`,
+/// but some (such as the disallowed list) also contain other hashes, like SHA-256
+/// hashes of public keys or truncated hashes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum IdentifierKind {
+    /// A digest computed with the list's own subject algorithm.
+    Digest(SubjectAlgorithm),
+    /// A digest whose length matches a different algorithm than the list's.
+    OtherDigest(SubjectAlgorithm),
+    /// An identifier shorter than the list's digests, e.g. a truncated hash.
+    Partial(usize),
+    /// An identifier that doesn't look like any known digest.
+    Unknown(usize),
+}
+
+impl TrustedSubject {
+    /// Classifies this subject's identifier by its length, given its list's
+    /// [`identifier_algorithm`](crate::CertificateTrustList::identifier_algorithm).
+    pub fn identifier_kind(&self, algorithm: Option<SubjectAlgorithm>) -> IdentifierKind {
+        let len = self.identifier.as_bytes().len();
+        match algorithm {
+            Some(algorithm) if algorithm.digest_len() == len => IdentifierKind::Digest(algorithm),
+            _ => match SubjectAlgorithm::ALL.iter().find(|a| a.digest_len() == len) {
+                Some(other) => IdentifierKind::OtherDigest(*other),
+                None if len < algorithm.map_or(0, |a| a.digest_len()) => {
+                    IdentifierKind::Partial(len)
+                }
+                None => IdentifierKind::Unknown(len),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SubjectAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let expected = SubjectId::from(&[0xab, 0xcd, 0xef, 0x01][..]);
+
+        for s in [
+            "abcdef01",
+            "ABCDEF01",
+            "AB:CD:EF:01",
+            "ab cd ef 01",
+            "  AB-CD-EF-01\n",
+        ] {
+            assert_eq!(s.parse::<SubjectId>().unwrap(), expected);
+        }
+
+        assert_eq!(expected.to_string(), "abcdef01");
+    }
+
+    #[test]
+    fn test_eq_hex() {
+        let id = SubjectId::from(&[0xab, 0xcd, 0xef, 0x01][..]);
+
+        for s in ["abcdef01", "ABCDEF01", "AB:CD:EF:01", " ab cd ef 01 "] {
+            assert!(id.eq_hex(s), "{s:?}");
+        }
+        for s in [
+            "",
+            "abcdef",
+            "abcdef0102",
+            "abcdef0",
+            "abcdef0x",
+            "0xabcdef01",
+        ] {
+            assert!(!id.eq_hex(s), "{s:?}");
+        }
+
+        assert!(id == [0xab, 0xcd, 0xef, 0x01][..]);
+        assert!([0xab, 0xcd, 0xef, 0x01][..] == id);
+        assert!(id != [0xab, 0xcd][..]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in ["", "abc", "ab:cd:e", "xyz0", "0xab"] {
+            assert!(matches!(
+                s.parse::<SubjectId>(),
+                Err(CtlError::InvalidSubjectId(_))
+            ));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let id = SubjectId::from(&[0xab, 0xcd][..]);
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, r#""abcd""#);
+        assert_eq!(serde_json::from_str::<SubjectId>(&json).unwrap(), id);
+        assert_eq!(serde_json::from_str::<SubjectId>(r#""AB:CD""#).unwrap(), id);
+        assert!(serde_json::from_str::<SubjectId>(r#""xyz""#).is_err());
+    }
+
+    #[test]
+    fn test_subject_algorithm() {
+        for algorithm in [SubjectAlgorithm::Sha1, SubjectAlgorithm::Sha256] {
+            assert_eq!(
+                SubjectAlgorithm::from_oid(&algorithm.oid()),
+                Some(algorithm)
+            );
+        }
+        assert_eq!(
+            SubjectAlgorithm::from_oid(&ObjectIdentifier::new_unwrap("1.2.840.113549.2.5")),
+            None
+        );
+
+        assert_eq!(SubjectAlgorithm::Sha256.digest_len(), 32);
+        assert_eq!(SubjectAlgorithm::Sha1.to_string(), "SHA-1");
+    }
+
+    #[test]
+    fn test_identifier_kind() {
+        use crate::testing;
+
+        let kind =
+            |len, algorithm| testing::subject(&vec![0; len], vec![]).identifier_kind(algorithm);
+
+        let sha1 = Some(SubjectAlgorithm::Sha1);
+        assert_eq!(
+            kind(20, sha1),
+            IdentifierKind::Digest(SubjectAlgorithm::Sha1)
+        );
+        assert_eq!(
+            kind(32, sha1),
+            IdentifierKind::OtherDigest(SubjectAlgorithm::Sha256)
+        );
+        assert_eq!(kind(16, sha1), IdentifierKind::Partial(16));
+        assert_eq!(kind(48, sha1), IdentifierKind::Unknown(48));
+
+        let sha256 = Some(SubjectAlgorithm::Sha256);
+        assert_eq!(
+            kind(32, sha256),
+            IdentifierKind::Digest(SubjectAlgorithm::Sha256)
+        );
+        assert_eq!(
+            kind(20, sha256),
+            IdentifierKind::OtherDigest(SubjectAlgorithm::Sha1)
+        );
+        assert_eq!(kind(24, sha256), IdentifierKind::Partial(24));
+
+        assert_eq!(
+            kind(20, None),
+            IdentifierKind::OtherDigest(SubjectAlgorithm::Sha1)
+        );
+        assert_eq!(kind(8, None), IdentifierKind::Unknown(8));
+    }
+
+    #[cfg(feature = "store")]
+    #[test]
+    fn test_identify() {
+        assert_eq!(
+            SubjectAlgorithm::Sha1.identify(b"abc").to_string(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            SubjectAlgorithm::Sha256.identify(b"abc").to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Helpers for synthesizing CTLs in tests.
+
+// Not every helper is used under every combination of features.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use der::asn1::{Any, AnyRef, ObjectIdentifier, OctetString, OctetStringRef, SetOfVec, UtcTime};
+use der::{Encode, Tag};
+use pkcs7::certificate_choices::CertificateChoices;
+use pkcs7::cms_version::CmsVersion;
+use pkcs7::encapsulated_content_info::EncapsulatedContentInfo;
+use pkcs7::signed_data_content::SignedDataContent;
+use pkcs7::signer_info::{IssuerAndSerialNumber, SignerIdentifier, SignerInfo};
+use pkcs7::ContentInfo;
+use spki::{AlgorithmIdentifier, AlgorithmIdentifierRef};
+use x509_cert::attr::Attribute;
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::time::Time;
+use x509_cert::Certificate;
+
+use crate::{
+    CertificateTrustList, CtlVersion, TrustedSubject, MS_CERT_PROP_ID_METAEKUS_OID,
+    MS_CERT_TRUST_LIST_OID, SHA1_OID, SHA256_OID, SIGNING_TIME_OID,
+};
+
+/// A test root CA, which issued [`CTL_SIGNER`].
+pub(crate) const CTL_ROOT: &str = include_str!("../testdata/ctl-root.pem");
+
+/// A test CTL signing certificate, whose key is [`TestSigner`]'s.
+pub(crate) const CTL_SIGNER: &str = include_str!("../testdata/ctl-signer.pem");
+
+/// A test time-stamping authority's certificate, issued by [`CTL_ROOT`] to
+/// [`TestSigner`]'s key.
+pub(crate) const TSA: &str = include_str!("../testdata/tsa.pem");
+
+/// A real root certificate, for tests that need one.
+pub(crate) const ISRG_ROOT_X1: &str = include_str!("../testdata/isrg-root-x1.pem");
+
+/// Wraps `value` in an `OCTET STRING`, the way Microsoft encodes property values.
+pub(crate) fn octet_string_value(value: &[u8]) -> Any {
+    Any::new(Tag::OctetString, value).unwrap()
+}
+
+/// Builds an attribute with a single `OCTET STRING` value.
+pub(crate) fn attribute(oid: ObjectIdentifier, value: &[u8]) -> Attribute {
+    Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![octet_string_value(value)]).unwrap(),
+    }
+}
+
+/// Builds a trusted subject with the given identifier and attributes.
+pub(crate) fn subject(identifier: &[u8], attributes: Vec<Attribute>) -> TrustedSubject {
+    TrustedSubject {
+        identifier: OctetString::new(identifier).unwrap(),
+        attributes: (!attributes.is_empty()).then(|| SetOfVec::try_from(attributes).unwrap()),
+    }
+}
+
+/// Builds a small AuthRoot-style CTL with a handful of subjects.
+pub(crate) fn sample_ctl() -> CertificateTrustList {
+    let server_auth = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.1");
+    let code_signing = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+    let metaeku = vec![server_auth, code_signing].to_der().unwrap();
+
+    CertificateTrustList {
+        version: CtlVersion::V1,
+        subject_usage: ExtendedKeyUsage(vec![ObjectIdentifier::new_unwrap(
+            "1.3.6.1.4.1.311.10.3.9",
+        )]),
+        list_identifier: None,
+        sequence_number: None,
+        this_update: Time::UtcTime(
+            UtcTime::from_unix_duration(Duration::from_secs(1_700_000_000)).unwrap(),
+        ),
+        next_update: None,
+        subject_algorithm: AlgorithmIdentifier {
+            oid: SHA1_OID,
+            parameters: None,
+        },
+        trusted_subjects: Some(vec![
+            subject(
+                &[0x11; 20],
+                vec![attribute(MS_CERT_PROP_ID_METAEKUS_OID, &metaeku)],
+            ),
+            subject(&[0x22; 20], vec![]),
+        ]),
+        ctl_extensions: None,
+    }
+}
+
+/// Like [`sample_ctl`], but identifying its subjects by SHA-256 rather than SHA-1.
+pub(crate) fn sample_sha256_ctl() -> CertificateTrustList {
+    let mut ctl = sample_ctl();
+    ctl.subject_algorithm.oid = SHA256_OID;
+    for (entry, byte) in ctl.trusted_subjects.iter_mut().flatten().zip([0x11, 0x22]) {
+        entry.identifier = OctetString::new([byte; 32]).unwrap();
+    }
+    ctl
+}
+
+/// Wraps `ctl` in an (unsigned) PKCS#7 `SignedData`, returning its DER.
+pub(crate) fn sign_ctl(ctl: &CertificateTrustList) -> Vec<u8> {
+    sign_ctl_with(ctl, None)
+}
+
+/// Like [`sign_ctl`], but embeds `signer` and a `SignerInfo` that refers to it.
+///
+/// The `SignerInfo` signs a `signingTime` of 2020-01-01, but the signature itself is
+/// garbage: this is only suitable for testing signer metadata, not signature
+/// verification.
+pub(crate) fn sign_ctl_with(ctl: &CertificateTrustList, signer: Option<&Certificate>) -> Vec<u8> {
+    let content = ctl.to_der().unwrap();
+    let sha1 = AlgorithmIdentifierRef {
+        oid: SHA1_OID,
+        parameters: None,
+    };
+
+    let (certificates, signer_infos) = match signer {
+        Some(cert) => (
+            Some(SetOfVec::try_from(vec![CertificateChoices::Certificate(cert.clone())]).unwrap()),
+            SetOfVec::try_from(vec![SignerInfo {
+                version: CmsVersion::V1,
+                sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                    name: cert.tbs_certificate.issuer.clone(),
+                    serial_number: cert.tbs_certificate.serial_number.clone(),
+                }),
+                digest_algorithm: sha1,
+                signed_attributes: Some(
+                    SetOfVec::try_from(vec![Attribute {
+                        oid: SIGNING_TIME_OID,
+                        values: SetOfVec::try_from(vec![Any::encode_from(
+                            &UtcTime::from_unix_duration(Duration::from_secs(1_577_836_800))
+                                .unwrap(),
+                        )
+                        .unwrap()])
+                        .unwrap(),
+                    }])
+                    .unwrap(),
+                ),
+                signature_algorithm: sha1,
+                signature: OctetStringRef::new(&[0; 32]).unwrap(),
+                unsigned_attributes: None,
+            }])
+            .unwrap(),
+        ),
+        None => (None, SetOfVec::new()),
+    };
+
+    let signed_data = SignedDataContent {
+        version: CmsVersion::V1,
+        digest_algorithms: if signer.is_some() {
+            SetOfVec::try_from(vec![sha1]).unwrap()
+        } else {
+            SetOfVec::new()
+        },
+        encap_content_info: EncapsulatedContentInfo {
+            e_content_type: MS_CERT_TRUST_LIST_OID,
+            e_content: Some(AnyRef::try_from(content.as_slice()).unwrap()),
+        },
+        certificates,
+        crls: None,
+        signer_infos,
+    };
+
+    ContentInfo::SignedData(signed_data).to_der().unwrap()
+}
+
+/// Builds an (unsigned) PKCS#7 `SignedData` for a CTL, with the CTL itself detached.
+pub(crate) fn sign_ctl_detached() -> Vec<u8> {
+    let signed_data = SignedDataContent {
+        version: CmsVersion::V1,
+        digest_algorithms: SetOfVec::new(),
+        encap_content_info: EncapsulatedContentInfo {
+            e_content_type: MS_CERT_TRUST_LIST_OID,
+            e_content: None,
+        },
+        certificates: None,
+        crls: None,
+        signer_infos: SetOfVec::new(),
+    };
+
+    ContentInfo::SignedData(signed_data).to_der().unwrap()
+}
+
+/// Detaches the list from the `ContentInfo` encoded as `der`, returning the DER of the
+/// resulting detached signature and of the list.
+pub(crate) fn detach(der: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    use der::Decode;
+
+    let ContentInfo::SignedData(mut signed_data) = ContentInfo::from_der(der).unwrap() else {
+        panic!("not SignedData");
+    };
+    let content = signed_data
+        .encap_content_info
+        .e_content
+        .take()
+        .unwrap()
+        .to_der()
+        .unwrap();
+
+    (
+        ContentInfo::SignedData(signed_data).to_der().unwrap(),
+        content,
+    )
+}
+
+/// The ECDSA P-256 key that [`CTL_SIGNER`] is issued to.
+#[cfg(feature = "sign")]
+pub(crate) struct TestSigner(ring::signature::EcdsaKeyPair);
+
+#[cfg(feature = "sign")]
+impl TestSigner {
+    pub(crate) fn new() -> Self {
+        let pem = include_str!("../testdata/ctl-signer.key");
+        let (_, pkcs8) = der::pem::decode_vec(pem.as_bytes()).unwrap();
+        Self(
+            ring::signature::EcdsaKeyPair::from_pkcs8(
+                &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+                &pkcs8,
+                &ring::rand::SystemRandom::new(),
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Returns the key pair, e.g. to verify signatures with its public key.
+    pub(crate) fn key_pair(&self) -> &ring::signature::EcdsaKeyPair {
+        &self.0
+    }
+}
+
+#[cfg(feature = "sign")]
+impl crate::sign::CtlSigner for TestSigner {
+    fn signature_algorithm(&self) -> Result<spki::AlgorithmIdentifierOwned, crate::CtlError> {
+        Ok(spki::AlgorithmIdentifierOwned {
+            oid: ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2"),
+            parameters: None,
+        })
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let signature = self
+            .0
+            .sign(&ring::rand::SystemRandom::new(), message)
+            .map_err(|_| "ECDSA signing failed")?;
+        Ok(signature.as_ref().to_vec())
+    }
+}
+
+/// Builds a `SignerInfo` in which `TestSigner`, as `cert`, signs `content` (with
+/// signed attributes of the given `content_type`, if any) at `time`, returning its
+/// DER.
+#[cfg(feature = "sign")]
+pub(crate) fn sign_content(
+    content: &[u8],
+    content_type: Option<ObjectIdentifier>,
+    cert: &Certificate,
+    time: std::time::SystemTime,
+) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    use crate::sign::CtlSigner;
+    use crate::{CONTENT_TYPE_OID, MESSAGE_DIGEST_OID};
+
+    let single = |oid, value| Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![value]).unwrap(),
+    };
+    let mut attributes = vec![
+        single(
+            MESSAGE_DIGEST_OID,
+            octet_string_value(&Sha256::digest(content)),
+        ),
+        single(
+            SIGNING_TIME_OID,
+            Any::encode_from(&UtcTime::from_system_time(time).unwrap()).unwrap(),
+        ),
+    ];
+    if let Some(content_type) = content_type {
+        attributes.push(single(
+            CONTENT_TYPE_OID,
+            Any::encode_from(&content_type).unwrap(),
+        ));
+    }
+    let attributes = SetOfVec::try_from(attributes).unwrap();
+
+    let signer = TestSigner::new();
+    let signature = signer.sign(&attributes.to_der().unwrap()).unwrap();
+    let signature_algorithm = signer.signature_algorithm().unwrap();
+    SignerInfo {
+        version: CmsVersion::V1,
+        sid: SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            name: cert.tbs_certificate.issuer.clone(),
+            serial_number: cert.tbs_certificate.serial_number.clone(),
+        }),
+        digest_algorithm: AlgorithmIdentifierRef {
+            oid: SHA256_OID,
+            parameters: None,
+        },
+        signed_attributes: Some(attributes),
+        signature_algorithm: AlgorithmIdentifierRef {
+            oid: signature_algorithm.oid,
+            parameters: None,
+        },
+        signature: OctetStringRef::new(&signature).unwrap(),
+        unsigned_attributes: None,
+    }
+    .to_der()
+    .unwrap()
+}
+
+/// Builds an RFC 3161 timestamp token, issued by [`TSA`] at `time`, over `signature`.
+#[cfg(feature = "sign")]
+pub(crate) fn timestamp_token(signature: &[u8], time: std::time::SystemTime) -> Vec<u8> {
+    use der::asn1::GeneralizedTime;
+    use der::{Decode, DecodePem, Sequence};
+    use sha2::{Digest, Sha256};
+
+    use crate::countersignature::{MessageImprint, TST_INFO_OID};
+
+    #[derive(Sequence)]
+    struct TstInfo<'a> {
+        version: u8,
+        policy: ObjectIdentifier,
+        message_imprint: MessageImprint<'a>,
+        serial_number: u8,
+        gen_time: GeneralizedTime,
+        nonce: u64,
+    }
+
+    let digest = Sha256::digest(signature);
+    let tst_info = TstInfo {
+        version: 1,
+        policy: ObjectIdentifier::new_unwrap("1.2.3.4"),
+        message_imprint: MessageImprint {
+            hash_algorithm: AlgorithmIdentifierRef {
+                oid: SHA256_OID,
+                parameters: None,
+            },
+            hashed_message: OctetStringRef::new(&digest).unwrap(),
+        },
+        serial_number: 1,
+        gen_time: GeneralizedTime::from_system_time(time).unwrap(),
+        nonce: 0x1234_5678,
+    }
+    .to_der()
+    .unwrap();
+
+    let tsa = Certificate::from_pem(TSA).unwrap();
+    let signer_info = sign_content(&tst_info, Some(TST_INFO_OID), &tsa, time);
+    let sha256 = AlgorithmIdentifierRef {
+        oid: SHA256_OID,
+        parameters: None,
+    };
+    let signed_data = SignedDataContent {
+        version: CmsVersion::V3,
+        digest_algorithms: SetOfVec::try_from(vec![sha256]).unwrap(),
+        encap_content_info: EncapsulatedContentInfo {
+            e_content_type: TST_INFO_OID,
+            e_content: Some(AnyRef::new(Tag::OctetString, &tst_info).unwrap()),
+        },
+        certificates: Some(SetOfVec::try_from(vec![CertificateChoices::Certificate(tsa)]).unwrap()),
+        crls: None,
+        signer_infos: SetOfVec::try_from(vec![SignerInfo::from_der(&signer_info).unwrap()])
+            .unwrap(),
+    };
+
+    ContentInfo::SignedData(signed_data).to_der().unwrap()
+}
+
+/// Adds `attribute` to the unsigned attributes of the (first) signer of the
+/// `ContentInfo` encoded as `der`, returning the new DER.
+pub(crate) fn add_unsigned_attribute(der: &[u8], attribute: Attribute) -> Vec<u8> {
+    use der::Decode;
+
+    let ContentInfo::SignedData(mut signed_data) = ContentInfo::from_der(der).unwrap() else {
+        panic!("not SignedData");
+    };
+    let mut signer_infos = signed_data.signer_infos.into_vec();
+    let unsigned = &mut signer_infos[0].unsigned_attributes;
+    let mut attributes = unsigned.take().map_or_else(Vec::new, SetOfVec::into_vec);
+    attributes.push(attribute);
+    *unsigned = Some(SetOfVec::try_from(attributes).unwrap());
+    signed_data.signer_infos = SetOfVec::try_from(signer_infos).unwrap();
+
+    ContentInfo::SignedData(signed_data).to_der().unwrap()
+}
+
+/// Signs [`sample_ctl`] with [`TestSigner`], then timestamps its signature at `time`:
+/// with an RFC 3161 token from [`TSA`], and with a countersignature by
+/// `countersigner` (using `TestSigner`'s key), whose certificate is embedded.
+#[cfg(feature = "sign")]
+pub(crate) fn timestamped_ctl(time: std::time::SystemTime, countersigner: &Certificate) -> Vec<u8> {
+    use der::{Decode, DecodePem};
+
+    let signer = Certificate::from_pem(CTL_SIGNER).unwrap();
+    let der = crate::sign::sign(
+        &sample_ctl(),
+        TestSigner::new(),
+        &signer,
+        std::slice::from_ref(countersigner),
+    )
+    .unwrap();
+    let message = crate::CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+    let signature = message.signers()[0].signature.clone();
+
+    let single = |oid, value: Vec<u8>| Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![Any::from_der(&value).unwrap()]).unwrap(),
+    };
+    let der = add_unsigned_attribute(
+        &der,
+        single(
+            crate::TIMESTAMP_TOKEN_OID,
+            timestamp_token(&signature, time),
+        ),
+    );
+    add_unsigned_attribute(
+        &der,
+        single(
+            crate::COUNTERSIGNATURE_OID,
+            sign_content(&signature, None, countersigner, time),
+        ),
+    )
+}
+
+/// Rewrites the PKCS#7 DER of a message signed by [`TestSigner`] so that its signer's
+/// signed attributes are encoded (and signed) in reverse of their sorted order, as a
+/// non-DER encoder might have left them.
+#[cfg(feature = "sign")]
+pub(crate) fn unsort_signed_attributes(der: &[u8]) -> Vec<u8> {
+    use der::{Decode, Header, Length, Reader, SliceReader, TagNumber};
+
+    use crate::sign::CtlSigner;
+
+    /// Returns the encodings of the elements of the constructed value in `der`.
+    fn elements(der: &[u8]) -> Vec<&[u8]> {
+        let mut reader = SliceReader::new(AnyRef::from_der(der).unwrap().value()).unwrap();
+        let mut elements = vec![];
+        while !reader.is_finished() {
+            elements.push(reader.tlv_bytes().unwrap());
+        }
+        elements
+    }
+    fn tlv(tag: Tag, elements: &[&[u8]]) -> Vec<u8> {
+        let value = elements.concat();
+        let length = Length::try_from(value.len()).unwrap();
+        let mut tlv = Header::new(tag, length).unwrap().to_der().unwrap();
+        tlv.extend_from_slice(&value);
+        tlv
+    }
+    let explicit_0 = Tag::ContextSpecific {
+        constructed: true,
+        number: TagNumber::N0,
+    };
+
+    // ContentInfo { contentType, [0] SignedData { ..., signerInfos } }
+    let content_info = elements(der);
+    let signed_data = elements(elements(content_info[1])[0]);
+    let (signer_infos, rest) = signed_data.split_last().unwrap();
+    // SignerInfo { version, sid, digestAlgorithm, [0] signedAttrs, signatureAlgorithm,
+    //     signature, ... }
+    let signer_info = elements(elements(signer_infos)[0]);
+    let mut attributes = elements(signer_info[3]);
+    attributes.reverse();
+
+    let signature = TestSigner::new().sign(&tlv(Tag::Set, &attributes)).unwrap();
+    let signer_info = tlv(
+        Tag::Sequence,
+        &[
+            &signer_info[..3],
+            &[
+                &tlv(explicit_0, &attributes),
+                signer_info[4],
+                &tlv(Tag::OctetString, &[&signature]),
+            ],
+            &signer_info[6..],
+        ]
+        .concat(),
+    );
+    let signed_data = tlv(
+        Tag::Sequence,
+        &[rest, &[&tlv(Tag::Set, &[&signer_info])]].concat(),
+    );
+    tlv(
+        Tag::Sequence,
+        &[content_info[0], &tlv(explicit_0, &[&signed_data])],
+    )
+}
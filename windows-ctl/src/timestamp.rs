@@ -0,0 +1,188 @@
+//! RFC 3339 formatting and parsing of [`SystemTime`]s, for serialization.
+//!
+//! `der::DateTime` only covers 1970 through 9999, but `FILETIME`s go back to 1601, so
+//! this converts between Unix time and the (proleptic Gregorian) calendar itself.
+
+use std::time::{Duration, SystemTime};
+
+/// Returns `time` as seconds since the Unix epoch, and the nanoseconds past that second.
+pub(crate) fn unix_seconds(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(before) => {
+            let before = before.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+            }
+        }
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp (e.g. `2020-01-01T00:00:00Z`), with
+/// fractional seconds only if it has any. Returns `None` for times outside of the
+/// years 0 through 9999, which RFC 3339 can't represent.
+pub(crate) fn format(time: SystemTime) -> Option<String> {
+    let (secs, nanos) = unix_seconds(time);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    let secs_of_day = secs.rem_euclid(86_400);
+    let mut formatted = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    );
+    if nanos != 0 {
+        let fraction = format!("{nanos:09}");
+        formatted.push('.');
+        formatted.push_str(fraction.trim_end_matches('0'));
+    }
+    formatted.push('Z');
+    Some(formatted)
+}
+
+/// Parses an RFC 3339 UTC timestamp, as produced by [`format`]. Only the `Z` offset
+/// is accepted.
+pub(crate) fn parse(time: &str) -> Option<SystemTime> {
+    let time = time.strip_suffix('Z')?;
+    let (datetime, fraction) = match time.split_once('.') {
+        Some((datetime, fraction)) => (datetime, Some(fraction)),
+        None => (time, None),
+    };
+
+    let bytes = datetime.as_bytes();
+    if !datetime.is_ascii()
+        || bytes.len() != 19
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[10] != b'T'
+    {
+        return None;
+    }
+    if bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| -> Option<u32> {
+        let digits = &datetime[range];
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+
+    let days = days_from_civil(year.into(), month, day);
+    if civil_from_days(days) != (year.into(), month, day) || hour > 23 || minute > 59 || second > 59
+    {
+        return None;
+    }
+
+    let nanos = match fraction {
+        Some(fraction) if (1..=9).contains(&fraction.len()) => {
+            if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            format!("{fraction:0<9}").parse().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let secs = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second);
+    let since_epoch = Duration::new(secs.unsigned_abs(), 0);
+    let time = if secs >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(since_epoch)?
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(since_epoch)?
+    };
+    time.checked_add(Duration::from_nanos(nanos))
+}
+
+/// Converts days since the Unix epoch into a (year, month, day) date.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a (year, month, day) date into days since the Unix epoch. The inverse of
+/// [`civil_from_days`], for valid dates.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let from_unix = |secs: i64, nanos: u32| {
+            let epoch = SystemTime::UNIX_EPOCH;
+            let time = if secs >= 0 {
+                epoch + Duration::from_secs(secs as u64)
+            } else {
+                epoch - Duration::from_secs(secs.unsigned_abs())
+            };
+            time + Duration::from_nanos(nanos.into())
+        };
+
+        for (secs, nanos, formatted) in [
+            (0, 0, "1970-01-01T00:00:00Z"),
+            (1_577_836_800, 0, "2020-01-01T00:00:00Z"),
+            (1_709_210_096, 500_000_000, "2024-02-29T12:34:56.5Z"),
+            (-1, 999_999_900, "1969-12-31T23:59:59.9999999Z"),
+            (-11_644_473_600, 0, "1601-01-01T00:00:00Z"),
+            (253_402_300_799, 0, "9999-12-31T23:59:59Z"),
+        ] {
+            let time = from_unix(secs, nanos);
+            assert_eq!(unix_seconds(time), (secs, nanos));
+            assert_eq!(format(time).as_deref(), Some(formatted));
+            assert_eq!(parse(formatted), Some(time));
+        }
+
+        assert_eq!(format(from_unix(253_402_300_800, 0)), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for invalid in [
+            "",
+            "2020-01-01",
+            "2020-01-01T00:00:00",
+            "2020-01-01T00:00:00+00:00",
+            "2020-01-01 00:00:00Z",
+            "2020-02-30T00:00:00Z",
+            "2021-02-29T00:00:00Z",
+            "2020-13-01T00:00:00Z",
+            "2020-01-01T24:00:00Z",
+            "2020-01-01T00:00:60Z",
+            "2020-01-01T00:00:00.Z",
+            "2020-01-01T00:00:00.1234567890Z",
+            "+020-01-01T00:00:00Z",
+            "2020-01-01T00:00:0éZ",
+        ] {
+            assert_eq!(parse(invalid), None, "{invalid}");
+        }
+    }
+}
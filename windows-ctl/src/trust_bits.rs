@@ -0,0 +1,188 @@
+//! Summaries of what each root is trusted for.
+
+use bitflags::bitflags;
+use der::asn1::ObjectIdentifier;
+
+use crate::{eku, CtlError, DecodedTrustedSubject, MetaEku, TrustedSubject};
+
+bitflags! {
+    /// A summary of what a root is trusted for, as computed by
+    /// [`TrustedSubject::trust_bits`].
+    ///
+    /// This reduces an entry's EKU and distrust attributes to what most trust store
+    /// formats can express: a handful of purposes, and whether the entry's trust is
+    /// qualified in ways they may not be able to represent.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+    pub struct TrustBits: u8 {
+        /// Trusted for TLS server authentication.
+        const SERVER_AUTH = 1 << 0;
+        /// Trusted for TLS client authentication.
+        const CLIENT_AUTH = 1 << 1;
+        /// Trusted for code signing.
+        const CODE_SIGNING = 1 << 2;
+        /// Trusted for email protection (S/MIME).
+        const EMAIL_PROTECTION = 1 << 3;
+        /// Trusted for timestamping.
+        const TIME_STAMPING = 1 << 4;
+        /// Distrusted for some, but not all, of the EKUs it's otherwise trusted for.
+        const RESTRICTED = 1 << 5;
+        /// Certificates issued under it after its [not-before](TrustedSubject::not_before)
+        /// time aren't trusted, although those issued before it still are.
+        const DISTRUSTED_AFTER = 1 << 6;
+    }
+}
+
+/// The purpose bits, and the EKUs they correspond to.
+const PURPOSES: &[(TrustBits, ObjectIdentifier)] = &[
+    (TrustBits::SERVER_AUTH, eku::SERVER_AUTH),
+    (TrustBits::CLIENT_AUTH, eku::CLIENT_AUTH),
+    (TrustBits::CODE_SIGNING, eku::CODE_SIGNING),
+    (TrustBits::EMAIL_PROTECTION, eku::EMAIL_PROTECTION),
+    (TrustBits::TIME_STAMPING, eku::TIME_STAMPING),
+];
+
+impl TrustBits {
+    /// Returns the purpose bit for `eku`, if it has one.
+    pub fn from_eku(eku: &ObjectIdentifier) -> Option<Self> {
+        PURPOSES
+            .iter()
+            .find(|(_, oid)| oid == eku)
+            .map(|(bit, _)| *bit)
+    }
+
+    /// Computes the bits from an entry's MetaEKUs, its disallowed EKUs (empty if it
+    /// has none), and whether it has disallowed and not-before times.
+    fn compute(
+        ekus: &MetaEku,
+        disallowed_ekus: &MetaEku,
+        disallowed: bool,
+        not_before: bool,
+    ) -> Self {
+        let distrusted = |eku| distrusted(eku, disallowed_ekus, disallowed);
+
+        let mut bits = Self::empty();
+        for (bit, eku) in PURPOSES {
+            if ekus.contains(eku) && !distrusted(eku) {
+                bits |= *bit;
+            }
+        }
+
+        let trusted = ekus.iter().filter(|eku| !distrusted(eku)).count();
+        if trusted > 0 && trusted < ekus.len() {
+            bits |= Self::RESTRICTED;
+        }
+        if not_before {
+            bits |= Self::DISTRUSTED_AFTER;
+        }
+
+        bits
+    }
+}
+
+/// Returns whether an entry with the given disallowed EKUs (empty if it has none), and
+/// with or without a disallowed time, is distrusted for `eku`.
+pub(crate) fn distrusted(
+    eku: &ObjectIdentifier,
+    disallowed_ekus: &MetaEku,
+    disallowed: bool,
+) -> bool {
+    // A distrust time with no EKUs listed applies to every EKU.
+    disallowed_ekus.contains(eku) || (disallowed_ekus.is_empty() && disallowed)
+}
+
+impl TrustedSubject {
+    /// Summarizes what this subject is trusted for, as [`TrustBits`].
+    ///
+    /// A subject is trusted for a purpose if its MetaEKUs include the purpose's EKU,
+    /// and it hasn't been distrusted for it: either explicitly, via its disallowed
+    /// EKUs, or because it has a disallowed time without any disallowed EKUs (which
+    /// distrusts it for everything).
+    pub fn trust_bits(&self) -> Result<TrustBits, CtlError> {
+        Ok(TrustBits::compute(
+            &self.extended_key_usages().collect::<Result<_, _>>()?,
+            &self.disallowed_ekus().collect::<Result<_, _>>()?,
+            self.disallowed_at()?.is_some(),
+            self.not_before()?.is_some(),
+        ))
+    }
+}
+
+impl DecodedTrustedSubject<'_> {
+    /// See [`TrustedSubject::trust_bits`].
+    pub fn trust_bits(&self) -> TrustBits {
+        TrustBits::compute(
+            self.extended_key_usages(),
+            self.disallowed_ekus().unwrap_or(&MetaEku::default()),
+            self.disallowed_at().is_some(),
+            self.not_before().is_some(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::Encode;
+
+    use super::*;
+    use crate::{
+        testing, MS_CERT_PROP_ID_DISALLOWED_EKUS_OID, MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID,
+        MS_CERT_PROP_ID_METAEKUS_OID, MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID,
+    };
+
+    #[test]
+    fn test_trust_bits() {
+        let ekus = |oids: &[_]| MetaEku(oids.to_vec()).to_der().unwrap();
+        let meta = testing::attribute(
+            MS_CERT_PROP_ID_METAEKUS_OID,
+            &ekus(&[
+                eku::SERVER_AUTH,
+                eku::CODE_SIGNING,
+                eku::MS_DOCUMENT_SIGNING,
+            ]),
+        );
+        let filetime = |oid| testing::attribute(oid, &132_223_104_000_000_000u64.to_le_bytes());
+
+        for (attributes, expected) in [
+            (vec![], TrustBits::empty()),
+            (
+                vec![meta.clone()],
+                TrustBits::SERVER_AUTH | TrustBits::CODE_SIGNING,
+            ),
+            // Distrusted for everything.
+            (
+                vec![
+                    meta.clone(),
+                    filetime(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID),
+                ],
+                TrustBits::empty(),
+            ),
+            // Distrusted for server authentication only.
+            (
+                vec![
+                    meta.clone(),
+                    filetime(MS_CERT_PROP_ID_DISALLOWED_FILETIME_OID),
+                    testing::attribute(
+                        MS_CERT_PROP_ID_DISALLOWED_EKUS_OID,
+                        &ekus(&[eku::SERVER_AUTH]),
+                    ),
+                ],
+                TrustBits::CODE_SIGNING | TrustBits::RESTRICTED,
+            ),
+            // Distrusted for new issuance.
+            (
+                vec![meta, filetime(MS_CERT_PROP_ID_NOT_BEFORE_FILETIME_OID)],
+                TrustBits::SERVER_AUTH | TrustBits::CODE_SIGNING | TrustBits::DISTRUSTED_AFTER,
+            ),
+        ] {
+            let subject = testing::subject(&[0x11; 20], attributes);
+            assert_eq!(subject.trust_bits().unwrap(), expected);
+            assert_eq!(subject.decoded().unwrap().trust_bits(), expected);
+        }
+
+        assert_eq!(
+            TrustBits::from_eku(&eku::EMAIL_PROTECTION),
+            Some(TrustBits::EMAIL_PROTECTION)
+        );
+        assert_eq!(TrustBits::from_eku(&eku::MS_DOCUMENT_SIGNING), None);
+    }
+}
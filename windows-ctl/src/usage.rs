@@ -0,0 +1,175 @@
+//! Well-known `SubjectUsage` OIDs, i.e. what a CTL is for.
+//!
+//! Each of these appears in a [`CertificateTrustList`]'s
+//! [`subject_usage`](CertificateTrustList::subject_usage), and is matched by
+//! [`ListUsage`].
+
+use std::fmt;
+
+use der::asn1::ObjectIdentifier;
+
+use crate::{eku, CertificateTrustList};
+
+/// `szOID_KP_CTL_USAGE_SIGNING`: a generic Microsoft trust list.
+pub const TRUST_LIST_SIGNING: ObjectIdentifier = eku::MS_TRUST_LIST_SIGNING;
+
+/// `szOID_ROOT_LIST_SIGNER`: the trusted root list (`authroot.stl`).
+pub const ROOT_LIST_SIGNER: ObjectIdentifier = eku::MS_ROOT_LIST_SIGNER;
+
+/// `szOID_DISALLOWED_LIST`: the disallowed certificate list (`disallowedcert.stl`).
+pub const DISALLOWED_LIST: ObjectIdentifier = eku::MS_DISALLOWED_LIST;
+
+/// `szOID_PIN_RULES_SIGNER`: signers of the certificate pinning rules list.
+pub const PIN_RULES_SIGNER: ObjectIdentifier = eku::MS_PIN_RULES_SIGNER;
+
+/// `szOID_PIN_RULES_CTL`: the certificate pinning rules list (`pinrules.stl`).
+pub const PIN_RULES_CTL: ObjectIdentifier = eku::MS_PIN_RULES_CTL;
+
+/// `szOID_AUTO_ENROLL_CTL_USAGE`: an auto-enrollment CTL.
+pub const AUTO_ENROLL_CTL_USAGE: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.20.1");
+
+/// `szOID_KP_ROOT_PROGRAM_AUTO_UPDATE_CA_REVOCATION`: auto-update revocation of CAs.
+pub const AUTO_UPDATE_CA_REVOCATION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.60.3.1");
+
+/// `szOID_KP_ROOT_PROGRAM_AUTO_UPDATE_END_REVOCATION`: auto-update revocation of
+/// end-entity certificates.
+pub const AUTO_UPDATE_END_REVOCATION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.311.60.3.2");
+
+/// A well-known `SubjectUsage`, for matching against a list's usage OIDs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ListUsage {
+    /// [`TRUST_LIST_SIGNING`].
+    TrustListSigning,
+    /// [`ROOT_LIST_SIGNER`].
+    RootListSigner,
+    /// [`DISALLOWED_LIST`].
+    DisallowedList,
+    /// [`PIN_RULES_SIGNER`].
+    PinRulesSigner,
+    /// [`PIN_RULES_CTL`].
+    PinRulesCtl,
+    /// [`AUTO_ENROLL_CTL_USAGE`].
+    AutoEnroll,
+    /// [`AUTO_UPDATE_CA_REVOCATION`].
+    AutoUpdateCaRevocation,
+    /// [`AUTO_UPDATE_END_REVOCATION`].
+    AutoUpdateEndRevocation,
+}
+
+/// Every usage, along with its OID and name.
+const USAGES: &[(ListUsage, ObjectIdentifier, &str)] = &[
+    (
+        ListUsage::TrustListSigning,
+        TRUST_LIST_SIGNING,
+        "trust list signing",
+    ),
+    (ListUsage::RootListSigner, ROOT_LIST_SIGNER, "root list"),
+    (
+        ListUsage::DisallowedList,
+        DISALLOWED_LIST,
+        "disallowed list",
+    ),
+    (
+        ListUsage::PinRulesSigner,
+        PIN_RULES_SIGNER,
+        "pin rules signing",
+    ),
+    (ListUsage::PinRulesCtl, PIN_RULES_CTL, "pin rules list"),
+    (
+        ListUsage::AutoEnroll,
+        AUTO_ENROLL_CTL_USAGE,
+        "auto-enrollment list",
+    ),
+    (
+        ListUsage::AutoUpdateCaRevocation,
+        AUTO_UPDATE_CA_REVOCATION,
+        "auto-update CA revocation",
+    ),
+    (
+        ListUsage::AutoUpdateEndRevocation,
+        AUTO_UPDATE_END_REVOCATION,
+        "auto-update end-entity revocation",
+    ),
+];
+
+impl ListUsage {
+    /// Returns the usage with the given OID, if it's a well-known one.
+    pub fn from_oid(oid: &ObjectIdentifier) -> Option<Self> {
+        USAGES
+            .iter()
+            .find(|(_, known, _)| known == oid)
+            .map(|(usage, _, _)| *usage)
+    }
+
+    /// Returns this usage's OID.
+    pub fn oid(&self) -> ObjectIdentifier {
+        self.entry().1
+    }
+
+    fn entry(&self) -> &'static (ListUsage, ObjectIdentifier, &'static str) {
+        USAGES
+            .iter()
+            .find(|(usage, _, _)| usage == self)
+            .expect("every usage is listed")
+    }
+}
+
+impl fmt::Display for ListUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.entry().2)
+    }
+}
+
+impl From<ListUsage> for ObjectIdentifier {
+    fn from(usage: ListUsage) -> Self {
+        usage.oid()
+    }
+}
+
+impl CertificateTrustList {
+    /// Returns the well-known usages in this list's
+    /// [`subject_usage`](CertificateTrustList::subject_usage), skipping any others.
+    pub fn usages(&self) -> impl Iterator<Item = ListUsage> + '_ {
+        self.subject_usage.0.iter().filter_map(ListUsage::from_oid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_cert::ext::pkix::ExtendedKeyUsage;
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_list_usage() {
+        for (usage, oid, _) in USAGES {
+            assert_eq!(ListUsage::from_oid(oid), Some(*usage));
+            assert_eq!(usage.oid(), *oid);
+        }
+        assert_eq!(ListUsage::RootListSigner.to_string(), "root list");
+        assert_eq!(
+            ListUsage::from_oid(&ObjectIdentifier::new_unwrap("1.2.3.4")),
+            None
+        );
+
+        let mut ctl = testing::sample_ctl();
+        assert_eq!(
+            ctl.usages().collect::<Vec<_>>(),
+            [ListUsage::RootListSigner]
+        );
+
+        ctl.subject_usage = ExtendedKeyUsage(vec![
+            ObjectIdentifier::new_unwrap("1.2.3.4"),
+            DISALLOWED_LIST,
+        ]);
+        assert_eq!(
+            ctl.usages().collect::<Vec<_>>(),
+            [ListUsage::DisallowedList]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Lenient decoding of the UTF-16 strings that Microsoft embeds in CTLs.
+
+/// Decodes a UTF-16 string as found in CTL data: friendly names, list identifiers,
+/// and the like.
+///
+/// Real lists contain a few oddities, which are handled leniently:
+///
+/// * The string is little-endian unless it begins with a big-endian byte order mark;
+///   any byte order mark is stripped.
+/// * The string ends at its first NUL, as Windows reads it. Anything after that
+///   (usually padding) is ignored.
+/// * Unpaired surrogates are replaced with U+FFFD, rather than rejected.
+///
+/// The only error is an odd number of bytes, which can't be UTF-16 at all.
+pub(crate) fn decode(bytes: &[u8]) -> Result<String, &'static str> {
+    let (bytes, from_bytes): (_, fn([u8; 2]) -> u16) = match bytes {
+        [0xff, 0xfe, rest @ ..] => (rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => (rest, u16::from_be_bytes),
+        _ => (bytes, u16::from_le_bytes),
+    };
+
+    // A byte order mark doesn't change the length's parity, so this checks the whole
+    // string.
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return Err("odd-length UTF-16 string");
+    }
+
+    let units = chunks
+        .map(|c| from_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0);
+
+    Ok(char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect())
+}
+
+/// Encodes a string the way Windows stores string properties: NUL-terminated UTF-16LE.
+/// The inverse of [`decode`].
+pub(crate) fn encode(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn le(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(&le("Test Root")).unwrap(), "Test Root");
+        assert_eq!(decode(&le("Test Root\0")).unwrap(), "Test Root");
+        assert_eq!(decode(&[]).unwrap(), "");
+
+        // Byte order marks.
+        assert_eq!(decode(&le("\u{feff}Test")).unwrap(), "Test");
+        let be = "\u{feff}Test"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(decode(&be).unwrap(), "Test");
+
+        // Embedded NULs end the string.
+        assert_eq!(decode(&le("Test\0garbage\0\0")).unwrap(), "Test");
+
+        // Unpaired surrogates.
+        let mut bytes = le("ab");
+        bytes.splice(2..2, [0x00, 0xd8]);
+        assert_eq!(decode(&bytes).unwrap(), "a\u{fffd}b");
+
+        assert_eq!(encode("Test"), le("Test\0"));
+        assert_eq!(decode(&encode("Test Root")).unwrap(), "Test Root");
+
+        assert!(decode(&[0x41]).is_err());
+        assert!(decode(&[0x41, 0x00, 0x42]).is_err());
+    }
+}
@@ -0,0 +1,790 @@
+//! Verifying the signatures on CTLs.
+//!
+//! A [`CtlMessage`] verifies when each of its signers' signatures covers the list (via
+//! the `messageDigest` of its signed attributes, if it has any, per
+//! [RFC 2315, section 9.3]; see [`SignerMetadata::check_message_digest`]), and each
+//! signer's certificate chains to one of the caller's trust anchors, through the
+//! certificates embedded in the message (or any that the caller supplies).
+//!
+//! The [timestamps](crate::Timestamp) on signers' signatures are verified separately,
+//! with [`Verifier::verify_timestamp`].
+//!
+//! Chain building and signature checks are done by `rustls-webpki`, so certificates
+//! and signatures are held to its standards: in particular, SHA-1 and MD5 signatures
+//! aren't supported. Signers are further held to a [`Policy`], which by default also
+//! rejects SHA-1 and MD5 digests and RSA keys shorter than 2048 bits.
+//!
+//! [RFC 2315, section 9.3]: https://www.rfc-editor.org/rfc/rfc2315#section-9.3
+
+use std::time::{Duration, SystemTime};
+
+use der::asn1::{AnyRef, ObjectIdentifier, UintRef};
+use der::{Decode, Encode, Sequence};
+use pki_types::{CertificateDer, SignatureVerificationAlgorithm, UnixTime};
+use webpki::ring as algs;
+use webpki::{EndEntityCert, ExtendedKeyUsageValidator, KeyPurposeIdIter};
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::Certificate;
+
+use crate::countersignature::TST_INFO_OID;
+use crate::envelope::{detached_signers, identifies};
+use crate::signer_info::{
+    digest, ECDSA_WITH_SHA1_OID, ECDSA_WITH_SHA256_OID, ECDSA_WITH_SHA384_OID,
+    ECDSA_WITH_SHA512_OID, EC_PUBLIC_KEY_OID, MD5_OID, MD5_WITH_RSA_OID, RSA_ENCRYPTION_OID,
+    SHA1_WITH_RSA_OID, SHA256_WITH_RSA_OID, SHA384_OID, SHA384_WITH_RSA_OID, SHA512_OID,
+    SHA512_WITH_RSA_OID,
+};
+use crate::{
+    eku, CtlError, CtlMessage, SignerMetadata, Timestamp, TimestampKind, MS_CERT_TRUST_LIST_OID,
+    SHA1_OID, SHA256_OID,
+};
+
+/// Verifies [`CtlMessage`]s against a set of trust anchors.
+///
+/// ```no_run
+/// # use windows_ctl::{verify::Verifier, CtlMessage};
+/// # fn example(roots: Vec<x509_cert::Certificate>, message: &CtlMessage) -> Result<(), windows_ctl::CtlError> {
+/// let verification = Verifier::new(roots).verify(message)?;
+/// for signer in &verification.signers {
+///     println!("signed by {}", signer.certificate.tbs_certificate.subject);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    anchors: Vec<Certificate>,
+    intermediates: Vec<Certificate>,
+    time: Option<SystemTime>,
+    policy: Policy,
+}
+
+/// What's required of a list's signers, beyond a valid signature and chain.
+///
+/// The default policy mirrors what crypt32 requires of root lists, and rejects
+/// algorithms and key sizes that are considered weak. Fields can be changed
+/// individually, e.g. to fail closed on anything short of SHA-384 and 3072-bit RSA:
+///
+/// ```
+/// # use windows_ctl::{verify::Policy, SubjectAlgorithm};
+/// let mut policy = Policy::default();
+/// policy
+///     .digest_algorithms
+///     .retain(|oid| *oid != SubjectAlgorithm::Sha256.oid());
+/// policy.min_rsa_bits = 3072;
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Policy {
+    /// The EKU that signers' certificates must assert, or `None` to accept signers
+    /// regardless of their EKUs. Defaults to [`eku::MS_ROOT_LIST_SIGNER`], so that
+    /// lists signed by certificates issued for other purposes (such as code signing)
+    /// under the same roots are rejected.
+    pub signer_eku: Option<ObjectIdentifier>,
+    /// The digest algorithms that signers may use, both to digest the list and
+    /// within their signature algorithm. Defaults to SHA-256, SHA-384, and SHA-512,
+    /// so that signers using SHA-1 or MD5 are rejected.
+    pub digest_algorithms: Vec<ObjectIdentifier>,
+    /// The smallest RSA key, in bits, that signers and intermediates may have.
+    /// Defaults to 2048, which is also the smallest that `rustls-webpki` supports.
+    pub min_rsa_bits: usize,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            signer_eku: Some(eku::MS_ROOT_LIST_SIGNER),
+            digest_algorithms: vec![SHA256_OID, SHA384_OID, SHA512_OID],
+            min_rsa_bits: 2048,
+        }
+    }
+}
+
+impl Policy {
+    /// Checks that `metadata`'s digest algorithm, and the digest within its signature
+    /// algorithm, are allowed.
+    fn check_algorithms(&self, metadata: &SignerMetadata) -> Result<(), CtlError> {
+        let digest = metadata.digest_algorithm.oid;
+        let signature_digest = match metadata.signature_algorithm.oid {
+            MD5_WITH_RSA_OID => MD5_OID,
+            SHA1_WITH_RSA_OID | ECDSA_WITH_SHA1_OID => SHA1_OID,
+            SHA256_WITH_RSA_OID | ECDSA_WITH_SHA256_OID => SHA256_OID,
+            SHA384_WITH_RSA_OID | ECDSA_WITH_SHA384_OID => SHA384_OID,
+            SHA512_WITH_RSA_OID | ECDSA_WITH_SHA512_OID => SHA512_OID,
+            // Bare key algorithms leave the hash to the digest algorithm.
+            _ => digest,
+        };
+        [digest, signature_digest]
+            .into_iter()
+            .try_for_each(|oid| self.check_digest(oid))
+    }
+
+    /// Checks that the digest algorithm `oid` is allowed.
+    fn check_digest(&self, oid: ObjectIdentifier) -> Result<(), CtlError> {
+        if !self.digest_algorithms.contains(&oid) {
+            return Err(CtlError::DisallowedDigest(oid));
+        }
+        Ok(())
+    }
+
+    /// Checks that `cert`'s key, if it's an RSA key, is at least `min_rsa_bits` long.
+    fn check_key(&self, cert: &Certificate) -> Result<(), CtlError> {
+        let spki = &cert.tbs_certificate.subject_public_key_info;
+        if spki.algorithm.oid != RSA_ENCRYPTION_OID {
+            return Ok(());
+        }
+
+        let key = RsaPublicKey::from_der(spki.subject_public_key.raw_bytes())?;
+        let modulus = key.modulus.as_bytes();
+        let bits = match modulus.first() {
+            Some(first) => modulus.len() * 8 - first.leading_zeros() as usize,
+            None => 0,
+        };
+        if bits < self.min_rsa_bits {
+            return Err(CtlError::WeakKey {
+                bits,
+                minimum: self.min_rsa_bits,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A PKCS#1 `RSAPublicKey`.
+#[derive(Sequence)]
+struct RsaPublicKey<'a> {
+    modulus: UintRef<'a>,
+    public_exponent: UintRef<'a>,
+}
+
+/// The outcome of successfully verifying a [`CtlMessage`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Verification {
+    /// Each of the message's signers, in the order of their `SignerInfo`s.
+    pub signers: Vec<VerifiedSigner>,
+    /// The time that certificates were checked for validity at.
+    pub time: SystemTime,
+}
+
+/// A signer whose signature was verified, and whose certificate chains to a trust
+/// anchor.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct VerifiedSigner {
+    /// The signer's `SignerInfo` metadata, including its digest and signature
+    /// algorithms.
+    pub metadata: SignerMetadata,
+    /// The signer's certificate.
+    pub certificate: Certificate,
+    /// The certificates that the signer's certificate chains to, from its issuer up
+    /// to (and including) the trust anchor.
+    pub chain: Vec<Certificate>,
+}
+
+impl Verifier {
+    /// Creates a verifier that trusts signers whose certificates chain to one of
+    /// `anchors`.
+    pub fn new(anchors: impl IntoIterator<Item = Certificate>) -> Self {
+        Self {
+            anchors: anchors.into_iter().collect(),
+            intermediates: vec![],
+            time: None,
+            policy: Policy::default(),
+        }
+    }
+
+    /// Adds certificates to build chains with, alongside those embedded in the
+    /// message. Signers' certificates are looked up among these too.
+    pub fn intermediates(mut self, certs: impl IntoIterator<Item = Certificate>) -> Self {
+        self.intermediates.extend(certs);
+        self
+    }
+
+    /// Sets the time that certificates must be valid at. Defaults to the current time.
+    pub fn time(mut self, time: SystemTime) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the policy that signers are held to. Defaults to [`Policy::default`].
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Verifies every signer of `message`, failing if there are none.
+    pub fn verify(&self, message: &CtlMessage) -> Result<Verification, CtlError> {
+        self.verify_signers(
+            message.signers(),
+            message.certificates(),
+            message.raw().content_octets(),
+        )
+    }
+
+    /// Verifies every signer of a detached PKCS#7 signature (see
+    /// [`crate::CertificateTrustList::from_detached`]) over `content`, the DER-encoded
+    /// `certTrustList` that it was computed over, failing if there are none.
+    pub fn verify_detached(
+        &self,
+        signature: &[u8],
+        content: &[u8],
+    ) -> Result<Verification, CtlError> {
+        let (signers, certificates) = detached_signers(signature)?;
+        self.verify_signers(signers, certificates, AnyRef::from_der(content)?.value())
+    }
+
+    /// Verifies that each of `signers`, whose certificates are among `certificates`
+    /// (or our intermediates), signed the list whose content octets are `content`.
+    fn verify_signers(
+        &self,
+        signers: Vec<SignerMetadata>,
+        mut certificates: Vec<Certificate>,
+        content: &[u8],
+    ) -> Result<Verification, CtlError> {
+        let time = self.time.unwrap_or_else(SystemTime::now);
+        if signers.is_empty() {
+            return Err(CtlError::Unsigned);
+        }
+
+        certificates.extend(self.intermediates.iter().cloned());
+        let signers = signers
+            .into_iter()
+            .map(|metadata| {
+                self.verify_signer(
+                    metadata,
+                    content,
+                    Some(MS_CERT_TRUST_LIST_OID),
+                    &certificates,
+                    time,
+                    self.policy.signer_eku,
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Verification { signers, time })
+    }
+
+    /// Verifies `timestamp`, one of the [timestamps](SignerMetadata::timestamps) on
+    /// `signer`'s signature, where `signer` is one of `message`'s signers: that it
+    /// covers that signature, and that its own signer's signature verifies and
+    /// certificate chains to one of this verifier's anchors, asserting the
+    /// [`eku::TIME_STAMPING`] EKU.
+    ///
+    /// Time-stamping authorities are usually issued under other roots than list
+    /// signers, so this is typically done by a separate verifier. Certificates are
+    /// checked for validity at the time the timestamp asserts, rather than at this
+    /// verifier's [`time`](Self::time). The [`Policy`]'s algorithm requirements apply
+    /// (to the timestamp's imprint, too), but its signer EKU doesn't. This doesn't
+    /// verify `signer` itself.
+    pub fn verify_timestamp(
+        &self,
+        message: &CtlMessage,
+        signer: &SignerMetadata,
+        timestamp: &Timestamp,
+    ) -> Result<VerifiedSigner, CtlError> {
+        self.policy.check_digest(timestamp.imprint_algorithm.oid)?;
+        if timestamp.imprint != digest(&timestamp.imprint_algorithm.oid, &signer.signature)? {
+            return Err(CtlError::TimestampMismatch);
+        }
+
+        let mut certificates = timestamp.certificates.clone();
+        certificates.extend(message.certificates());
+        certificates.extend(self.intermediates.iter().cloned());
+        let content_type = match timestamp.kind {
+            TimestampKind::Token => Some(TST_INFO_OID),
+            // Countersignatures sign no content type of their own.
+            _ => None,
+        };
+        self.verify_signer(
+            timestamp.signer.clone(),
+            timestamp.signed_content(),
+            content_type,
+            &certificates,
+            timestamp.time,
+            Some(eku::TIME_STAMPING),
+        )
+    }
+
+    /// Verifies that the signer described by `metadata` signed `content` (of the
+    /// given `content_type`, if its signed attributes must say), and that its
+    /// certificate asserts the `required` EKU and chains to one of our anchors at
+    /// `time`, through `certificates`.
+    fn verify_signer(
+        &self,
+        metadata: SignerMetadata,
+        content: &[u8],
+        content_type: Option<ObjectIdentifier>,
+        certificates: &[Certificate],
+        time: SystemTime,
+        required: Option<ObjectIdentifier>,
+    ) -> Result<VerifiedSigner, CtlError> {
+        let unix_time = UnixTime::since_unix_epoch(
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or(Duration::ZERO),
+        );
+
+        let certificates_der = certificates
+            .iter()
+            .map(|cert| Ok(CertificateDer::from(cert.to_der()?)))
+            .collect::<Result<Vec<_>, CtlError>>()?;
+        let anchors_der = self
+            .anchors
+            .iter()
+            .map(|cert| Ok(CertificateDer::from(cert.to_der()?)))
+            .collect::<Result<Vec<_>, CtlError>>()?;
+        let anchors = anchors_der
+            .iter()
+            .map(webpki::anchor_from_trusted_cert)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(CtlError::InvalidTrustAnchor)?;
+
+        let index = certificates
+            .iter()
+            .position(|cert| identifies(&metadata.sid, cert))
+            .ok_or(CtlError::MissingSignerCertificate)?;
+        let ee =
+            EndEntityCert::try_from(&certificates_der[index]).map_err(CtlError::UntrustedSigner)?;
+
+        self.policy.check_algorithms(&metadata)?;
+        verify_signature(&metadata, content, content_type, &ee)?;
+        if let Some(required) = required {
+            check_signer_eku(&certificates[index], required)?;
+        }
+
+        let path = ee
+            .verify_for_usage(
+                webpki::ALL_VERIFICATION_ALGS,
+                &anchors,
+                &certificates_der,
+                unix_time,
+                AnyUsage,
+                None,
+                None,
+            )
+            .map_err(CtlError::UntrustedSigner)?;
+
+        let mut chain = path
+            .intermediate_certificates()
+            .map(|cert| Certificate::from_der(cert.der().as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let anchor = anchors
+            .iter()
+            .position(|anchor| std::ptr::eq(anchor, path.anchor()))
+            .expect("path ends at one of our anchors");
+        // The anchors are the caller's choice, so only the rest of the chain is held
+        // to the policy's key sizes.
+        self.policy.check_key(&certificates[index])?;
+        chain
+            .iter()
+            .try_for_each(|cert| self.policy.check_key(cert))?;
+        chain.push(self.anchors[anchor].clone());
+
+        Ok(VerifiedSigner {
+            metadata,
+            certificate: certificates[index].clone(),
+            chain,
+        })
+    }
+}
+
+/// Checks that the signer's certificate asserts the `required` EKU.
+///
+/// Unlike a chain's EKUs, which only constrain a certificate if it has any, a signer
+/// without an EKU extension is rejected: it isn't a list signer.
+fn check_signer_eku(cert: &Certificate, required: ObjectIdentifier) -> Result<(), CtlError> {
+    let asserted = cert
+        .tbs_certificate
+        .get::<ExtendedKeyUsage>()?
+        .is_some_and(|(_, ekus)| ekus.0.contains(&required));
+    if !asserted {
+        return Err(CtlError::SignerUsage(required));
+    }
+    Ok(())
+}
+
+/// Checks that `metadata`'s signature covers `content`, under the key in `ee`.
+fn verify_signature(
+    metadata: &SignerMetadata,
+    content: &[u8],
+    content_type: Option<ObjectIdentifier>,
+    ee: &EndEntityCert<'_>,
+) -> Result<(), CtlError> {
+    // The signature covers the signed attributes exactly as they were encoded (but
+    // tagged as a SET OF), which needn't be the sorted order they were decoded in.
+    let signed = match &metadata.signed_attributes_der {
+        None => content,
+        Some(attrs) => {
+            metadata.check_signed_attributes(content, content_type)?;
+            attrs.as_slice()
+        }
+    };
+
+    let candidates = signature_algorithms(
+        &metadata.digest_algorithm.oid,
+        &metadata.signature_algorithm.oid,
+    )?;
+    for alg in candidates {
+        match ee.verify_signature(alg, signed, &metadata.signature) {
+            Ok(()) => return Ok(()),
+            // The algorithm is for a different kind of key (e.g. another curve), so
+            // try the next.
+            Err(webpki::Error::UnsupportedSignatureAlgorithmForPublicKeyContext(_)) => {}
+            Err(e) => return Err(CtlError::BadSignature(e)),
+        }
+    }
+    Err(CtlError::UnsupportedAlgorithm {
+        kind: "signature",
+        oid: metadata.signature_algorithm.oid,
+    })
+}
+
+/// Returns the algorithms that a `SignerInfo`'s signature might have been made with,
+/// given its digest and signature algorithms.
+///
+/// PKCS#7 signers often give just the key's algorithm (e.g. `rsaEncryption`) as their
+/// signature algorithm, leaving the hash to their digest algorithm. ECDSA algorithms
+/// don't name a curve, so there's a candidate for each.
+fn signature_algorithms(
+    digest: &ObjectIdentifier,
+    signature: &ObjectIdentifier,
+) -> Result<Vec<&'static dyn SignatureVerificationAlgorithm>, CtlError> {
+    Ok(match (*signature, *digest) {
+        (SHA256_WITH_RSA_OID, _) | (RSA_ENCRYPTION_OID, SHA256_OID) => {
+            vec![algs::RSA_PKCS1_2048_8192_SHA256]
+        }
+        (SHA384_WITH_RSA_OID, _) | (RSA_ENCRYPTION_OID, SHA384_OID) => {
+            vec![algs::RSA_PKCS1_2048_8192_SHA384]
+        }
+        (SHA512_WITH_RSA_OID, _) | (RSA_ENCRYPTION_OID, SHA512_OID) => {
+            vec![algs::RSA_PKCS1_2048_8192_SHA512]
+        }
+        (ECDSA_WITH_SHA256_OID, _) | (EC_PUBLIC_KEY_OID, SHA256_OID) => {
+            vec![algs::ECDSA_P256_SHA256, algs::ECDSA_P384_SHA256]
+        }
+        (ECDSA_WITH_SHA384_OID, _) | (EC_PUBLIC_KEY_OID, SHA384_OID) => {
+            vec![algs::ECDSA_P384_SHA384, algs::ECDSA_P256_SHA384]
+        }
+        (oid, _) => {
+            return Err(CtlError::UnsupportedAlgorithm {
+                kind: "signature",
+                oid,
+            })
+        }
+    })
+}
+
+/// Accepts certificates regardless of their EKUs (as long as they're well-formed).
+struct AnyUsage;
+
+impl ExtendedKeyUsageValidator for AnyUsage {
+    fn validate(&self, mut iter: KeyPurposeIdIter<'_, '_>) -> Result<(), webpki::Error> {
+        iter.try_for_each(|eku| eku.map(drop))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use der::DecodePem;
+
+    use super::*;
+    use crate::testing;
+
+    /// A time at which the test certificates are valid.
+    fn valid_time() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000)
+    }
+
+    fn root() -> Certificate {
+        Certificate::from_pem(testing::CTL_ROOT).unwrap()
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify() {
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(
+            &testing::sample_ctl(),
+            testing::TestSigner::new(),
+            &signer,
+            &[],
+        )
+        .unwrap();
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+
+        let verification = Verifier::new([root()])
+            .time(valid_time())
+            .verify(&message)
+            .unwrap();
+        assert_eq!(verification.time, valid_time());
+        assert_eq!(verification.signers.len(), 1);
+        let verified = &verification.signers[0];
+        assert_eq!(verified.certificate, signer);
+        assert_eq!(verified.chain, [root()]);
+        assert_eq!(
+            verified.metadata.signature_algorithm_name(),
+            Some("ECDSA with SHA-256")
+        );
+
+        // The signer asserts the Root List Signer EKU, but not code signing.
+        let verifier = Verifier::new([root()]).time(valid_time());
+        let policy = |signer_eku| Policy {
+            signer_eku,
+            ..Policy::default()
+        };
+        assert!(matches!(
+            verifier
+                .clone()
+                .policy(policy(Some(eku::CODE_SIGNING)))
+                .verify(&message),
+            Err(CtlError::SignerUsage(oid)) if oid == eku::CODE_SIGNING
+        ));
+        assert!(verifier.policy(policy(None)).verify(&message).is_ok());
+
+        // Not a trust anchor.
+        let isrg = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        assert!(matches!(
+            Verifier::new([isrg]).time(valid_time()).verify(&message),
+            Err(CtlError::UntrustedSigner(_))
+        ));
+
+        // Before the certificates were issued.
+        assert!(matches!(
+            Verifier::new([root()])
+                .time(SystemTime::UNIX_EPOCH)
+                .verify(&message),
+            Err(CtlError::UntrustedSigner(_))
+        ));
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_tampered() {
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(
+            &testing::sample_ctl(),
+            testing::TestSigner::new(),
+            &signer,
+            &[],
+        )
+        .unwrap();
+        let verifier = Verifier::new([root()]).time(valid_time());
+
+        // Flip a bit in the first subject's identifier, which the digest covers.
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let identifier = message.ctl().trusted_subjects.as_ref().unwrap()[0]
+            .identifier
+            .as_bytes()
+            .to_vec();
+        let offset = der
+            .windows(identifier.len())
+            .position(|window| window == identifier)
+            .unwrap();
+        let mut tampered = der.clone();
+        tampered[offset] ^= 1;
+
+        let message = CtlMessage::from_der(std::io::Cursor::new(&tampered)).unwrap();
+        assert!(matches!(
+            verifier.verify(&message),
+            Err(CtlError::SignedAttributes(_))
+        ));
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_unsorted_attributes() {
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(
+            &testing::sample_ctl(),
+            testing::TestSigner::new(),
+            &signer,
+            &[],
+        )
+        .unwrap();
+        let der = testing::unsort_signed_attributes(&der);
+        let message = CtlMessage::from_der(&der[..]).unwrap();
+
+        // The attributes were signed in an order that re-encoding them wouldn't give.
+        let metadata = &message.signers()[0];
+        let sorted = der::asn1::SetOfVec::try_from(metadata.signed_attributes.clone())
+            .unwrap()
+            .to_der()
+            .unwrap();
+        assert_ne!(metadata.signed_attributes_der, Some(sorted));
+
+        let verification = Verifier::new([root()])
+            .time(valid_time())
+            .verify(&message)
+            .unwrap();
+        assert_eq!(verification.signers[0].certificate, signer);
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_timestamp() {
+        let tsa = Certificate::from_pem(testing::TSA).unwrap();
+        let der = testing::timestamped_ctl(valid_time(), &tsa);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let signer = &message.signers()[0];
+        let timestamps = signer.timestamps().unwrap();
+
+        // Timestamps are checked at the time they assert, not the verifier's.
+        let verifier = Verifier::new([root()]).time(SystemTime::UNIX_EPOCH);
+        for timestamp in &timestamps {
+            let verified = verifier
+                .verify_timestamp(&message, signer, timestamp)
+                .unwrap();
+            assert_eq!(verified.certificate, tsa);
+            assert_eq!(verified.chain, [root()]);
+        }
+
+        // On another signature.
+        let mut other = signer.clone();
+        other.signature[0] ^= 1;
+        let token = timestamps
+            .iter()
+            .find(|ts| ts.kind == TimestampKind::Token)
+            .unwrap();
+        assert!(matches!(
+            verifier.verify_timestamp(&message, &other, token),
+            Err(CtlError::TimestampMismatch)
+        ));
+
+        // Not a trust anchor.
+        let isrg = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        assert!(matches!(
+            Verifier::new([isrg]).verify_timestamp(&message, signer, token),
+            Err(CtlError::UntrustedSigner(_))
+        ));
+
+        // Countersigned by the list signer, which isn't a time-stamping authority.
+        let list_signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = testing::timestamped_ctl(valid_time(), &list_signer);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let signer = &message.signers()[0];
+        let countersignature = signer
+            .timestamps()
+            .unwrap()
+            .into_iter()
+            .find(|ts| ts.kind == TimestampKind::Countersignature)
+            .unwrap();
+        assert!(matches!(
+            verifier.verify_timestamp(&message, signer, &countersignature),
+            Err(CtlError::SignerUsage(oid)) if oid == eku::TIME_STAMPING
+        ));
+
+        // The list itself still verifies with its timestamps attached.
+        assert!(Verifier::new([root()])
+            .time(valid_time())
+            .verify(&message)
+            .is_ok());
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_policy() {
+        let tsa = Certificate::from_pem(testing::TSA).unwrap();
+        let der = testing::timestamped_ctl(valid_time(), &tsa);
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        let signer = &message.signers()[0];
+
+        let sha384_only = Policy {
+            digest_algorithms: vec![SHA384_OID],
+            ..Policy::default()
+        };
+        let verifier = Verifier::new([root()])
+            .time(valid_time())
+            .policy(sha384_only);
+        assert!(matches!(
+            verifier.verify(&message),
+            Err(CtlError::DisallowedDigest(SHA256_OID))
+        ));
+        for timestamp in signer.timestamps().unwrap() {
+            assert!(matches!(
+                verifier.verify_timestamp(&message, signer, &timestamp),
+                Err(CtlError::DisallowedDigest(SHA256_OID))
+            ));
+        }
+
+        // SHA-1 within the signature algorithm is disallowed too.
+        let mut sha1 = signer.clone();
+        sha1.signature_algorithm.oid = ECDSA_WITH_SHA1_OID;
+        assert!(matches!(
+            Policy::default().check_algorithms(&sha1),
+            Err(CtlError::DisallowedDigest(SHA1_OID))
+        ));
+    }
+
+    #[test]
+    fn test_policy_key_size() {
+        let isrg = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let policy = |min_rsa_bits| Policy {
+            min_rsa_bits,
+            ..Policy::default()
+        };
+        policy(4096).check_key(&isrg).unwrap();
+        assert!(matches!(
+            policy(8192).check_key(&isrg),
+            Err(CtlError::WeakKey {
+                bits: 4096,
+                minimum: 8192
+            })
+        ));
+
+        // Only RSA keys have a minimum size.
+        policy(8192).check_key(&root()).unwrap();
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn test_verify_detached() {
+        let signer = Certificate::from_pem(testing::CTL_SIGNER).unwrap();
+        let der = crate::sign::sign(
+            &testing::sample_ctl(),
+            testing::TestSigner::new(),
+            &signer,
+            &[],
+        )
+        .unwrap();
+        let (signature, mut content) = testing::detach(&der);
+        let verifier = Verifier::new([root()]).time(valid_time());
+
+        let verification = verifier.verify_detached(&signature, &content).unwrap();
+        assert_eq!(verification.signers.len(), 1);
+        assert_eq!(verification.signers[0].certificate, signer);
+
+        // Flip a bit in the first subject's identifier, which the digest covers.
+        let offset = content.windows(20).position(|w| w == [0x11; 20]).unwrap();
+        content[offset] ^= 1;
+        assert!(matches!(
+            verifier.verify_detached(&signature, &content),
+            Err(CtlError::SignedAttributes(_))
+        ));
+
+        // Attached signatures aren't detached.
+        assert!(matches!(
+            verifier.verify_detached(&der, &content),
+            Err(CtlError::UnexpectedSignedDataContent)
+        ));
+        assert!(matches!(
+            verifier.verify_detached(&testing::sign_ctl_detached(), &content),
+            Err(CtlError::Unsigned)
+        ));
+    }
+
+    #[test]
+    fn test_verify_unsigned() {
+        let der = testing::sign_ctl(&testing::sample_ctl());
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(matches!(
+            Verifier::new([root()]).time(valid_time()).verify(&message),
+            Err(CtlError::Unsigned)
+        ));
+
+        // A signer whose signature is garbage.
+        let signer = Certificate::from_pem(testing::ISRG_ROOT_X1).unwrap();
+        let der = testing::sign_ctl_with(&testing::sample_ctl(), Some(&signer));
+        let message = CtlMessage::from_der(std::io::Cursor::new(&der)).unwrap();
+        assert!(Verifier::new([signer]).verify(&message).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Lenient validation of CTLs.
+//!
+//! Parsing a [`CertificateTrustList`] only requires that it be well-formed DER;
+//! the contents of each entry's attributes are decoded on demand. The checks here
+//! surface the non-fatal problems that would otherwise only be discovered later
+//! (e.g. as silently skipped entries), so that tools can report them up front.
+
+use std::collections::HashSet;
+
+use der::asn1::ObjectIdentifier;
+use thiserror::Error;
+
+use crate::{CertificateTrustList, CtlError, SubjectAttribute, SubjectId};
+
+/// A non-fatal problem found in an otherwise well-formed [`CertificateTrustList`].
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum CtlWarning {
+    /// An entry has an attribute value that couldn't be decoded.
+    #[error("{subject}: malformed {oid} attribute: {reason}")]
+    MalformedAttribute {
+        /// The entry's identifier.
+        subject: SubjectId,
+        /// The attribute's OID.
+        oid: ObjectIdentifier,
+        /// Why the value couldn't be decoded.
+        reason: String,
+    },
+
+    /// An entry's identifier is the wrong length for the list's `subjectAlgorithm`.
+    #[error("{subject}: identifier is {len} bytes, expected {expected}")]
+    IdentifierLength {
+        /// The entry's identifier.
+        subject: SubjectId,
+        /// The identifier's length.
+        len: usize,
+        /// The length implied by the list's `subjectAlgorithm`.
+        expected: usize,
+    },
+
+    /// An identifier that's listed more than once.
+    #[error("{0}: listed more than once")]
+    DuplicateSubject(SubjectId),
+}
+
+impl CtlWarning {
+    /// Returns the identifier of the entry this warning concerns.
+    pub fn subject(&self) -> &SubjectId {
+        match self {
+            Self::MalformedAttribute { subject, .. } => subject,
+            Self::IdentifierLength { subject, .. } => subject,
+            Self::DuplicateSubject(subject) => subject,
+        }
+    }
+}
+
+impl CertificateTrustList {
+    /// Checks this list for non-fatal problems, returning a [`CtlWarning`] for each one found.
+    pub fn warnings(&self) -> Vec<CtlWarning> {
+        let expected_len = self
+            .identifier_algorithm()
+            .map(|algorithm| algorithm.digest_len());
+
+        let mut warnings = vec![];
+        let mut seen = HashSet::new();
+        for entry in self.trusted_subjects.iter().flatten() {
+            let subject = entry.cert_id();
+
+            if let Some(expected) = expected_len.filter(|len| *len != subject.as_bytes().len()) {
+                warnings.push(CtlWarning::IdentifierLength {
+                    subject: subject.clone(),
+                    len: subject.as_bytes().len(),
+                    expected,
+                });
+            }
+
+            for attr in entry.attributes.iter().flat_map(|attrs| attrs.iter()) {
+                for value in attr.values.iter() {
+                    if let Err(e) = SubjectAttribute::decode(attr.oid, value) {
+                        let reason = match e {
+                            CtlError::Attribute { reason, .. } => reason.into(),
+                            e => e.to_string(),
+                        };
+                        warnings.push(CtlWarning::MalformedAttribute {
+                            subject: subject.clone(),
+                            oid: attr.oid,
+                            reason,
+                        });
+                    }
+                }
+            }
+
+            if !seen.insert(subject.clone()) {
+                warnings.push(CtlWarning::DuplicateSubject(subject));
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, MS_CERT_PROP_ID_SHA256_HASH_OID};
+
+    #[test]
+    fn test_warnings() {
+        let mut ctl = testing::sample_ctl();
+        assert!(ctl.warnings().is_empty());
+
+        let subjects = ctl.trusted_subjects.as_mut().unwrap();
+        subjects.push(testing::subject(&[0x11; 20], vec![]));
+        subjects.push(testing::subject(&[0x33; 16], vec![]));
+        subjects.push(testing::subject(
+            &[0x44; 20],
+            vec![testing::attribute(MS_CERT_PROP_ID_SHA256_HASH_OID, &[0; 4])],
+        ));
+
+        assert_eq!(
+            ctl.warnings(),
+            [
+                CtlWarning::DuplicateSubject(SubjectId::from(vec![0x11; 20])),
+                CtlWarning::IdentifierLength {
+                    subject: SubjectId::from(vec![0x33; 16]),
+                    len: 16,
+                    expected: 20,
+                },
+                CtlWarning::MalformedAttribute {
+                    subject: SubjectId::from(vec![0x44; 20]),
+                    oid: MS_CERT_PROP_ID_SHA256_HASH_OID,
+                    reason: "unexpected hash length".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warnings_sha256() {
+        let mut ctl = testing::sample_sha256_ctl();
+        assert!(ctl.warnings().is_empty());
+
+        ctl.trusted_subjects
+            .as_mut()
+            .unwrap()
+            .push(testing::subject(&[0x33; 20], vec![]));
+        assert_eq!(
+            ctl.warnings(),
+            [CtlWarning::IdentifierLength {
+                subject: SubjectId::from(vec![0x33; 20]),
+                len: 20,
+                expected: 32,
+            }]
+        );
+
+        // Identifiers can't be checked against an algorithm that isn't understood.
+        ctl.subject_algorithm.oid = der::asn1::ObjectIdentifier::new_unwrap("1.2.840.113549.2.5");
+        assert!(ctl.warnings().is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! Peak memory bounds for parsing a large, disallowed-style list, so that regressions
+//! in the borrowed and lazy parsing paths show up as test failures.
+//!
+//! Every test holds [`LOCK`] throughout, since allocations are counted process-wide.
+
+mod support;
+
+use std::io::Cursor;
+use std::mem::size_of;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use support::{disallowed_list, measure, LARGE_LIST_ENTRIES};
+use windows_ctl::{CertificateTrustList, CtlKind, RawCtl, SequenceNumber, TrustedSubjectRef};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes the tests, returning the (shared) synthetic list.
+fn large_list() -> (MutexGuard<'static, ()>, &'static [u8]) {
+    static LIST: OnceLock<Vec<u8>> = OnceLock::new();
+
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    (
+        guard,
+        LIST.get_or_init(|| disallowed_list(LARGE_LIST_ENTRIES)),
+    )
+}
+
+#[test]
+fn test_peek_header_doesnt_allocate() {
+    let (_guard, der) = large_list();
+
+    let (header, peak) = measure(|| CertificateTrustList::peek_header(der).unwrap());
+    assert_eq!(peak, 0);
+    assert_eq!(
+        SequenceNumber::try_from(header.sequence_number.unwrap()).unwrap(),
+        SequenceNumber::new(&[0x01, 0xd9]).unwrap()
+    );
+}
+
+#[test]
+fn test_raw_ctl_doesnt_copy() {
+    let (_guard, der) = large_list();
+    let owned = der.to_vec();
+
+    // Validating the envelope only records offsets into the DER it's given.
+    let (raw, peak) = measure(|| RawCtl::new(owned).unwrap());
+    assert!(peak <= 4096, "{peak} bytes");
+    assert_eq!(raw.as_bytes(), der);
+}
+
+#[test]
+fn test_ctl_ref_only_allocates_subject_refs() {
+    let (_guard, der) = large_list();
+    let raw = RawCtl::new(der.to_vec()).unwrap();
+
+    // The subjects borrow their identifiers and attributes from the DER, so the only
+    // allocation of note is the vector of them (which may have up to twice the
+    // capacity that it needs).
+    let (ctl, peak) = measure(|| raw.ctl_ref().unwrap());
+    let budget = 2 * LARGE_LIST_ENTRIES * size_of::<TrustedSubjectRef<'_>>() + 4096;
+    assert!(
+        peak <= budget,
+        "{peak} bytes, over the {budget} byte budget"
+    );
+    assert_eq!(ctl.trusted_subjects.unwrap().len(), LARGE_LIST_ENTRIES);
+}
+
+#[test]
+fn test_owned_parse_is_bounded() {
+    let (_guard, der) = large_list();
+
+    // Fully owned parsing is inherently heavier (each entry's identifier and
+    // attributes get their own allocations), currently by a factor of about 10.
+    let (ctl, peak) = measure(|| CertificateTrustList::from_der(Cursor::new(der)).unwrap());
+    let budget = 12 * der.len();
+    assert!(
+        peak <= budget,
+        "{peak} bytes, over the {budget} byte budget"
+    );
+    assert_eq!(ctl.kind(), CtlKind::Disallowed);
+
+    // Parsing from a slice skips the copy that parsing from a reader makes.
+    let (from_slice, slice_peak) = measure(|| CertificateTrustList::from_der_slice(der).unwrap());
+    assert!(
+        slice_peak + der.len() <= peak,
+        "{slice_peak} bytes from a slice, {peak} bytes from a reader"
+    );
+    assert_eq!(from_slice, ctl);
+
+    // Decoding attributes is lazy: walking every entry doesn't accumulate anything.
+    let (count, peak) = measure(|| ctl.decoded_subjects().filter(Result::is_ok).count());
+    assert!(peak <= 4096, "{peak} bytes");
+    assert_eq!(count, LARGE_LIST_ENTRIES);
+}
@@ -0,0 +1,105 @@
+//! Allocation accounting and synthetic lists, shared by the memory tests and the
+//! `large_list` benchmark.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+
+use der::pem::LineEnding;
+use windows_ctl::{
+    CtlBuilder, ListUsage, SequenceNumber, SubjectAttribute, SubjectId, DISALLOWED_CERT_LIST_NAME,
+};
+
+/// The number of entries in a realistically large list: the real disallowed list has
+/// tens of thousands.
+pub const LARGE_LIST_ENTRIES: usize = 100_000;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, keeping track of how many bytes are live and the most that
+/// have been since [`measure`] last reset it.
+struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grow(by: usize) {
+        let current = CURRENT.fetch_add(by, Ordering::SeqCst) + by;
+        PEAK.fetch_max(current, Ordering::SeqCst);
+    }
+
+    fn shrink(by: usize) {
+        CURRENT.fetch_sub(by, Ordering::SeqCst);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                Self::grow(new_size - layout.size());
+            } else {
+                Self::shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Runs `f`, returning its result along with the peak number of bytes allocated
+/// while it ran (beyond what was already live beforehand).
+///
+/// Allocations on other threads are counted too, so callers must make sure that
+/// nothing else is running.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let result = f();
+    (result, PEAK.load(Ordering::SeqCst) - baseline)
+}
+
+/// Returns a PKCS#7-wrapped list shaped like the disallowed list, with `entries`
+/// SHA-1-identified entries that each have a disallowed time.
+pub fn disallowed_list(entries: usize) -> Vec<u8> {
+    let disallowed_at = UNIX_EPOCH + Duration::from_secs(1_400_000_000);
+    let mut builder = CtlBuilder::new()
+        .usage(ListUsage::DisallowedList)
+        .name(DISALLOWED_CERT_LIST_NAME)
+        .sequence_number(SequenceNumber::new(&[0x01, 0xd9]).unwrap());
+
+    for i in 0..entries {
+        let mut id = vec![0x5a; 20];
+        id[..8].copy_from_slice(&(i as u64).to_be_bytes());
+        builder = builder.add_subject_id(
+            SubjectId::from(id),
+            [SubjectAttribute::DisallowedFiletime(disallowed_at)],
+        );
+    }
+
+    let pem = builder.build().unwrap().to_pem(LineEnding::LF).unwrap();
+    der::pem::decode_vec(pem.as_bytes()).unwrap().1
+}